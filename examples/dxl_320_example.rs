@@ -30,10 +30,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         // let temp = xl320::sync_read_present_temperature(&io, serial_port.as_mut(), &[30, 31])?;
         // println!("{:?}", temp);
 
-        xl320::sync_write_goal_position(&io, serial_port.as_mut(), &[30, 31], &[0, 512])?;
+        xl320::sync_write_raw_goal_position(&io, serial_port.as_mut(), &[30, 31], &[0, 512])?;
         thread::sleep(Duration::from_millis(500));
 
-        xl320::sync_write_goal_position(&io, serial_port.as_mut(), &[31, 30], &[0, 512])?;
+        xl320::sync_write_raw_goal_position(&io, serial_port.as_mut(), &[31, 30], &[0, 512])?;
         thread::sleep(Duration::from_millis(500));
     }
 }
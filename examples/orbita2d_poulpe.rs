@@ -60,7 +60,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("Ping {:?}: {:?}", id, x);
     thread::sleep(Duration::from_millis(100));
 
-    let _ = orbita2d_poulpe::write_torque_enable(
+    orbita2d_poulpe::write_torque_enable(
         &io,
         serial_port.as_mut(),
         id,
@@ -150,7 +150,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     );
 
     println!("STOP");
-    let _ = orbita2d_poulpe::write_torque_enable(
+    orbita2d_poulpe::write_torque_enable(
         &io,
         serial_port.as_mut(),
         id,
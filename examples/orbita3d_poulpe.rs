@@ -59,7 +59,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     let x = io.ping(serial_port.as_mut(), id);
     println!("Ping {:?}: {:?}", id, x);
 
-    let _ = orbita3d_poulpe::write_torque_enable(
+    orbita3d_poulpe::write_torque_enable(
         &io,
         serial_port.as_mut(),
         id,
@@ -209,7 +209,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     );
 
     println!("STOP");
-    let _ = orbita3d_poulpe::write_torque_enable(
+    orbita3d_poulpe::write_torque_enable(
         &io,
         serial_port.as_mut(),
         id,
@@ -0,0 +1,68 @@
+use clap::{Parser, ValueEnum};
+use std::{error::Error, path::PathBuf, time::Duration};
+
+use rustypot::servo::bus_config::{self, BusConfig};
+use rustypot::DynamixelProtocolHandler;
+
+/// Apply a declarative bus configuration (JSON/YAML/TOML file), writing only what differs
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "/dev/ttyUSB0")]
+    serialport: String,
+    /// baud
+    #[arg(short, long, default_value_t = 1_000_000)]
+    baudrate: u32,
+
+    #[arg(short, long, value_enum, default_value_t = ProtocolVersion::V1)]
+    protocol: ProtocolVersion,
+
+    /// Configuration file
+    #[arg(short, long)]
+    config: PathBuf,
+
+    /// Only show the differences, without writing anything
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum ProtocolVersion {
+    V1,
+    V2,
+    Herkulex,
+    Modbus,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let config = BusConfig::load(&args.config)?;
+
+    let mut serial_port = serialport::new(args.serialport, args.baudrate)
+        .timeout(Duration::from_millis(10))
+        .open()?;
+
+    let dph = match args.protocol {
+        ProtocolVersion::V1 => DynamixelProtocolHandler::v1(),
+        ProtocolVersion::V2 => DynamixelProtocolHandler::v2(),
+        ProtocolVersion::Herkulex => DynamixelProtocolHandler::herkulex(),
+        ProtocolVersion::Modbus => DynamixelProtocolHandler::modbus(),
+    };
+
+    let diffs = match args.dry_run {
+        true => bus_config::diff(&dph, serial_port.as_mut(), &config)?,
+        false => bus_config::apply(&dph, serial_port.as_mut(), &config)?,
+    };
+    for servo in diffs.iter() {
+        println!("{servo}");
+    }
+
+    let nb_changes: usize = diffs.iter().map(|s| s.changes.len()).sum();
+    match args.dry_run {
+        true => println!("{nb_changes} changes to apply"),
+        false => println!("{nb_changes} changes applied"),
+    }
+
+    Ok(())
+}
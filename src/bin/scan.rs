@@ -2,7 +2,7 @@ use clap::{Parser, ValueEnum};
 use std::{error::Error, time::Duration};
 
 use rustypot::servo::ServoKind;
-use rustypot::DynamixelProtocolHandler;
+use rustypot::{DynamixelProtocolHandler, PingStatus};
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -20,6 +20,8 @@ struct Args {
 enum ProtocolVersion {
     V1,
     V2,
+    Herkulex,
+    Modbus,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -38,24 +40,31 @@ fn main() -> Result<(), Box<dyn Error>> {
     let dph = match protocol {
         ProtocolVersion::V1 => DynamixelProtocolHandler::v1(),
         ProtocolVersion::V2 => DynamixelProtocolHandler::v2(),
+        ProtocolVersion::Herkulex => DynamixelProtocolHandler::herkulex(),
+        ProtocolVersion::Modbus => DynamixelProtocolHandler::modbus(),
     };
 
-    for id in 1..253 {
-        match dph.ping(serial_port.as_mut(), id) {
-            Ok(present) => {
-                if present {
-                    let model = dph.read(serial_port.as_mut(), id, 0, 2).unwrap();
-                    let model = u16::from_le_bytes([model[0], model[1]]);
-                    let model = ServoKind::try_from(model);
-                    match model {
-                        Ok(m) => println!("Found motor with id {id} and model: {m:?}"),
-                        Err(e) => println!("Found motor with id {id} with {e:?}"),
-                    }
-                }
+    let mut duplicates = Vec::new();
+    for id in 1..=dph.max_id() {
+        match dph.ping_status(serial_port.as_mut(), id) {
+            Ok(PingStatus::Present) => match ServoKind::identify(&dph, serial_port.as_mut(), id) {
+                Ok(m) => println!("Found motor with id {id} and model: {m:?}"),
+                Err(e) => println!("Found motor with id {id} with {e}"),
+            },
+            Ok(PingStatus::Collision) => {
+                println!("Found several motors sharing the id {id}");
+                duplicates.push(id);
             }
+            Ok(PingStatus::Absent) => {}
             Err(e) => eprintln!("Error: {e}"),
         };
     }
 
+    if !duplicates.is_empty() {
+        eprintln!(
+            "Duplicate ids found: {duplicates:?}, connect these motors one at a time to give them unique ids"
+        );
+    }
+
     Ok(())
 }
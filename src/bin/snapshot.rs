@@ -0,0 +1,95 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use std::{error::Error, path::PathBuf, time::Duration};
+
+use rustypot::servo::snapshot::{self, RestoreScope, Snapshot};
+use rustypot::DynamixelProtocolHandler;
+
+/// Dump a servo control table to a file, or restore it from a file
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[arg(short, long, default_value = "/dev/ttyUSB0")]
+    serialport: String,
+    /// baud
+    #[arg(short, long, default_value_t = 1_000_000)]
+    baudrate: u32,
+
+    #[arg(short, long, value_enum, default_value_t = ProtocolVersion::V1)]
+    protocol: ProtocolVersion,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Read all registers of a servo into a JSON/YAML/TOML file
+    Dump {
+        #[arg(short, long)]
+        id: u8,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Write a snapshot file back to a servo (of the same model)
+    Restore {
+        #[arg(short, long)]
+        id: u8,
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Also restore the RAM registers (goals, gains, torque): the servo may move
+        #[arg(long)]
+        ram: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+enum ProtocolVersion {
+    V1,
+    V2,
+    Herkulex,
+    Modbus,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+
+    let mut serial_port = serialport::new(args.serialport, args.baudrate)
+        .timeout(Duration::from_millis(10))
+        .open()?;
+
+    let dph = match args.protocol {
+        ProtocolVersion::V1 => DynamixelProtocolHandler::v1(),
+        ProtocolVersion::V2 => DynamixelProtocolHandler::v2(),
+        ProtocolVersion::Herkulex => DynamixelProtocolHandler::herkulex(),
+        ProtocolVersion::Modbus => DynamixelProtocolHandler::modbus(),
+    };
+
+    match args.command {
+        Command::Dump { id, output } => {
+            let snap = snapshot::dump(&dph, serial_port.as_mut(), id)?;
+            snap.save(&output)?;
+            println!(
+                "Dumped {} registers of motor {id} ({}) to {output:?}",
+                snap.registers.len(),
+                snap.model
+            );
+        }
+        Command::Restore { id, input, ram } => {
+            let snap = Snapshot::load(&input)?;
+            let scope = match ram {
+                true => RestoreScope::EepromAndRam,
+                false => RestoreScope::Eeprom,
+            };
+            let changes = snapshot::restore(&dph, serial_port.as_mut(), id, &snap, scope)?;
+            for change in changes.iter() {
+                println!("{change}");
+            }
+            println!(
+                "Restored {input:?} to motor {id} ({} changes)",
+                changes.len()
+            );
+        }
+    }
+
+    Ok(())
+}
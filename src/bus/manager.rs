@@ -0,0 +1,582 @@
+//! Several serial buses driven in parallel.
+//!
+//! A [BusManager] owns one IO worker thread per bus (with its port and protocol handler). Servos are addressed by
+//! joint name, or by id when no other bus uses the same id, and each operation is routed to the worker of their bus.
+//! Group reads and writes are split per bus, run on all the workers at once and merged back in the requested order;
+//! a failing bus does not prevent the others from answering (see [GroupResult]).
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{bus::BusManager, servo::dynamixel::xl330, DynamixelProtocolHandler};
+//! use std::time::Duration;
+//!
+//! let mut manager = BusManager::new();
+//! for (bus, path) in [("left_arm", "/dev/ttyUSB0"), ("right_arm", "/dev/ttyUSB1")] {
+//!     let port = serialport::new(path, 1_000_000)
+//!         .timeout(Duration::from_millis(10))
+//!         .open()
+//!         .unwrap();
+//!     manager.add_bus(bus, DynamixelProtocolHandler::v2(), port).unwrap();
+//! }
+//! manager.add_joint("l_shoulder", "left_arm", 1).unwrap();
+//! manager.add_joint("l_elbow", "left_arm", 2).unwrap();
+//! manager.add_joint("r_shoulder", "right_arm", 1).unwrap();
+//! manager.add_joint("r_elbow", "right_arm", 2).unwrap();
+//!
+//! let joints = ["l_shoulder", "l_elbow", "r_shoulder", "r_elbow"];
+//! let positions = manager
+//!     .sync_read_with(&joints, xl330::sync_read_present_position)
+//!     .unwrap();
+//! for (bus, error) in &positions.errors {
+//!     eprintln!("{bus} failed: {error}");
+//! }
+//! println!("{:?}", positions.values);
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    sync::{mpsc, Arc},
+    thread::JoinHandle,
+};
+
+use serialport::SerialPort;
+
+use crate::DynamixelProtocolHandler;
+
+type Job = Box<dyn FnOnce(&DynamixelProtocolHandler, &mut dyn SerialPort) + Send>;
+
+/// IO thread running the jobs of one bus, in order
+struct Worker {
+    name: String,
+    jobs: Option<mpsc::Sender<Job>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    fn spawn(
+        name: &str,
+        dph: DynamixelProtocolHandler,
+        mut serial_port: Box<dyn SerialPort>,
+    ) -> crate::Result<Self> {
+        let (jobs, rx) = mpsc::channel::<Job>();
+        let thread = std::thread::Builder::new()
+            .name(format!("rustypot-{name}"))
+            .spawn(move || {
+                for job in rx {
+                    job(&dph, serial_port.as_mut());
+                }
+            })?;
+
+        Ok(Worker {
+            name: name.to_string(),
+            jobs: Some(jobs),
+            thread: Some(thread),
+        })
+    }
+
+    /// Runs `f` on the worker, the result is received on the returned channel
+    fn submit<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&DynamixelProtocolHandler, &mut dyn SerialPort) -> crate::Result<T>
+            + Send
+            + 'static,
+    ) -> mpsc::Receiver<Result<T, String>> {
+        let (tx, rx) = mpsc::channel();
+        let job: Job = Box::new(move |dph, serial_port| {
+            // Errors are not Send, only their message leaves the worker
+            let _ = tx.send(f(dph, serial_port).map_err(|e| e.to_string()));
+        });
+        if let Some(jobs) = &self.jobs {
+            // If the worker is gone, the result channel is closed and reported by `collect`
+            let _ = jobs.send(job);
+        }
+        rx
+    }
+
+    fn collect<T>(&self, rx: mpsc::Receiver<Result<T, String>>) -> Result<T, String> {
+        rx.recv()
+            .unwrap_or_else(|_| Err(format!("IO worker of bus {} stopped", self.name)))
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.jobs.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Servo addressed by a [BusManager]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target<'a> {
+    /// Servo id, when a single bus uses it
+    Id(u8),
+    /// Joint name given to [BusManager::add_joint]
+    Joint(&'a str),
+}
+
+impl From<u8> for Target<'_> {
+    fn from(id: u8) -> Self {
+        Target::Id(id)
+    }
+}
+
+impl<'a> From<&'a str> for Target<'a> {
+    fn from(joint: &'a str) -> Self {
+        Target::Joint(joint)
+    }
+}
+
+/// Invalid bus or joint declaration, or servo that cannot be routed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoutingError {
+    DuplicateBus(String),
+    DuplicateJoint(String),
+    UnknownBus(String),
+    UnknownJoint(String),
+    UnknownId(u8),
+    /// The id is used on several buses, the servos need joint names
+    AmbiguousId(u8),
+    /// Number of values different from the number of targets (targets, values)
+    ValueCount(usize, usize),
+}
+
+impl fmt::Display for RoutingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RoutingError::DuplicateBus(bus) => write!(f, "Bus {bus} already added"),
+            RoutingError::DuplicateJoint(joint) => write!(f, "Joint {joint} already added"),
+            RoutingError::UnknownBus(bus) => write!(f, "Unknown bus {bus}"),
+            RoutingError::UnknownJoint(joint) => write!(f, "Unknown joint {joint}"),
+            RoutingError::UnknownId(id) => write!(f, "No bus has a servo with id {id}"),
+            RoutingError::AmbiguousId(id) => {
+                write!(
+                    f,
+                    "Several buses have a servo with id {id}, use joint names"
+                )
+            }
+            RoutingError::ValueCount(targets, values) => {
+                write!(f, "{values} values given for {targets} servos")
+            }
+        }
+    }
+}
+
+impl Error for RoutingError {}
+
+/// Merged result of a group operation over several buses
+#[derive(Debug)]
+pub struct GroupResult<T> {
+    /// Value for each target, in the requested order (`None` for the servos of failed buses)
+    pub values: Vec<Option<T>>,
+    /// Error message of each failed bus
+    pub errors: BTreeMap<String, String>,
+}
+
+impl<T> GroupResult<T> {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// All the values, or the errors of the failed buses
+    pub fn into_result(self) -> Result<Vec<T>, GroupError> {
+        match self.errors.is_empty() {
+            true => Ok(self.values.into_iter().flatten().collect()),
+            false => Err(GroupError(self.errors)),
+        }
+    }
+}
+
+/// Errors of the failed buses of a group operation (bus name, error message)
+#[derive(Debug, Clone)]
+pub struct GroupError(pub BTreeMap<String, String>);
+
+impl fmt::Display for GroupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let errors: Vec<String> = self
+            .0
+            .iter()
+            .map(|(bus, error)| format!("{bus}: {error}"))
+            .collect();
+        write!(f, "Bus errors ({})", errors.join(", "))
+    }
+}
+
+impl Error for GroupError {}
+
+/// Serial buses with one IO worker each, see the [module](self) documentation
+#[derive(Default)]
+pub struct BusManager {
+    workers: Vec<Worker>,
+    joints: HashMap<String, (usize, u8)>,
+    /// Bus of each id, `None` if several buses use it
+    ids: HashMap<u8, Option<usize>>,
+}
+
+impl BusManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a bus, starting its IO worker
+    pub fn add_bus(
+        &mut self,
+        name: &str,
+        dph: DynamixelProtocolHandler,
+        serial_port: Box<dyn SerialPort>,
+    ) -> crate::Result<()> {
+        if self.bus_index(name).is_some() {
+            return Err(Box::new(RoutingError::DuplicateBus(name.to_string())));
+        }
+        self.workers.push(Worker::spawn(name, dph, serial_port)?);
+        Ok(())
+    }
+
+    /// Declares the servo `id` of `bus`, under the name `joint`
+    pub fn add_joint(&mut self, joint: &str, bus: &str, id: u8) -> crate::Result<()> {
+        let bus = self
+            .bus_index(bus)
+            .ok_or_else(|| RoutingError::UnknownBus(bus.to_string()))?;
+        if self.joints.contains_key(joint) {
+            return Err(Box::new(RoutingError::DuplicateJoint(joint.to_string())));
+        }
+        self.joints.insert(joint.to_string(), (bus, id));
+        self.ids
+            .entry(id)
+            .and_modify(|b| {
+                if *b != Some(bus) {
+                    *b = None
+                }
+            })
+            .or_insert(Some(bus));
+        Ok(())
+    }
+
+    /// Declares servos by id only (reachable by id as long as no other bus uses it)
+    pub fn add_ids(&mut self, bus: &str, ids: &[u8]) -> crate::Result<()> {
+        for &id in ids {
+            self.add_joint(&format!("{bus}/{id}"), bus, id)?;
+        }
+        Ok(())
+    }
+
+    pub fn buses(&self) -> Vec<&str> {
+        self.workers.iter().map(|w| w.name.as_str()).collect()
+    }
+
+    /// Bus name and id of a servo
+    pub fn route<'a>(&self, target: impl Into<Target<'a>>) -> crate::Result<(&str, u8)> {
+        let (bus, id) = self.resolve(target.into())?;
+        Ok((&self.workers[bus].name, id))
+    }
+
+    fn bus_index(&self, name: &str) -> Option<usize> {
+        self.workers.iter().position(|w| w.name == name)
+    }
+
+    fn resolve(&self, target: Target) -> Result<(usize, u8), RoutingError> {
+        match target {
+            Target::Joint(joint) => self
+                .joints
+                .get(joint)
+                .copied()
+                .ok_or_else(|| RoutingError::UnknownJoint(joint.to_string())),
+            Target::Id(id) => match self.ids.get(&id) {
+                Some(Some(bus)) => Ok((*bus, id)),
+                Some(None) => Err(RoutingError::AmbiguousId(id)),
+                None => Err(RoutingError::UnknownId(id)),
+            },
+        }
+    }
+
+    /// Runs `f` with the id of a servo on the worker of its bus
+    pub fn execute<'a, T: Send + 'static>(
+        &self,
+        target: impl Into<Target<'a>>,
+        f: impl FnOnce(&DynamixelProtocolHandler, &mut dyn SerialPort, u8) -> crate::Result<T>
+            + Send
+            + 'static,
+    ) -> crate::Result<T> {
+        let (bus, id) = self.resolve(target.into())?;
+        let worker = &self.workers[bus];
+        let rx = worker.submit(move |dph, serial_port| f(dph, serial_port, id));
+        Ok(worker.collect(rx)?)
+    }
+
+    /// Reads raw register bytes of a servo
+    pub fn read<'a>(
+        &self,
+        target: impl Into<Target<'a>>,
+        addr: u16,
+        length: u16,
+    ) -> crate::Result<Vec<u8>> {
+        self.execute(target, move |dph, serial_port, id| {
+            dph.read(serial_port, id, addr, length)
+        })
+    }
+
+    /// Writes raw register bytes of a servo
+    pub fn write<'a>(
+        &self,
+        target: impl Into<Target<'a>>,
+        addr: u16,
+        data: &[u8],
+    ) -> crate::Result<()> {
+        let data = data.to_vec();
+        self.execute(target, move |dph, serial_port, id| {
+            dph.write(serial_port, id, addr, &data)
+        })
+    }
+
+    /// Groups the targets per bus: ids and positions in `targets` of each bus
+    #[allow(clippy::type_complexity)]
+    fn split<'a, S: Into<Target<'a>> + Copy>(
+        &self,
+        targets: &[S],
+    ) -> Result<BTreeMap<usize, (Vec<u8>, Vec<usize>)>, RoutingError> {
+        let mut per_bus: BTreeMap<usize, (Vec<u8>, Vec<usize>)> = BTreeMap::new();
+        for (i, &target) in targets.iter().enumerate() {
+            let (bus, id) = self.resolve(target.into())?;
+            let (ids, positions) = per_bus.entry(bus).or_default();
+            ids.push(id);
+            positions.push(i);
+        }
+        Ok(per_bus)
+    }
+
+    /// Reads the servos of each bus with `f` (e.g. a generated `sync_read_*` function), all the buses in parallel
+    pub fn sync_read_with<'a, S, T, F>(&self, targets: &[S], f: F) -> crate::Result<GroupResult<T>>
+    where
+        S: Into<Target<'a>> + Copy,
+        T: Send + 'static,
+        F: Fn(&DynamixelProtocolHandler, &mut dyn SerialPort, &[u8]) -> crate::Result<Vec<T>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let f = Arc::new(f);
+        let pending: Vec<_> = self
+            .split(targets)?
+            .into_iter()
+            .map(|(bus, (ids, positions))| {
+                let f = f.clone();
+                let n = ids.len();
+                let rx = self.workers[bus].submit(move |dph, serial_port| {
+                    let values = f(dph, serial_port, &ids)?;
+                    match values.len() == n {
+                        true => Ok(values),
+                        false => Err(format!("{} values read for {n} servos", values.len()).into()),
+                    }
+                });
+                (bus, positions, rx)
+            })
+            .collect();
+
+        let mut result = GroupResult {
+            values: targets.iter().map(|_| None).collect(),
+            errors: BTreeMap::new(),
+        };
+        for (bus, positions, rx) in pending {
+            let worker = &self.workers[bus];
+            match worker.collect(rx) {
+                Ok(values) => {
+                    for (i, value) in positions.into_iter().zip(values) {
+                        result.values[i] = Some(value);
+                    }
+                }
+                Err(e) => {
+                    result.errors.insert(worker.name.clone(), e);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Writes the values to the servos of each bus with `f` (e.g. a generated `sync_write_*` function), all the buses
+    /// in parallel
+    pub fn sync_write_with<'a, S, V, F>(
+        &self,
+        targets: &[S],
+        values: &[V],
+        f: F,
+    ) -> crate::Result<GroupResult<()>>
+    where
+        S: Into<Target<'a>> + Copy,
+        V: Clone + Send + 'static,
+        F: Fn(&DynamixelProtocolHandler, &mut dyn SerialPort, &[u8], &[V]) -> crate::Result<()>
+            + Send
+            + Sync
+            + 'static,
+    {
+        if targets.len() != values.len() {
+            return Err(Box::new(RoutingError::ValueCount(
+                targets.len(),
+                values.len(),
+            )));
+        }
+
+        let f = Arc::new(f);
+        let pending: Vec<_> = self
+            .split(targets)?
+            .into_iter()
+            .map(|(bus, (ids, positions))| {
+                let f = f.clone();
+                let bus_values: Vec<V> = positions.iter().map(|&i| values[i].clone()).collect();
+                let rx = self.workers[bus]
+                    .submit(move |dph, serial_port| f(dph, serial_port, &ids, &bus_values));
+                (bus, positions, rx)
+            })
+            .collect();
+
+        let mut result = GroupResult {
+            values: targets.iter().map(|_| None).collect(),
+            errors: BTreeMap::new(),
+        };
+        for (bus, positions, rx) in pending {
+            let worker = &self.workers[bus];
+            match worker.collect(rx) {
+                Ok(()) => {
+                    for i in positions {
+                        result.values[i] = Some(());
+                    }
+                }
+                Err(e) => {
+                    result.errors.insert(worker.name.clone(), e);
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// Reads raw register bytes of the servos, all the buses in parallel
+    pub fn sync_read<'a, S: Into<Target<'a>> + Copy>(
+        &self,
+        targets: &[S],
+        addr: u16,
+        length: u16,
+    ) -> crate::Result<GroupResult<Vec<u8>>> {
+        self.sync_read_with(targets, move |dph, serial_port, ids| {
+            dph.sync_read(serial_port, ids, addr, length)
+        })
+    }
+
+    /// Writes raw register bytes to the servos, all the buses in parallel
+    pub fn sync_write<'a, S: Into<Target<'a>> + Copy>(
+        &self,
+        targets: &[S],
+        addr: u16,
+        data: &[Vec<u8>],
+    ) -> crate::Result<GroupResult<()>> {
+        self.sync_write_with(targets, data, move |dph, serial_port, ids, data| {
+            dph.sync_write(serial_port, ids, addr, data)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV2, MockSerialPort},
+        servo::dynamixel::xl330,
+    };
+
+    fn limb(ids: &[u8]) -> Box<dyn SerialPort> {
+        let servos = ids
+            .iter()
+            .fold(FakeServosV2::default(), |s, &id| s.with_servo(id, 1200));
+        Box::new(MockSerialPort::new(servos))
+    }
+
+    fn humanoid() -> BusManager {
+        let mut manager = BusManager::new();
+        manager
+            .add_bus("left_arm", DynamixelProtocolHandler::v2(), limb(&[1, 2]))
+            .unwrap();
+        manager
+            .add_bus("right_arm", DynamixelProtocolHandler::v2(), limb(&[1, 2]))
+            .unwrap();
+        manager
+            .add_bus("head", DynamixelProtocolHandler::v2(), limb(&[10]))
+            .unwrap();
+        for (joint, bus, id) in [
+            ("l_shoulder", "left_arm", 1),
+            ("l_elbow", "left_arm", 2),
+            ("r_shoulder", "right_arm", 1),
+            ("r_elbow", "right_arm", 2),
+        ] {
+            manager.add_joint(joint, bus, id).unwrap();
+        }
+        manager.add_ids("head", &[10]).unwrap();
+        manager
+    }
+
+    #[test]
+    fn routing() {
+        let manager = humanoid();
+        assert_eq!(manager.route("r_elbow").unwrap(), ("right_arm", 2));
+        assert_eq!(manager.route(10).unwrap(), ("head", 10));
+
+        let err = manager.route(1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<RoutingError>(),
+            Some(&RoutingError::AmbiguousId(1))
+        );
+        assert!(manager.route("tail").is_err());
+
+        let mut manager = manager;
+        assert!(manager.add_joint("l_elbow", "head", 3).is_err());
+        assert!(manager.add_joint("neck", "body", 3).is_err());
+    }
+
+    #[test]
+    fn group_operations() {
+        let manager = humanoid();
+        let joints = ["r_elbow", "l_shoulder", "r_shoulder", "l_elbow"];
+
+        let written = manager
+            .sync_write_with(&joints, &[4, 1, 3, 2], xl330::sync_write_raw_goal_position)
+            .unwrap();
+        assert!(written.is_ok());
+        assert!(manager
+            .sync_write_with(&joints, &[0], xl330::sync_write_raw_goal_position)
+            .is_err());
+        manager.write(10, 116, &5_i32.to_le_bytes()).unwrap();
+
+        let positions = manager
+            .sync_read_with(&joints, xl330::sync_read_raw_goal_position)
+            .unwrap();
+        assert_eq!(positions.into_result().unwrap(), [4, 1, 3, 2]);
+        assert_eq!(
+            manager.read("head/10", 116, 4).unwrap(),
+            5_i32.to_le_bytes()
+        );
+
+        let raw = manager.sync_read(&["l_elbow", "head/10"], 116, 1).unwrap();
+        assert_eq!(raw.into_result().unwrap(), [vec![2], vec![5]]);
+    }
+
+    #[test]
+    fn per_bus_errors() {
+        let mut manager = humanoid();
+        // Declared on the left arm, but not connected
+        manager.add_joint("l_wrist", "left_arm", 3).unwrap();
+
+        let positions = manager
+            .sync_read_with(
+                &["l_wrist", "r_elbow", "head/10"],
+                xl330::sync_read_raw_goal_position,
+            )
+            .unwrap();
+        assert!(!positions.is_ok());
+        assert_eq!(positions.values[0], None);
+        assert_eq!(positions.values[1], Some(0));
+        assert_eq!(positions.values[2], Some(0));
+        assert_eq!(positions.errors.keys().collect::<Vec<_>>(), ["left_arm"]);
+        assert!(positions.into_result().is_err());
+    }
+}
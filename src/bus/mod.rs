@@ -0,0 +1,150 @@
+//! Serial buses shared by several controllers.
+//!
+//! Controllers talk to their servos through a [Transport]: the serial port they own (`Box<dyn SerialPort>`), or a
+//! [SharedBus] when servos of several models share the same RS485/TTL chain. Each transaction (an instruction and its
+//! status replies) holds the bus for its whole duration, so controllers used from different threads never interleave
+//! their packets. Each controller keeps its own protocol settings (version, post delay).
+//!
+//! To drive several buses in parallel, see [BusManager].
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{
+//!     bus::SharedBus,
+//!     servo::{dynamixel::xl330::Xl330Controller, orbita::orbita2d_poulpe::Orbita2dPoulpeController},
+//! };
+//! use std::time::Duration;
+//!
+//! let bus = SharedBus::open("/dev/ttyUSB0", 1_000_000, Duration::from_millis(10)).unwrap();
+//!
+//! let mut gripper = Xl330Controller::new().with_bus(bus.clone()).with_protocol_v2();
+//! let mut neck = Orbita2dPoulpeController::new().with_bus(bus).with_protocol_v1();
+//!
+//! std::thread::spawn(move || loop {
+//!     let _ = gripper.read_present_position(1);
+//! });
+//! println!("{:?}", neck.read_current_position(2));
+//! ```
+
+use std::{
+    ops::{Deref, DerefMut},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+use serialport::SerialPort;
+
+mod manager;
+pub use manager::{BusManager, GroupError, GroupResult, RoutingError, Target};
+
+/// Access to the serial port of a controller, held for the duration of a transaction
+pub trait Transport: Send {
+    fn lock(&mut self) -> TransportGuard<'_>;
+}
+
+/// Serial port locked by a [Transport]
+pub enum TransportGuard<'a> {
+    Owned(&'a mut Box<dyn SerialPort>),
+    Shared(MutexGuard<'a, Box<dyn SerialPort>>),
+}
+
+impl Deref for TransportGuard<'_> {
+    type Target = dyn SerialPort;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            TransportGuard::Owned(port) => port.as_ref(),
+            TransportGuard::Shared(port) => port.as_ref(),
+        }
+    }
+}
+
+impl DerefMut for TransportGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            TransportGuard::Owned(port) => port.as_mut(),
+            TransportGuard::Shared(port) => port.as_mut(),
+        }
+    }
+}
+
+impl Transport for Box<dyn SerialPort> {
+    fn lock(&mut self) -> TransportGuard<'_> {
+        TransportGuard::Owned(self)
+    }
+}
+
+/// Serial port shared by several controllers (cheap to clone, all clones use the same port)
+#[derive(Clone)]
+pub struct SharedBus(Arc<Mutex<Box<dyn SerialPort>>>);
+
+impl SharedBus {
+    pub fn new(serial_port: Box<dyn SerialPort>) -> Self {
+        SharedBus(Arc::new(Mutex::new(serial_port)))
+    }
+
+    /// Opens the serial port at `path`
+    pub fn open(path: &str, baudrate: u32, timeout: Duration) -> crate::Result<Self> {
+        let serial_port = serialport::new(path, baudrate).timeout(timeout).open()?;
+        Ok(SharedBus::new(serial_port))
+    }
+
+    /// Locks the port, e.g. to use it with [crate::DynamixelProtocolHandler] or the generated free functions
+    pub fn port(&self) -> MutexGuard<'_, Box<dyn SerialPort>> {
+        // A controller panicking mid-transaction leaves at worst stale bytes, flushed by the next instruction
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Transport for SharedBus {
+    fn lock(&mut self) -> TransportGuard<'_> {
+        TransportGuard::Shared(self.port())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV2, MockSerialPort},
+        servo::dynamixel::{xl330::Xl330Controller, xl430::Xl430Controller},
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn controllers_sharing_a_bus() {
+        let bus = SharedBus::new(Box::new(MockSerialPort::new(
+            FakeServosV2::default()
+                .with_servo(1, 1200)
+                .with_servo(2, 1060),
+        )));
+
+        let mut xl330 = Xl330Controller::new()
+            .with_bus(bus.clone())
+            .with_protocol_v2();
+        let mut xl430 = Xl430Controller::new()
+            .with_protocol_v2()
+            .with_bus(bus.clone());
+
+        let threads = [
+            std::thread::spawn(move || {
+                for i in 0..200 {
+                    xl330.write_raw_goal_position(1, i).unwrap();
+                    assert_eq!(xl330.read_raw_goal_position(1).unwrap(), [i]);
+                }
+            }),
+            std::thread::spawn(move || {
+                for i in 0..200 {
+                    xl430.write_goal_position(2, 4095 - i).unwrap();
+                    assert_eq!(xl430.read_goal_position(2).unwrap(), [4095 - i]);
+                }
+            }),
+        ];
+        for t in threads {
+            t.join().unwrap();
+        }
+
+        let dph = DynamixelProtocolHandler::v2();
+        assert!(dph.ping(bus.port().as_mut(), 1).unwrap());
+    }
+}
@@ -0,0 +1,369 @@
+//! Per-call deadlines: the replies of each call are awaited for a fixed time, or a time estimated from the
+//! baudrate, the bytes exchanged and the return delay of the servos when enabled. Otherwise each read waits for
+//! the port timeout.
+
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use super::{DynamixelProtocolHandler, ProtocolKind};
+
+/// Default delay of the servos before sending their status packet (250 x 2 µs, the Dynamixel default)
+pub const DEFAULT_RETURN_DELAY: Duration = Duration::from_micros(500);
+
+/// Default time added to the estimated deadlines, absorbing the USB and OS latencies
+///
+/// USB serial adapters hold the received bytes up to their latency timer before passing them to the host:
+/// 16 ms by default for the FTDI based ones (USB2Dynamixel, U2D2), which is longer than this margin. Lower the
+/// timer (e.g. to 1 ms with `/sys/bus/usb-serial/devices/ttyUSB0/latency_timer` on Linux) or raise the margin with
+/// [DynamixelProtocolHandler::with_deadline_margin] before enabling the estimated deadlines.
+pub const DEFAULT_DEADLINE_MARGIN: Duration = Duration::from_millis(2);
+
+/// Shortest deadline of the reboot and factory reset instructions, answered once the servo is done
+pub const SLOW_INSTRUCTION_DEADLINE: Duration = Duration::from_millis(500);
+
+impl DynamixelProtocolHandler {
+    /// Handler using a fixed deadline for its calls instead of the estimated one.
+    ///
+    /// The copy shares the pending (and cancelled) transaction of this handler, so it can be used for a single call,
+    /// e.g. an EEPROM write.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use rustypot::DynamixelProtocolHandler;
+    /// use std::time::Duration;
+    ///
+    /// let mut serial_port = serialport::new("/dev/ttyACM0", 1_000_000)
+    ///     .timeout(Duration::from_millis(10))
+    ///     .open()
+    ///     .expect("Failed to open port");
+    ///
+    /// let dph = DynamixelProtocolHandler::v2();
+    ///
+    /// // Writing the EEPROM area of motor 1 takes longer than its reply
+    /// dph.deadline(Duration::from_millis(300))
+    ///     .write(serial_port.as_mut(), 1, 11, &[3])
+    ///     .expect("Communication error");
+    /// ```
+    pub fn deadline(&self, deadline: Duration) -> Self {
+        DynamixelProtocolHandler {
+            protocol: self.protocol.clone(),
+            pending: self.pending.clone(),
+            late_replies: self.late_replies.clone(),
+            deadline: Some(deadline),
+            ..*self
+        }
+    }
+
+    /// Wait for the replies until a deadline estimated for each call (see [DynamixelProtocolHandler::default_deadline])
+    /// instead of the port timeout.
+    ///
+    /// The estimate is much shorter than usual port timeouts (about 3 ms for a ping at 1 Mbps), which speeds up scans
+    /// but requires a low latency adapter, see [DEFAULT_DEADLINE_MARGIN].
+    pub fn with_estimated_deadlines(self) -> Self {
+        DynamixelProtocolHandler {
+            estimated_deadlines: true,
+            ..self
+        }
+    }
+
+    /// Set the delay of the servos before sending their status packet (their *return_delay_time*), used to estimate the deadlines.
+    pub fn with_return_delay(self, delay: Duration) -> Self {
+        DynamixelProtocolHandler {
+            return_delay: delay,
+            ..self
+        }
+    }
+
+    /// Set the time added to the estimated deadlines.
+    pub fn with_deadline_margin(self, margin: Duration) -> Self {
+        DynamixelProtocolHandler {
+            deadline_margin: margin,
+            ..self
+        }
+    }
+
+    /// Estimated deadline of a call sending `sent` parameter bytes and receiving `replies` status packets of `received` parameter bytes each.
+    ///
+    /// It covers the transmission of the packets at `baudrate` (10 bits per byte), the return delay of each reply and the margin.
+    pub fn default_deadline(
+        &self,
+        baudrate: u32,
+        sent: usize,
+        received: usize,
+        replies: usize,
+    ) -> Duration {
+        let bytes = self.protocol.packet_size(sent) + replies * self.protocol.packet_size(received);
+        let transmission = Duration::from_secs_f64((bytes * 10) as f64 / baudrate.max(1) as f64);
+        transmission + self.return_delay * replies as u32 + self.deadline_margin
+    }
+
+    /// Deadline of a call on `port`: the fixed one, or the estimated one if enabled (and the baudrate known).
+    ///
+    /// `None` when the call has no deadline, each read then waits for the port timeout.
+    pub(super) fn call_deadline(
+        &self,
+        port: &dyn SerialPort,
+        sent: usize,
+        received: usize,
+        replies: usize,
+    ) -> Option<Duration> {
+        match (self.deadline, self.estimated_deadlines, port.baud_rate()) {
+            (Some(deadline), _, _) => Some(deadline),
+            (None, true, Ok(baudrate)) => {
+                Some(self.default_deadline(baudrate, sent, received, replies))
+            }
+            _ => None,
+        }
+    }
+
+    /// Port waiting at most for the deadline of the call, if any
+    pub(super) fn expecting<'a>(
+        &self,
+        port: &'a mut dyn SerialPort,
+        sent: usize,
+        received: usize,
+        replies: usize,
+    ) -> DeadlinePort<'a> {
+        let deadline = self.call_deadline(port, sent, received, replies);
+        DeadlinePort::new(port, deadline.map(|d| Instant::now() + d))
+    }
+
+    /// Port waiting at most for the fixed deadline, or the port timeout but at least [SLOW_INSTRUCTION_DEADLINE]
+    pub(super) fn expecting_slow<'a>(&self, port: &'a mut dyn SerialPort) -> DeadlinePort<'a> {
+        let deadline = self
+            .deadline
+            .unwrap_or(port.timeout().max(SLOW_INSTRUCTION_DEADLINE));
+        DeadlinePort::new(port, Some(Instant::now() + deadline))
+    }
+}
+
+impl ProtocolKind {
+    /// Size of a packet carrying `params` parameter bytes, framing included
+    fn packet_size(&self, params: usize) -> usize {
+        let framing = match self {
+            ProtocolKind::V1(_) | ProtocolKind::Lx16a(_) | ProtocolKind::Modbus(_) => 6,
+            ProtocolKind::V2(_) | ProtocolKind::Herkulex(_) => 11,
+        };
+        framing + params
+    }
+}
+
+/// Serial port whose reads fail once the deadline is passed, restoring the port timeout when dropped
+///
+/// Without deadline, the reads are passed through and each one waits for the port timeout.
+pub(super) struct DeadlinePort<'a> {
+    port: &'a mut dyn SerialPort,
+    deadline: Option<Instant>,
+    timeout: Duration,
+}
+
+impl<'a> DeadlinePort<'a> {
+    pub(super) fn new(port: &'a mut dyn SerialPort, deadline: Option<Instant>) -> Self {
+        let timeout = port.timeout();
+        DeadlinePort {
+            port,
+            deadline,
+            timeout,
+        }
+    }
+}
+
+impl Drop for DeadlinePort<'_> {
+    fn drop(&mut self) {
+        let _ = self.port.set_timeout(self.timeout);
+    }
+}
+
+impl io::Read for DeadlinePort<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(deadline) = self.deadline else {
+            return self.port.read(buf);
+        };
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "Deadline exceeded"));
+        }
+        self.port.set_timeout(remaining)?;
+        self.port.read(buf)
+    }
+}
+
+impl io::Write for DeadlinePort<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl SerialPort for DeadlinePort<'_> {
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        self.port.baud_rate()
+    }
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        self.port.data_bits()
+    }
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        self.port.flow_control()
+    }
+    fn parity(&self) -> serialport::Result<Parity> {
+        self.port.parity()
+    }
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        self.port.stop_bits()
+    }
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.port.set_baud_rate(baud_rate)
+    }
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.port.set_data_bits(data_bits)
+    }
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.port.set_flow_control(flow_control)
+    }
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.port.set_parity(parity)
+    }
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.port.set_stop_bits(stop_bits)
+    }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        if self.deadline.is_none() {
+            self.port.set_timeout(timeout)?;
+        }
+        self.timeout = timeout;
+        Ok(())
+    }
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.port.write_request_to_send(level)
+    }
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.port.write_data_terminal_ready(level)
+    }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        self.port.read_clear_to_send()
+    }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        self.port.read_data_set_ready()
+    }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        self.port.read_ring_indicator()
+    }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        self.port.read_carrier_detect()
+    }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.port.bytes_to_read()
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        self.port.bytes_to_write()
+    }
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.port.clear(buffer_to_clear)
+    }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        self.port.try_clone()
+    }
+    fn set_break(&self) -> serialport::Result<()> {
+        self.port.set_break()
+    }
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.port.clear_break()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamixel_protocol::mock::{FakeServosV2, MockSerialPort};
+
+    #[test]
+    fn estimated_deadlines() {
+        let dph = DynamixelProtocolHandler::v2();
+
+        // Ping at 1 Mbps: about 2 ms
+        let ping = dph.default_deadline(1_000_000, 0, 3, 1);
+        assert!(ping > Duration::from_millis(2) && ping < Duration::from_millis(3));
+
+        // Only used once enabled
+        let port = MockSerialPort::new(FakeServosV2::default());
+        assert_eq!(dph.call_deadline(&port, 0, 3, 1), None);
+        let estimated = DynamixelProtocolHandler::v2().with_estimated_deadlines();
+        assert_eq!(estimated.call_deadline(&port, 0, 3, 1), Some(ping));
+
+        // Scaled with the number of replies and the baudrate
+        let one = dph.default_deadline(1_000_000, 5, 4, 1);
+        let twelve = dph.default_deadline(1_000_000, 16, 4, 12);
+        assert!(twelve > one + 11 * DEFAULT_RETURN_DELAY);
+        assert!(dph.default_deadline(57_600, 0, 3, 1) > ping + Duration::from_millis(3));
+
+        let slow = DynamixelProtocolHandler::v2()
+            .with_return_delay(Duration::from_millis(1))
+            .with_deadline_margin(Duration::from_millis(5));
+        assert!(slow.default_deadline(1_000_000, 0, 3, 1) > ping + Duration::from_millis(3));
+    }
+
+    #[test]
+    fn deadline_port() {
+        let mut port = MockSerialPort::new(FakeServosV2::default().with_servo(1, 1200));
+        let dph = DynamixelProtocolHandler::v2().with_estimated_deadlines();
+
+        // Replies are read within the deadline, missing ones time out, the port timeout is kept
+        assert_eq!(dph.read(&mut port, 1, 7, 1).unwrap(), [1]);
+        assert!(dph.read(&mut port, 2, 7, 1).is_err());
+        assert!(dph
+            .deadline(Duration::from_millis(300))
+            .reboot(&mut port, 1)
+            .unwrap());
+        assert_eq!(port.timeout(), Duration::from_millis(10));
+
+        // Past the deadline, even received bytes are not read
+        let transaction = dph.send_read(&mut port, 1, 7, 1).unwrap();
+        port.set_timeout(Duration::from_millis(20)).unwrap();
+        {
+            let mut late = DeadlinePort::new(&mut port, Some(Instant::now()));
+            let mut buf = [0u8; 1];
+            let err = io::Read::read(&mut late, &mut buf).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        }
+        assert_eq!(port.timeout(), Duration::from_millis(20));
+        assert_eq!(dph.wait(&mut port, transaction).unwrap(), [vec![1]]);
+    }
+
+    #[test]
+    fn port_timeout_per_read_by_default() {
+        let mut servos = FakeServosV2::default();
+        for id in 1..=6 {
+            servos = servos.with_servo(id, 1200);
+        }
+        // Each read takes 4 ms, the whole sync read much longer than the 10 ms timeout
+        let mut port = MockSerialPort::new(servos).with_latency(Duration::from_millis(4));
+        let ids = [1, 2, 3, 4, 5, 6];
+
+        let start = Instant::now();
+        let dph = DynamixelProtocolHandler::v2();
+        assert_eq!(dph.sync_read(&mut port, &ids, 7, 1).unwrap().len(), 6);
+        assert!(start.elapsed() > port.timeout());
+
+        let transaction = dph.send_sync_read(&mut port, &ids, 7, 1).unwrap();
+        assert_eq!(dph.wait(&mut port, transaction).unwrap().len(), 6);
+
+        // A deadline bounds the whole call
+        assert!(dph
+            .deadline(Duration::from_millis(10))
+            .sync_read(&mut port, &ids, 7, 1)
+            .is_err());
+    }
+}
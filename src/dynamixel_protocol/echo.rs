@@ -0,0 +1,241 @@
+//! Echo cancellation for half-duplex adapters looping every transmitted byte back on their RX line.
+
+use std::{io, time::Duration};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use super::{CommunicationErrorKind, DynamixelProtocolHandler};
+
+impl DynamixelProtocolHandler {
+    /// Consume the echo of each sent instruction before reading its reply.
+    ///
+    /// For RS485/TTL adapters (or one-wire boards) receiving their own transmission. The echo must match the sent bytes,
+    /// a difference means another device was driving the bus and is reported as [CommunicationErrorKind::BusCollision].
+    /// Generated controllers take such a handler with `with_protocol_handler`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use rustypot::{DynamixelProtocolHandler, servo::dynamixel::xl330};
+    /// use std::time::Duration;
+    ///
+    /// let mut serial_port = serialport::new("/dev/ttyUSB0", 1_000_000)
+    ///     .timeout(Duration::from_millis(10))
+    ///     .open()
+    ///     .expect("Failed to open port");
+    ///
+    /// let dph = DynamixelProtocolHandler::v2().with_echo_cancellation();
+    ///
+    /// let pos =
+    ///     xl330::read_present_position(&dph, serial_port.as_mut(), 1).expect("Communication error");
+    /// ```
+    pub fn with_echo_cancellation(self) -> Self {
+        DynamixelProtocolHandler {
+            echo_cancellation: true,
+            ..self
+        }
+    }
+
+    /// Port consuming the echo of the written bytes if echo cancellation is enabled
+    pub(super) fn echoing<'a>(&self, port: &'a mut dyn SerialPort) -> EchoPort<'a> {
+        EchoPort {
+            port,
+            enabled: self.echo_cancellation,
+        }
+    }
+}
+
+/// Serial port reading back and checking each written buffer when enabled
+pub(super) struct EchoPort<'a> {
+    port: &'a mut dyn SerialPort,
+    enabled: bool,
+}
+
+impl io::Read for EchoPort<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl io::Write for EchoPort<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.port.write(buf)?;
+        if self.enabled {
+            let mut echo = vec![0u8; n];
+            self.port.read_exact(&mut echo)?;
+            log::debug!("<<< echo {echo:?}");
+            if echo != buf[..n] {
+                return Err(io::Error::other(CommunicationErrorKind::BusCollision));
+            }
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl SerialPort for EchoPort<'_> {
+    fn name(&self) -> Option<String> {
+        self.port.name()
+    }
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        self.port.baud_rate()
+    }
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        self.port.data_bits()
+    }
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        self.port.flow_control()
+    }
+    fn parity(&self) -> serialport::Result<Parity> {
+        self.port.parity()
+    }
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        self.port.stop_bits()
+    }
+    fn timeout(&self) -> Duration {
+        self.port.timeout()
+    }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.port.set_baud_rate(baud_rate)
+    }
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.port.set_data_bits(data_bits)
+    }
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.port.set_flow_control(flow_control)
+    }
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.port.set_parity(parity)
+    }
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.port.set_stop_bits(stop_bits)
+    }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.port.set_timeout(timeout)
+    }
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.port.write_request_to_send(level)
+    }
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.port.write_data_terminal_ready(level)
+    }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        self.port.read_clear_to_send()
+    }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        self.port.read_data_set_ready()
+    }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        self.port.read_ring_indicator()
+    }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        self.port.read_carrier_detect()
+    }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.port.bytes_to_read()
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        self.port.bytes_to_write()
+    }
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.port.clear(buffer_to_clear)
+    }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        self.port.try_clone()
+    }
+    fn set_break(&self) -> serialport::Result<()> {
+        self.port.set_break()
+    }
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.port.clear_break()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamixel_protocol::mock::{FakeServosV2, MockSerialPort, Responder};
+
+    /// Adapter looping the written bytes back before the replies, corrupting them on a collision
+    struct Echo {
+        servos: FakeServosV2,
+        collision: bool,
+    }
+
+    impl Responder for Echo {
+        fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+            let mut echo = written.to_vec();
+            if self.collision {
+                echo[4] ^= 0x10;
+            }
+            echo.extend(self.servos.respond(written));
+            echo
+        }
+    }
+
+    fn bus(collision: bool) -> MockSerialPort {
+        MockSerialPort::new(Echo {
+            servos: FakeServosV2::default()
+                .with_servo(1, 1200)
+                .with_servo(2, 1200),
+            collision,
+        })
+    }
+
+    #[test]
+    fn echo_is_consumed() {
+        let mut port = bus(false);
+
+        // Without echo cancellation, our instruction is read as the reply
+        let dph = DynamixelProtocolHandler::v2();
+        assert!(dph.read(&mut port, 1, 7, 1).is_err());
+
+        let dph = DynamixelProtocolHandler::v2().with_echo_cancellation();
+        assert!(dph.ping(&mut port, 1).unwrap());
+        dph.write(&mut port, 2, 65, &[1]).unwrap();
+        assert_eq!(dph.read(&mut port, 2, 65, 1).unwrap(), [1]);
+        dph.sync_write(&mut port, &[1, 2], 65, &[vec![0], vec![0]])
+            .unwrap();
+        assert_eq!(
+            dph.sync_read(&mut port, &[1, 2], 65, 1).unwrap(),
+            [vec![0], vec![0]]
+        );
+
+        let transaction = dph.send_sync_read(&mut port, &[2, 1], 7, 1).unwrap();
+        assert_eq!(
+            dph.wait(&mut port, transaction).unwrap(),
+            [vec![2], vec![1]]
+        );
+    }
+
+    #[test]
+    fn controller_with_echo_cancellation() {
+        use crate::servo::dynamixel::xl330::Xl330Controller;
+
+        let mut c = Xl330Controller::new()
+            .with_serial_port(Box::new(bus(false)))
+            .with_protocol_handler(DynamixelProtocolHandler::v2().with_echo_cancellation());
+        assert!(c.ping(1).unwrap());
+        c.write_torque_enable(2, true).unwrap();
+        assert_eq!(c.read_torque_enable(2).unwrap(), [true]);
+    }
+
+    #[test]
+    fn collisions_are_reported() {
+        let mut port = bus(true);
+        let dph = DynamixelProtocolHandler::v2().with_echo_cancellation();
+
+        let e = dph.read(&mut port, 1, 7, 1).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<CommunicationErrorKind>(),
+            Some(CommunicationErrorKind::BusCollision)
+        ));
+        let e = dph.write(&mut port, 1, 65, &[1]).unwrap_err();
+        assert!(matches!(
+            e.downcast_ref::<CommunicationErrorKind>(),
+            Some(CommunicationErrorKind::BusCollision)
+        ));
+    }
+}
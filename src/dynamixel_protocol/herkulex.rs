@@ -0,0 +1,482 @@
+//! Dongbu Herkulex DRS-0101/0201 protocol.
+//!
+//! Packets are `FF FF size id command checksum1 checksum2 data...`, where `size` is the whole packet length.
+//! The servos have two register areas read and written with distinct commands: the RAM (addresses 0 to 255) and
+//! the EEP, mapped at [EEP_BANK] + its address so that both fit in a single control table.
+//!
+//! With the default ACK policy (1), only the read and STAT commands are answered: writes, reboots, rollbacks and
+//! jog commands are sent without waiting for a reply.
+
+use serialport::SerialPort;
+
+use crate::Result;
+
+use super::{
+    packet::{InstructionPacket, Packet, StatusPacket},
+    CommunicationErrorKind, Protocol,
+};
+
+/// Offset of the EEP addresses in the register address space
+pub const EEP_BANK: u16 = 0x100;
+
+const BROADCAST_ID: u8 = 0xFE;
+
+/// Size of the header, including both checksums
+const HEADER_SIZE: usize = 7;
+
+/// Largest packet accepted by the servos (the packet size field ranges from 7 to 223 in the DRS-0101/0201 manual)
+const MAX_PACKET_SIZE: usize = 223;
+
+/// Target of one servo in an I_JOG or S_JOG motion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HerkulexJog {
+    pub id: u8,
+    /// Goal position, or speed in continuous rotation (bit 14 is the direction)
+    pub value: u16,
+    /// SET byte: bit 0 stops, bit 1 selects continuous rotation, bits 2 to 4 light the green, blue and red LEDs
+    pub set: u8,
+    /// Move duration in 11.2 ms units (only used by I_JOG, S_JOG has a single play time)
+    pub playtime: u8,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Herkulex;
+impl Protocol<PacketHerkulex> for Herkulex {
+    fn reboot(&self, port: &mut dyn SerialPort, id: u8) -> Result<bool> {
+        self.send_instruction_packet(port, PacketHerkulex::reboot_packet(id).as_ref())?;
+        Ok(true)
+    }
+
+    fn factory_reset(
+        &self,
+        port: &mut dyn SerialPort,
+        id: u8,
+        conserve_id_only: bool,
+        conserve_id_and_baudrate: bool,
+    ) -> Result<()> {
+        self.send_instruction_packet(
+            port,
+            PacketHerkulex::factory_reset_packet(id, conserve_id_only, conserve_id_and_baudrate)
+                .as_ref(),
+        )
+    }
+
+    fn write(&self, port: &mut dyn SerialPort, id: u8, addr: u16, data: &[u8]) -> Result<()> {
+        let packet = PacketHerkulex::write(id, addr, data).ok_or_else(|| {
+            CommunicationErrorKind::AddressOutOfRange(
+                addr,
+                data.len().try_into().unwrap_or(u16::MAX),
+            )
+        })?;
+        self.send_instruction_packet(port, &packet)
+    }
+}
+
+impl Herkulex {
+    /// Moves several servos, each with its own play time
+    pub(crate) fn i_jog(&self, port: &mut dyn SerialPort, jogs: &[HerkulexJog]) -> Result<()> {
+        let params = jogs
+            .iter()
+            .flat_map(|j| {
+                let [lsb, msb] = j.value.to_le_bytes();
+                [lsb, msb, j.set, j.id, j.playtime]
+            })
+            .collect();
+        let packet = PacketHerkulex::packet(BROADCAST_ID, Command::IJog, params)
+            .ok_or_else(|| too_many_jogs(jogs))?;
+        self.send_instruction_packet(port, &packet)
+    }
+
+    /// Moves several servos with a common play time
+    pub(crate) fn s_jog(
+        &self,
+        port: &mut dyn SerialPort,
+        playtime: u8,
+        jogs: &[HerkulexJog],
+    ) -> Result<()> {
+        let mut params = vec![playtime];
+        params.extend(jogs.iter().flat_map(|j| {
+            let [lsb, msb] = j.value.to_le_bytes();
+            [lsb, msb, j.set, j.id]
+        }));
+        let packet = PacketHerkulex::packet(BROADCAST_ID, Command::SJog, params)
+            .ok_or_else(|| too_many_jogs(jogs))?;
+        self.send_instruction_packet(port, &packet)
+    }
+}
+
+/// Jog commands not fitting in a packet, reported with the number of servos as length
+fn too_many_jogs(jogs: &[HerkulexJog]) -> CommunicationErrorKind {
+    CommunicationErrorKind::AddressOutOfRange(0, jogs.len().try_into().unwrap_or(u16::MAX))
+}
+
+#[derive(Debug)]
+pub(crate) struct PacketHerkulex;
+
+impl PacketHerkulex {
+    /// Instruction packet, `None` if the parameters do not fit in [MAX_PACKET_SIZE] bytes
+    fn packet(id: u8, command: Command, params: Vec<u8>) -> Option<InstructionPacketHerkulex> {
+        let size = HEADER_SIZE + params.len();
+        match size <= MAX_PACKET_SIZE {
+            true => Some(InstructionPacketHerkulex {
+                id,
+                command,
+                size: size as u8,
+                params,
+            }),
+            false => None,
+        }
+    }
+
+    /// Instruction packet with a few fixed parameters, always fitting in a packet
+    fn small_packet(id: u8, command: Command, params: Vec<u8>) -> Box<InstructionPacketHerkulex> {
+        Box::new(Self::packet(id, command, params).expect("a few parameters fit in a packet"))
+    }
+
+    /// Write packet, `None` if the data do not fit in a packet
+    fn write(id: u8, addr: u16, data: &[u8]) -> Option<InstructionPacketHerkulex> {
+        let (command, addr) = Self::area(addr, Command::EepWrite, Command::RamWrite);
+        let mut params = vec![addr, u8::try_from(data.len()).ok()?];
+        params.extend(data);
+        Self::packet(id, command, params)
+    }
+
+    /// Read or write command and address within its area
+    fn area(addr: u16, eep: Command, ram: Command) -> (Command, u8) {
+        match addr >= EEP_BANK {
+            true => (eep, (addr - EEP_BANK) as u8),
+            false => (ram, addr as u8),
+        }
+    }
+}
+
+impl Packet for PacketHerkulex {
+    const HEADER_SIZE: usize = HEADER_SIZE;
+
+    type ErrorKind = HerkulexError;
+    type InstructionKind = Command;
+
+    fn get_payload_size(header: &[u8]) -> Result<usize> {
+        match header {
+            [0xFF, 0xFF, size, ..] if *size as usize >= HEADER_SIZE => {
+                Ok(*size as usize - HEADER_SIZE)
+            }
+            _ => Err(Box::new(CommunicationErrorKind::ParsingError)),
+        }
+    }
+
+    fn ping_packet(id: u8) -> Box<dyn InstructionPacket<Self>> {
+        Self::small_packet(id, Command::Stat, vec![])
+    }
+
+    fn reboot_packet(id: u8) -> Box<dyn InstructionPacket<Self>> {
+        Self::small_packet(id, Command::Reboot, vec![])
+    }
+
+    fn factory_reset_packet(
+        id: u8,
+        conserve_id_only: bool,
+        conserve_id_and_baudrate: bool,
+    ) -> Box<dyn InstructionPacket<Self>> {
+        // ID skip and baudrate skip flags
+        let params = vec![
+            (conserve_id_only || conserve_id_and_baudrate) as u8,
+            conserve_id_and_baudrate as u8,
+        ];
+        Self::small_packet(id, Command::Rollback, params)
+    }
+
+    fn read_packet(id: u8, addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>> {
+        let (command, addr) = Self::area(addr, Command::EepRead, Command::RamRead);
+        Self::small_packet(id, command, vec![addr, length as u8])
+    }
+
+    /// Only used for data fitting in a packet, [Herkulex] checks the length of the written data
+    fn write_packet(id: u8, addr: u16, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
+        Box::new(Self::write(id, addr, data).expect("the written data fit in a packet"))
+    }
+
+    fn sync_read_packet(_ids: &[u8], _addr: u16, _length: u16) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("Herkulex servos have no sync read command")
+    }
+
+    fn sync_write_packet(
+        _ids: &[u8],
+        _addr: u16,
+        _data: &[Vec<u8>],
+    ) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("Herkulex servos have no sync write command, use I_JOG or S_JOG")
+    }
+
+    fn status_packet(data: &[u8], sender_id: u8) -> Result<Box<dyn StatusPacket<Self>>> {
+        Ok(Box::new(StatusPacketHerkulex::from_bytes(data, sender_id)?))
+    }
+}
+
+#[derive(Debug)]
+struct InstructionPacketHerkulex {
+    id: u8,
+    command: Command,
+    /// Whole packet length, checked against [MAX_PACKET_SIZE] when built
+    size: u8,
+    params: Vec<u8>,
+}
+
+impl InstructionPacket<PacketHerkulex> for InstructionPacketHerkulex {
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn instruction(&self) -> Command {
+        self.command
+    }
+
+    fn params(&self) -> &Vec<u8> {
+        &self.params
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let (checksum1, checksum2) =
+            checksums(self.size, self.id, self.command.value(), &self.params);
+
+        let mut bytes = vec![
+            0xFF,
+            0xFF,
+            self.size,
+            self.id,
+            self.command.value(),
+            checksum1,
+            checksum2,
+        ];
+        bytes.extend(self.params.iter());
+
+        bytes
+    }
+}
+
+#[derive(Debug)]
+struct StatusPacketHerkulex {
+    id: u8,
+    errors: Vec<HerkulexError>,
+    params: Vec<u8>,
+}
+
+impl StatusPacket<PacketHerkulex> for StatusPacketHerkulex {
+    fn from_bytes(data: &[u8], sender_id: u8) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if data.len() < HEADER_SIZE + 2 || data.len() != data[2] as usize {
+            return Err(Box::new(CommunicationErrorKind::ParsingError));
+        }
+
+        let (id, command, payload) = (data[3], data[4], &data[HEADER_SIZE..]);
+        if (data[5], data[6]) != checksums(data[2], id, command, payload) {
+            return Err(Box::new(CommunicationErrorKind::ChecksumError));
+        }
+        if id != sender_id {
+            return Err(Box::new(CommunicationErrorKind::IncorrectId(sender_id, id)));
+        }
+
+        // Read acknowledgements echo the address and length before the data, all end with the status
+        let (data, status) = payload.split_at(payload.len() - 2);
+        let params = match command & !ACK {
+            c if c == Command::EepRead.value() || c == Command::RamRead.value() => match data {
+                [_, length, values @ ..] if values.len() == *length as usize => values.to_vec(),
+                _ => return Err(Box::new(CommunicationErrorKind::ParsingError)),
+            },
+            _ => data.to_vec(),
+        };
+
+        Ok(StatusPacketHerkulex {
+            id,
+            errors: HerkulexError::from_bytes(status[0], status[1]),
+            params,
+        })
+    }
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn errors(&self) -> &Vec<HerkulexError> {
+        &self.errors
+    }
+
+    fn params(&self) -> &Vec<u8> {
+        &self.params
+    }
+}
+
+/// Errors reported in the status bytes ending every acknowledgement
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub(crate) enum HerkulexError {
+    InputVoltage,
+    PotLimit,
+    Temperature,
+    InvalidPacket,
+    Overload,
+    DriverFault,
+    EepDistorted,
+    Checksum,
+    UnknownCommand,
+    RegisterRange,
+    Garbage,
+}
+
+impl HerkulexError {
+    /// Decodes the status error and status detail bytes (the moving, in-position and motor on flags are not errors)
+    fn from_bytes(error: u8, detail: u8) -> Vec<Self> {
+        use HerkulexError::*;
+
+        let errors = [
+            InputVoltage,
+            PotLimit,
+            Temperature,
+            InvalidPacket,
+            Overload,
+            DriverFault,
+            EepDistorted,
+        ];
+        let details = [
+            (2, Checksum),
+            (3, UnknownCommand),
+            (4, RegisterRange),
+            (5, Garbage),
+        ];
+
+        (0..7)
+            .filter(|i| error & (1 << i) != 0)
+            .map(|i| errors[i])
+            .chain(
+                details
+                    .into_iter()
+                    .filter(|(bit, _)| detail & (1 << bit) != 0)
+                    .map(|(_, e)| e),
+            )
+            .collect()
+    }
+}
+
+/// Set on the command of acknowledgements
+const ACK: u8 = 0x40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Command {
+    EepWrite,
+    EepRead,
+    RamWrite,
+    RamRead,
+    IJog,
+    SJog,
+    Stat,
+    Rollback,
+    Reboot,
+}
+
+impl Command {
+    fn value(&self) -> u8 {
+        match self {
+            Command::EepWrite => 0x01,
+            Command::EepRead => 0x02,
+            Command::RamWrite => 0x03,
+            Command::RamRead => 0x04,
+            Command::IJog => 0x05,
+            Command::SJog => 0x06,
+            Command::Stat => 0x07,
+            Command::Rollback => 0x08,
+            Command::Reboot => 0x09,
+        }
+    }
+}
+
+pub(crate) fn checksums(size: u8, id: u8, command: u8, data: &[u8]) -> (u8, u8) {
+    let checksum1 = data.iter().fold(size ^ id ^ command, |c, b| c ^ b) & 0xFE;
+    (checksum1, !checksum1 & 0xFE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_packets() {
+        // Status of servo 0xFD
+        let p = PacketHerkulex::ping_packet(0xFD);
+        assert_eq!(p.to_bytes(), [0xFF, 0xFF, 0x07, 0xFD, 0x07, 0xFC, 0x02]);
+
+        // Torque on (RAM 52) for servo 0xFD
+        let p = PacketHerkulex::write_packet(0xFD, 52, &[0x60]);
+        assert_eq!(
+            p.to_bytes(),
+            [0xFF, 0xFF, 0x0A, 0xFD, 0x03, 0xA0, 0x5E, 0x34, 0x01, 0x60]
+        );
+
+        // EEP read of the model number
+        let p = PacketHerkulex::read_packet(1, EEP_BANK, 2);
+        assert_eq!(p.to_bytes()[4], 0x02);
+        assert_eq!(p.params(), &[0, 2]);
+    }
+
+    #[test]
+    fn oversized_packets() {
+        use crate::dynamixel_protocol::mock::{FakeServosV2, MockSerialPort};
+
+        let mut port = MockSerialPort::new(FakeServosV2::default());
+        let jogs = |n: u8| -> Vec<HerkulexJog> {
+            (0..n)
+                .map(|id| HerkulexJog {
+                    id,
+                    value: 512,
+                    set: 0,
+                    playtime: 50,
+                })
+                .collect()
+        };
+
+        // 223 bytes at most: 43 servos per I_JOG, 53 per S_JOG
+        assert!(Herkulex.i_jog(&mut port, &jogs(43)).is_ok());
+        assert!(Herkulex.s_jog(&mut port, 50, &jogs(53)).is_ok());
+        for res in [
+            Herkulex.i_jog(&mut port, &jogs(44)),
+            Herkulex.i_jog(&mut port, &jogs(60)),
+            Herkulex.s_jog(&mut port, 50, &jogs(54)),
+            Herkulex.write(&mut port, 1, 0, &[0; 250]),
+        ] {
+            let e = res
+                .unwrap_err()
+                .downcast::<CommunicationErrorKind>()
+                .unwrap();
+            assert!(matches!(*e, CommunicationErrorKind::AddressOutOfRange(..)));
+        }
+    }
+
+    #[test]
+    fn parse_status_packet() {
+        // RAM read ack of servo 0xFD: 1 byte at 53 (LED), then the status error and detail
+        let payload = [0x35, 0x01, 0x01, 0x00, 0x42];
+        let (c1, c2) = checksums(12, 0xFD, 0x44, &payload);
+        let mut bytes = vec![0xFF, 0xFF, 12, 0xFD, 0x44, c1, c2];
+        bytes.extend(payload);
+
+        let sp = StatusPacketHerkulex::from_bytes(&bytes, 0xFD).unwrap();
+        assert_eq!(sp.params, [0x01]);
+        assert!(sp.errors.is_empty());
+
+        assert!(StatusPacketHerkulex::from_bytes(&bytes, 1).is_err());
+        bytes[7] = 0x36;
+        assert!(StatusPacketHerkulex::from_bytes(&bytes, 0xFD).is_err());
+    }
+
+    #[test]
+    fn status_errors() {
+        assert_eq!(
+            HerkulexError::from_bytes(0x11, 0x43),
+            [HerkulexError::InputVoltage, HerkulexError::Overload]
+        );
+        assert_eq!(
+            HerkulexError::from_bytes(0x08, 0x08),
+            [HerkulexError::InvalidPacket, HerkulexError::UnknownCommand]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! Hiwonder/LewanSoul LX-16A bus servo protocol.
+//!
+//! Packets are `55 55 id length command params... checksum`, where `length` counts itself, the command, the
+//! params and the checksum. Servos are driven by commands rather than registers: the command code plays the role
+//! of the register address, read commands are answered with the same command code and write commands are not
+//! answered at all.
+//!
+//! See <https://www.hiwonder.com/> (LX-16A bus servo communication protocol) for details.
+
+use serialport::SerialPort;
+
+use crate::Result;
+
+use super::{
+    packet::{InstructionPacket, Packet, StatusPacket},
+    CommunicationErrorKind, Protocol,
+};
+
+const HEADER: u8 = 0x55;
+
+/// Command answered by every servo, used to ping
+const ID_READ: u8 = 14;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Lx16a;
+impl Protocol<PacketLx16a> for Lx16a {
+    /// Write commands are not answered
+    fn write(&self, port: &mut dyn SerialPort, id: u8, addr: u16, data: &[u8]) -> Result<()> {
+        self.send_instruction_packet(port, PacketLx16a::write_packet(id, addr, data).as_ref())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PacketLx16a;
+impl Packet for PacketLx16a {
+    const HEADER_SIZE: usize = 4;
+
+    type ErrorKind = ();
+    type InstructionKind = u8;
+
+    fn get_payload_size(header: &[u8]) -> Result<usize> {
+        match header {
+            [HEADER, HEADER, _, length] if *length >= 3 => Ok(*length as usize - 1),
+            _ => Err(Box::new(CommunicationErrorKind::ParsingError)),
+        }
+    }
+
+    fn ping_packet(id: u8) -> Box<dyn InstructionPacket<Self>> {
+        Box::new(InstructionPacketLx16a {
+            id,
+            command: ID_READ,
+            params: vec![],
+        })
+    }
+
+    fn reboot_packet(_id: u8) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("LX-16A servos have no reboot command")
+    }
+
+    fn factory_reset_packet(
+        _id: u8,
+        _conserve_id_only: bool,
+        _conserve_id_and_baudrate: bool,
+    ) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("LX-16A servos have no factory reset command")
+    }
+
+    fn read_packet(id: u8, addr: u16, _length: u16) -> Box<dyn InstructionPacket<Self>> {
+        Box::new(InstructionPacketLx16a {
+            id,
+            command: addr as u8,
+            params: vec![],
+        })
+    }
+
+    fn write_packet(id: u8, addr: u16, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
+        Box::new(InstructionPacketLx16a {
+            id,
+            command: addr as u8,
+            params: data.to_vec(),
+        })
+    }
+
+    fn sync_read_packet(_ids: &[u8], _addr: u16, _length: u16) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("LX-16A servos have no sync read command")
+    }
+
+    fn sync_write_packet(
+        _ids: &[u8],
+        _addr: u16,
+        _data: &[Vec<u8>],
+    ) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("LX-16A servos have no sync write command")
+    }
+
+    fn status_packet(data: &[u8], sender_id: u8) -> Result<Box<dyn StatusPacket<Self>>> {
+        Ok(Box::new(StatusPacketLx16a::from_bytes(data, sender_id)?))
+    }
+}
+
+#[derive(Debug)]
+struct InstructionPacketLx16a {
+    id: u8,
+    command: u8,
+    params: Vec<u8>,
+}
+impl InstructionPacket<PacketLx16a> for InstructionPacketLx16a {
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn instruction(&self) -> u8 {
+        self.command
+    }
+
+    fn params(&self) -> &Vec<u8> {
+        &self.params
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let length: u8 = (self.params.len() + 3).try_into().unwrap();
+
+        let mut bytes = vec![HEADER, HEADER, self.id, length, self.command];
+        bytes.extend(self.params.iter());
+        bytes.push(checksum(&bytes[2..]));
+
+        bytes
+    }
+}
+
+#[derive(Debug)]
+struct StatusPacketLx16a {
+    id: u8,
+    errors: Vec<()>,
+    params: Vec<u8>,
+}
+
+impl StatusPacket<PacketLx16a> for StatusPacketLx16a {
+    fn from_bytes(data: &[u8], sender_id: u8) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if data.len() < PacketLx16a::HEADER_SIZE + 2 || data.len() != data[3] as usize + 3 {
+            return Err(Box::new(CommunicationErrorKind::ParsingError));
+        }
+
+        if *data.last().unwrap() != checksum(&data[2..data.len() - 1]) {
+            return Err(Box::new(CommunicationErrorKind::ChecksumError));
+        }
+
+        let id = data[2];
+        if id != sender_id {
+            return Err(Box::new(CommunicationErrorKind::IncorrectId(sender_id, id)));
+        }
+
+        Ok(StatusPacketLx16a {
+            id,
+            errors: vec![],
+            params: data[5..data.len() - 1].to_vec(),
+        })
+    }
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn errors(&self) -> &Vec<()> {
+        &self.errors
+    }
+
+    fn params(&self) -> &Vec<u8> {
+        &self.params
+    }
+}
+
+pub(crate) fn checksum(data: &[u8]) -> u8 {
+    !data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_move_packet() {
+        // Move servo 1 to 500 in 1000 ms
+        let p = PacketLx16a::write_packet(1, 1, &[0xF4, 0x01, 0xE8, 0x03]);
+        assert_eq!(
+            p.to_bytes(),
+            [0x55, 0x55, 0x01, 0x07, 0x01, 0xF4, 0x01, 0xE8, 0x03, 0x16]
+        );
+    }
+
+    #[test]
+    fn create_read_packet() {
+        // Position read
+        let p = PacketLx16a::read_packet(1, 28, 2);
+        assert_eq!(p.to_bytes(), [0x55, 0x55, 0x01, 0x03, 0x1C, 0xDF]);
+    }
+
+    #[test]
+    fn parse_status_packet() {
+        let bytes = [0x55, 0x55, 0x01, 0x05, 0x1C, 0xF4, 0x01, 0xE8];
+        assert_eq!(PacketLx16a::get_payload_size(&bytes[..4]).unwrap(), 4);
+
+        let sp = StatusPacketLx16a::from_bytes(&bytes, 1).unwrap();
+        assert_eq!(sp.id, 1);
+        assert_eq!(sp.params, [0xF4, 0x01]);
+
+        assert!(StatusPacketLx16a::from_bytes(&bytes, 2).is_err());
+
+        let mut corrupted = bytes;
+        corrupted[5] = 0;
+        assert!(StatusPacketLx16a::from_bytes(&corrupted, 1).is_err());
+    }
+}
@@ -0,0 +1,629 @@
+//! In-memory serial port and fake servos, to test the protocol and the tooling without hardware.
+
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+/// Produces the bytes answered to a written buffer
+pub(crate) trait Responder: Send {
+    fn respond(&mut self, written: &[u8]) -> Vec<u8>;
+}
+
+#[derive(Default)]
+struct MockState {
+    rx: VecDeque<u8>,
+    baud_rate: u32,
+}
+
+/// Serial port whose replies are produced by a [Responder]
+pub(crate) struct MockSerialPort {
+    state: Arc<Mutex<MockState>>,
+    responder: Arc<Mutex<dyn Responder>>,
+    timeout: Duration,
+    /// Physical link, failing every call when unplugged
+    link: Option<Arc<AtomicBool>>,
+    /// Time taken by each read, which times out if it is longer than the timeout
+    latency: Duration,
+    /// Whether the replies are still in flight, only received by a blocking read (not counted by `bytes_to_read`)
+    in_flight: bool,
+}
+
+impl MockSerialPort {
+    pub(crate) fn new(responder: impl Responder + 'static) -> Self {
+        MockSerialPort {
+            state: Arc::new(Mutex::new(MockState {
+                baud_rate: 1_000_000,
+                ..Default::default()
+            })),
+            responder: Arc::new(Mutex::new(responder)),
+            timeout: Duration::from_millis(10),
+            link: None,
+            latency: Duration::ZERO,
+            in_flight: false,
+        }
+    }
+
+    /// Port whose replies are still in flight when the write returns
+    pub(crate) fn in_flight(self) -> Self {
+        MockSerialPort {
+            in_flight: true,
+            ..self
+        }
+    }
+
+    /// Port whose reads each take `latency`, e.g. a slow baudrate
+    pub(crate) fn with_latency(self, latency: Duration) -> Self {
+        MockSerialPort { latency, ..self }
+    }
+
+    /// Another handle on the same bus, failing as a disconnected device while `link` is false
+    pub(crate) fn with_link(&self, link: Arc<AtomicBool>) -> Self {
+        MockSerialPort {
+            state: self.state.clone(),
+            responder: self.responder.clone(),
+            timeout: self.timeout,
+            link: Some(link),
+            latency: self.latency,
+            in_flight: self.in_flight,
+        }
+    }
+
+    fn check_link(&self) -> io::Result<()> {
+        match &self.link {
+            Some(link) if !link.load(Ordering::SeqCst) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "Device disconnected",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl io::Read for MockSerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check_link()?;
+        if !self.latency.is_zero() {
+            std::thread::sleep(self.latency.min(self.timeout));
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.rx.is_empty() || self.latency > self.timeout {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "Operation timed out",
+            ));
+        }
+        let n = buf.len().min(state.rx.len());
+        for (b, v) in buf.iter_mut().zip(state.rx.drain(..n)) {
+            *b = v;
+        }
+        Ok(n)
+    }
+}
+
+impl io::Write for MockSerialPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_link()?;
+        let reply = self.responder.lock().unwrap().respond(buf);
+        let mut state = self.state.lock().unwrap();
+        state.rx.extend(reply);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for MockSerialPort {
+    fn name(&self) -> Option<String> {
+        Some("mock".to_string())
+    }
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.state.lock().unwrap().baud_rate)
+    }
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.state.lock().unwrap().baud_rate = baud_rate;
+        Ok(())
+    }
+    fn set_data_bits(&mut self, _: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_flow_control(&mut self, _: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_parity(&mut self, _: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_stop_bits(&mut self, _: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+    fn write_request_to_send(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn write_data_terminal_ready(&mut self, _: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.check_link()?;
+        match self.in_flight {
+            true => Ok(0),
+            false => Ok(self.state.lock().unwrap().rx.len() as u32),
+        }
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.check_link()?;
+        if let ClearBuffer::Input | ClearBuffer::All = buffer_to_clear {
+            self.state.lock().unwrap().rx.clear();
+        }
+        Ok(())
+    }
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(MockSerialPort {
+            state: self.state.clone(),
+            responder: self.responder.clone(),
+            timeout: self.timeout,
+            link: self.link.clone(),
+            latency: self.latency,
+            in_flight: self.in_flight,
+        }))
+    }
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
+
+/// Servo control table, large enough for the Dynamixel X-series
+pub(crate) struct FakeServo {
+    pub(crate) id_addr: usize,
+    pub(crate) table: Vec<u8>,
+    /// Indirect address and data areas (X-series), with 20 entries
+    pub(crate) indirect: Option<(usize, usize)>,
+}
+
+const INDIRECT_ENTRIES: usize = 20;
+
+impl FakeServo {
+    fn new(id: u8, model: u16, id_addr: usize) -> Self {
+        let mut table = vec![0; 1024];
+        table[0..2].copy_from_slice(&model.to_le_bytes());
+        table[id_addr] = id;
+        FakeServo {
+            id_addr,
+            table,
+            indirect: None,
+        }
+    }
+
+    pub(crate) fn id(&self) -> u8 {
+        self.table[self.id_addr]
+    }
+
+    /// Address of the byte actually accessed, following the indirect address table
+    fn resolve(&self, addr: usize) -> usize {
+        match self.indirect {
+            Some((addresses, data)) if (data..data + INDIRECT_ENTRIES).contains(&addr) => {
+                let entry = addresses + 2 * (addr - data);
+                u16::from_le_bytes([self.table[entry], self.table[entry + 1]]) as usize
+            }
+            _ => addr,
+        }
+    }
+
+    pub(crate) fn read(&self, addr: usize, length: usize) -> Vec<u8> {
+        (addr..addr + length)
+            .map(|a| self.table[self.resolve(a)])
+            .collect()
+    }
+
+    pub(crate) fn write(&mut self, addr: usize, data: &[u8]) {
+        for (a, &b) in (addr..).zip(data) {
+            let a = self.resolve(a);
+            self.table[a] = b;
+        }
+    }
+}
+
+/// Protocol v1 servos sharing a bus.
+///
+/// Servos sharing an id all answer, their replies end up concatenated.
+#[derive(Default)]
+pub(crate) struct FakeServosV1 {
+    pub(crate) servos: Vec<FakeServo>,
+}
+
+impl FakeServosV1 {
+    /// Adds a servo with the model number at address 0 and its id at `id_addr`
+    pub(crate) fn with_servo(mut self, id: u8, model: u16, id_addr: usize) -> Self {
+        self.servos.push(FakeServo::new(id, model, id_addr));
+        self
+    }
+
+    pub(crate) fn status_packet(id: u8, params: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xFF, 0xFF, id, params.len() as u8 + 2, 0];
+        packet.extend(params);
+        let crc = packet[2..].iter().fold(0u8, |crc, b| crc.wrapping_add(*b));
+        packet.push(!crc);
+        packet
+    }
+}
+
+impl Responder for FakeServosV1 {
+    fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+        let (id, instruction, params) = (written[2], written[4], &written[5..written.len() - 1]);
+
+        let mut reply = Vec::new();
+        for servo in self.servos.iter_mut().filter(|s| s.id() == id) {
+            match instruction {
+                // Ping
+                0x01 => reply.extend(Self::status_packet(id, &[])),
+                // Read
+                0x02 => {
+                    let (addr, length) = (params[0] as usize, params[1] as usize);
+                    reply.extend(Self::status_packet(id, &servo.read(addr, length)))
+                }
+                // Write
+                0x03 => {
+                    let addr = params[0] as usize;
+                    servo.write(addr, &params[1..]);
+                    reply.extend(Self::status_packet(id, &[]));
+                }
+                _ => {}
+            }
+        }
+        reply
+    }
+}
+
+/// Protocol v2 servos sharing a bus, with sync read and write
+#[derive(Default)]
+pub(crate) struct FakeServosV2 {
+    pub(crate) servos: Vec<FakeServo>,
+}
+
+impl FakeServosV2 {
+    /// Adds an X-series servo (id at address 7, indirect areas at 168 and 224)
+    pub(crate) fn with_servo(mut self, id: u8, model: u16) -> Self {
+        let mut servo = FakeServo::new(id, model, 7);
+        servo.indirect = Some((168, 224));
+        self.servos.push(servo);
+        self
+    }
+
+    pub(crate) fn status_packet(id: u8, params: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0xFF, 0xFF, 0xFD, 0x00, id];
+        packet.extend((params.len() as u16 + 4).to_le_bytes());
+        packet.extend([0x55, 0]);
+        packet.extend(params);
+        packet.extend(crate::dynamixel_protocol::v2::crc(&packet).to_le_bytes());
+        packet
+    }
+}
+
+impl Responder for FakeServosV2 {
+    fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+        let (id, instruction, params) = (written[4], written[7], &written[8..written.len() - 2]);
+        let u16_at = |i: usize| u16::from_le_bytes([params[i], params[i + 1]]) as usize;
+
+        let mut reply = Vec::new();
+        match instruction {
+            // Sync read
+            0x82 => {
+                let (addr, length) = (u16_at(0), u16_at(2));
+                for &id in &params[4..] {
+                    for servo in self.servos.iter().filter(|s| s.id() == id) {
+                        reply.extend(Self::status_packet(id, &servo.read(addr, length)));
+                    }
+                }
+            }
+            // Sync write
+            0x83 => {
+                let (addr, length) = (u16_at(0), u16_at(2));
+                for chunk in params[4..].chunks(length + 1) {
+                    for servo in self.servos.iter_mut().filter(|s| s.id() == chunk[0]) {
+                        servo.write(addr, &chunk[1..]);
+                    }
+                }
+            }
+            _ => {
+                for servo in self.servos.iter_mut().filter(|s| s.id() == id) {
+                    match instruction {
+                        // Ping
+                        0x01 => reply.extend(Self::status_packet(id, &servo.read(0, 3))),
+                        // Read
+                        0x02 => {
+                            reply.extend(Self::status_packet(id, &servo.read(u16_at(0), u16_at(2))))
+                        }
+                        // Write
+                        0x03 => {
+                            servo.write(u16_at(0), &params[2..]);
+                            reply.extend(Self::status_packet(id, &[]));
+                        }
+                        // Reboot, resetting the RAM area (up to the indirect address table)
+                        0x08 => {
+                            reply.extend(Self::status_packet(id, &[]));
+                            servo.table[64..168].fill(0);
+                        }
+                        // Clear
+                        0x10 => reply.extend(Self::status_packet(id, &[])),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        reply
+    }
+}
+
+/// LX-16A servo state
+#[derive(Default)]
+pub(crate) struct FakeLx16a {
+    pub(crate) id: u8,
+    pub(crate) move_time: [u8; 4],
+    pub(crate) offset: u8,
+    pub(crate) mode: [u8; 4],
+    pub(crate) load: u8,
+}
+
+/// LX-16A servos sharing a bus, answering the read commands with their state
+#[derive(Default)]
+pub(crate) struct FakeServosLx16a {
+    pub(crate) servos: Vec<FakeLx16a>,
+}
+
+impl FakeServosLx16a {
+    pub(crate) fn with_servo(mut self, id: u8) -> Self {
+        self.servos.push(FakeLx16a {
+            id,
+            ..Default::default()
+        });
+        self
+    }
+
+    pub(crate) fn status_packet(id: u8, command: u8, params: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x55, 0x55, id, params.len() as u8 + 3, command];
+        packet.extend(params);
+        packet.push(crate::dynamixel_protocol::lx16a::checksum(&packet[2..]));
+        packet
+    }
+}
+
+impl Responder for FakeServosLx16a {
+    fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+        let (id, command, params) = (written[2], written[4], &written[5..written.len() - 1]);
+
+        let mut reply = Vec::new();
+        for servo in self.servos.iter_mut().filter(|s| id == 254 || s.id == id) {
+            let data = match command {
+                1 => {
+                    servo.move_time.copy_from_slice(params);
+                    continue;
+                }
+                13 => {
+                    servo.id = params[0];
+                    continue;
+                }
+                17 => {
+                    servo.offset = params[0];
+                    continue;
+                }
+                29 => {
+                    servo.mode.copy_from_slice(params);
+                    continue;
+                }
+                31 => {
+                    servo.load = params[0];
+                    continue;
+                }
+                2 => servo.move_time.to_vec(),
+                14 => vec![servo.id],
+                19 => vec![servo.offset],
+                26 => vec![42],
+                27 => 7_400_u16.to_le_bytes().to_vec(),
+                // Position read: the servo reached its goal
+                28 => servo.move_time[..2].to_vec(),
+                30 => servo.mode.to_vec(),
+                32 => vec![servo.load],
+                _ => continue,
+            };
+            reply.extend(Self::status_packet(servo.id, command, &data));
+        }
+        reply
+    }
+}
+
+/// Herkulex servo RAM and EEP
+pub(crate) struct FakeHerkulex {
+    pub(crate) ram: Vec<u8>,
+    pub(crate) eep: Vec<u8>,
+}
+
+/// Herkulex servos sharing a bus (default ACK policy: only reads and STAT are answered).
+///
+/// Jog commands set the *absolute_goal_position* (RAM 68).
+#[derive(Default)]
+pub(crate) struct FakeServosHerkulex {
+    pub(crate) servos: Vec<FakeHerkulex>,
+}
+
+impl FakeServosHerkulex {
+    /// Adds a servo with the model number at EEP 0 and its id at RAM 0 and EEP 6
+    pub(crate) fn with_servo(mut self, id: u8, model: u16) -> Self {
+        let (mut ram, mut eep) = (vec![0; 256], vec![0; 256]);
+        eep[0..2].copy_from_slice(&model.to_le_bytes());
+        (ram[0], ram[1], eep[6], eep[7]) = (id, 1, id, 1);
+        self.servos.push(FakeHerkulex { ram, eep });
+        self
+    }
+
+    pub(crate) fn ack_packet(id: u8, command: u8, data: &[u8]) -> Vec<u8> {
+        let mut payload = data.to_vec();
+        payload.extend([0, 0]);
+        let size = 7 + payload.len() as u8;
+        let (c1, c2) =
+            crate::dynamixel_protocol::herkulex::checksums(size, id, command | 0x40, &payload);
+        let mut packet = vec![0xFF, 0xFF, size, id, command | 0x40, c1, c2];
+        packet.extend(payload);
+        packet
+    }
+}
+
+impl Responder for FakeServosHerkulex {
+    fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+        let (id, command, params) = (written[3], written[4], &written[7..]);
+
+        let mut reply = Vec::new();
+        match command {
+            // I_JOG and S_JOG
+            0x05 | 0x06 => {
+                let (jogs, size) = match command {
+                    0x05 => (params, 5),
+                    _ => (&params[1..], 4),
+                };
+                for jog in jogs.chunks(size) {
+                    for servo in self.servos.iter_mut().filter(|s| s.ram[0] == jog[3]) {
+                        servo.ram[68..70].copy_from_slice(&jog[0..2]);
+                    }
+                }
+            }
+            _ => {
+                for servo in self.servos.iter_mut().filter(|s| s.ram[0] == id) {
+                    match command {
+                        // EEP and RAM writes
+                        0x01 | 0x03 => {
+                            let (addr, length) = (params[0] as usize, params[1] as usize);
+                            let area = match command {
+                                0x01 => &mut servo.eep,
+                                _ => &mut servo.ram,
+                            };
+                            area[addr..addr + length].copy_from_slice(&params[2..]);
+                        }
+                        // EEP and RAM reads
+                        0x02 | 0x04 => {
+                            let (addr, length) = (params[0] as usize, params[1] as usize);
+                            let area = match command {
+                                0x02 => &servo.eep,
+                                _ => &servo.ram,
+                            };
+                            let mut data = vec![params[0], params[1]];
+                            data.extend(&area[addr..addr + length]);
+                            reply.extend(Self::ack_packet(id, command, &data));
+                        }
+                        // STAT
+                        0x07 => reply.extend(Self::ack_packet(id, command, &[])),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        reply
+    }
+}
+
+/// Modbus-RTU slaves sharing a bus, exposing their byte table as 512 holding registers
+#[derive(Default)]
+pub(crate) struct FakeServosModbus {
+    pub(crate) servos: Vec<FakeServo>,
+}
+
+impl FakeServosModbus {
+    /// Adds a servo with the model number in the register 0 and its id at byte address 7 (X-series table)
+    pub(crate) fn with_servo(mut self, id: u8, model: u16) -> Self {
+        self.servos.push(FakeServo::new(id, model, 7));
+        self
+    }
+
+    pub(crate) fn response(id: u8, function: u8, data: &[u8]) -> Vec<u8> {
+        let mut packet = vec![id, function];
+        packet.extend(data);
+        packet.extend(crate::dynamixel_protocol::modbus::crc(&packet).to_le_bytes());
+        packet
+    }
+}
+
+impl Responder for FakeServosModbus {
+    fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+        let (id, function, params) = (written[0], written[1], &written[2..written.len() - 2]);
+        let u16_at = |i: usize| u16::from_be_bytes([params[i], params[i + 1]]) as usize;
+
+        let mut reply = Vec::new();
+        for servo in self.servos.iter_mut().filter(|s| s.id() == id) {
+            let (first, count) = match function {
+                0x06 => (u16_at(0), 1),
+                _ => (u16_at(0), u16_at(2)),
+            };
+            if 2 * (first + count) > servo.table.len() {
+                // Illegal data address
+                reply.extend(Self::response(id, function | 0x80, &[0x02]));
+                continue;
+            }
+            match function {
+                // Read holding registers
+                0x03 => {
+                    let mut data = vec![2 * count as u8];
+                    for word in servo.read(2 * first, 2 * count).chunks(2) {
+                        data.extend([word[1], word[0]]);
+                    }
+                    reply.extend(Self::response(id, function, &data));
+                }
+                // Write single / multiple registers, echoing the register and value / quantity
+                0x06 | 0x10 => {
+                    let values = match function {
+                        0x06 => &params[2..4],
+                        _ => &params[5..],
+                    };
+                    let bytes: Vec<u8> = values.chunks(2).flat_map(|w| [w[1], w[0]]).collect();
+                    servo.write(2 * first, &bytes);
+                    reply.extend(Self::response(id, function, &params[..4]));
+                }
+                // Illegal function
+                _ => reply.extend(Self::response(id, function | 0x80, &[0x01])),
+            }
+        }
+        reply
+    }
+}
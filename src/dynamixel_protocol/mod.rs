@@ -3,25 +3,62 @@ use serialport::SerialPort;
 mod packet;
 use packet::{InstructionPacket, Packet, StatusPacket};
 
+#[cfg(test)]
+pub(crate) mod mock;
+
 mod v1;
 use v1::V1;
 
 mod v2;
 use v2::V2;
 
+mod lx16a;
+use lx16a::Lx16a;
+
+mod herkulex;
+use herkulex::Herkulex;
+pub use herkulex::{HerkulexJog, EEP_BANK as HERKULEX_EEP_BANK};
+
+mod modbus;
+use modbus::Modbus;
+
+mod transaction;
+use transaction::LateReplies;
+pub use transaction::PendingTransaction;
+
+mod echo;
+
+mod deadline;
+use deadline::DeadlinePort;
+pub use deadline::{DEFAULT_DEADLINE_MARGIN, DEFAULT_RETURN_DELAY, SLOW_INSTRUCTION_DEADLINE};
+
 use crate::Result;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 enum ProtocolKind {
     V1(V1),
     V2(V2),
+    Lx16a(Lx16a),
+    Herkulex(Herkulex),
+    Modbus(Modbus),
 }
 
 #[derive(Debug)]
-/// Raw dynamixel communication messages controller (protocol v1 or v2)
+/// Raw dynamixel communication messages controller (protocol v1 or v2, or the LX-16A, Herkulex and Modbus-RTU protocols)
 pub struct DynamixelProtocolHandler {
     protocol: ProtocolKind,
     post_delay: Option<Duration>,
+    /// Sequence number of the pending split-phase transaction (0 if none)
+    pending: Arc<AtomicU64>,
+    /// Replies of a cancelled split-phase transaction, drained before the next request
+    late_replies: Arc<Mutex<Option<LateReplies>>>,
+    /// Fixed deadline of the calls, instead of the estimated one or the port timeout
+    deadline: Option<Duration>,
+    estimated_deadlines: bool,
+    return_delay: Duration,
+    deadline_margin: Duration,
+    /// Whether the echo of the sent instructions is read back before the replies
+    echo_cancellation: bool,
 }
 
 impl DynamixelProtocolHandler {
@@ -46,10 +83,7 @@ impl DynamixelProtocolHandler {
     /// println!("Motor MX ID: 11 present position: {:?}", pos);
     /// ```
     pub fn v1() -> Self {
-        DynamixelProtocolHandler {
-            protocol: ProtocolKind::V1(V1),
-            post_delay: None,
-        }
+        Self::with_protocol(ProtocolKind::V1(V1))
     }
     /// Creates a protocol v2 communication IO.
     ///
@@ -72,9 +106,86 @@ impl DynamixelProtocolHandler {
     /// println!("Motor XL-320 ID: 11 present position: {:?}", pos);
     /// ```
     pub fn v2() -> Self {
+        Self::with_protocol(ProtocolKind::V2(V2))
+    }
+
+    /// Creates a Hiwonder/LewanSoul LX-16A bus servo communication IO.
+    ///
+    /// These servos are driven by commands instead of registers: the command code is given as the register address
+    /// (see [crate::servo::hiwonder::lx16a] for the commands). Write commands are not answered, and reboot,
+    /// factory reset, sync read and sync write are not supported.
+    pub fn lx16a() -> Self {
+        Self::with_protocol(ProtocolKind::Lx16a(Lx16a))
+    }
+
+    /// Creates a Dongbu Herkulex (DRS-0101/0201) communication IO.
+    ///
+    /// The RAM registers are at their own address, the EEP ones at [HERKULEX_EEP_BANK] + their address.
+    /// Writes are not answered (default ACK policy), sync read and sync write are not supported: use
+    /// [DynamixelProtocolHandler::i_jog] and [DynamixelProtocolHandler::s_jog] to move several servos at once.
+    pub fn herkulex() -> Self {
+        Self::with_protocol(ProtocolKind::Herkulex(Herkulex))
+    }
+
+    /// Creates a Modbus-RTU communication IO (functions 03, 06 and 16).
+    ///
+    /// The byte addressed control tables are mapped onto 16-bit holding registers: the byte `addr` is in the register
+    /// `addr / 2`, as its low byte for even addresses. Unaligned writes read the edge registers first to keep their
+    /// other byte. Ping reads the first holding register, reboot, factory reset, sync read and sync write are not supported.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use rustypot::{DynamixelProtocolHandler, servo::dynamixel::xl330};
+    /// use std::time::Duration;
+    ///
+    /// let mut serial_port = serialport::new("/dev/ttyUSB0", 115_200)
+    ///     .timeout(Duration::from_millis(10))
+    ///     .open()
+    ///     .expect("Failed to open port");
+    ///
+    /// let dph = DynamixelProtocolHandler::modbus();
+    ///
+    /// // Bytes 132-135, in the holding registers 66 and 67
+    /// let pos =
+    ///     xl330::read_present_position(&dph, serial_port.as_mut(), 1).expect("Communication error");
+    /// ```
+    pub fn modbus() -> Self {
+        Self::with_protocol(ProtocolKind::Modbus(Modbus))
+    }
+
+    fn with_protocol(protocol: ProtocolKind) -> Self {
         DynamixelProtocolHandler {
-            protocol: ProtocolKind::V2(V2),
+            protocol,
             post_delay: None,
+            pending: Arc::default(),
+            late_replies: Arc::default(),
+            deadline: None,
+            estimated_deadlines: false,
+            return_delay: DEFAULT_RETURN_DELAY,
+            deadline_margin: DEFAULT_DEADLINE_MARGIN,
+            echo_cancellation: false,
+        }
+    }
+
+    /// Whether this IO uses the Herkulex protocol
+    pub(crate) fn is_herkulex(&self) -> bool {
+        matches!(self.protocol, ProtocolKind::Herkulex(_))
+    }
+
+    /// Whether this IO uses the protocol v2
+    pub(crate) fn is_v2(&self) -> bool {
+        matches!(self.protocol, ProtocolKind::V2(_))
+    }
+
+    /// Highest id that can be given to a servo with this protocol.
+    ///
+    /// Protocol v2 reserves 253 (it starts the packet header), Modbus limits addresses to 247,
+    /// and the others only reserve the broadcast id 254.
+    pub fn max_id(&self) -> u8 {
+        match self.protocol {
+            ProtocolKind::V2(_) => 252,
+            ProtocolKind::Modbus(_) => 247,
+            ProtocolKind::V1(_) | ProtocolKind::Herkulex(_) | ProtocolKind::Lx16a(_) => 253,
         }
     }
 
@@ -112,9 +223,58 @@ impl DynamixelProtocolHandler {
     /// }
     /// ```
     pub fn ping(&self, serial_port: &mut dyn serialport::SerialPort, id: u8) -> Result<bool> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 0, 3, 1);
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
             ProtocolKind::V1(p) => p.ping(serial_port, id),
             ProtocolKind::V2(p) => p.ping(serial_port, id),
+            ProtocolKind::Herkulex(p) => p.ping(serial_port, id),
+            ProtocolKind::Modbus(p) => p.ping(serial_port, id),
+            ProtocolKind::Lx16a(p) => p.ping(serial_port, id),
+        }
+    }
+
+    /// Ping the motor with specified `id`, checking whether several motors answered.
+    ///
+    /// Motors sharing an id reply at the same time: their replies overlap, which shows up as extra bytes after the reply.
+    /// A corrupted (checksum, parsing) reply is retried once, as it may come from line noise: it is only reported as a collision
+    /// when the retry is corrupted too. Other errors (e.g. a reply from another id) are returned.
+    /// Without reply before the timeout, the motor is reported absent at once, without waiting for other replies.
+    pub fn ping_status(
+        &self,
+        serial_port: &mut dyn serialport::SerialPort,
+        id: u8,
+    ) -> Result<PingStatus> {
+        match self.ping_status_once(serial_port, id) {
+            Err(e) if is_corrupted_reply(e.as_ref()) => {
+                log::debug!("Corrupted ping reply from {id}, retrying: {e}");
+                match self.ping_status_once(serial_port, id) {
+                    Err(e) if is_corrupted_reply(e.as_ref()) => {
+                        log::debug!("Corrupted ping reply from {id}: {e}");
+                        Ok(PingStatus::Collision)
+                    }
+                    status => status,
+                }
+            }
+            status => status,
+        }
+    }
+
+    fn ping_status_once(
+        &self,
+        serial_port: &mut dyn serialport::SerialPort,
+        id: u8,
+    ) -> Result<PingStatus> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 0, 3, 1);
+        let serial_port = &mut self.echoing(serial_port);
+        match &self.protocol {
+            ProtocolKind::V1(p) => p.ping_status(serial_port, id),
+            ProtocolKind::V2(p) => p.ping_status(serial_port, id),
+            ProtocolKind::Herkulex(p) => p.ping_status(serial_port, id),
+            ProtocolKind::Modbus(p) => p.ping_status(serial_port, id),
+            ProtocolKind::Lx16a(p) => p.ping_status(serial_port, id),
         }
     }
 
@@ -123,9 +283,36 @@ impl DynamixelProtocolHandler {
     /// Reboot the motor with specified `id`.
     /// Returns an [CommunicationErrorKind] if the communication fails.
     pub fn reboot(&self, serial_port: &mut dyn serialport::SerialPort, id: u8) -> Result<bool> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting_slow(serial_port);
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
             ProtocolKind::V1(p) => p.reboot(serial_port, id),
             ProtocolKind::V2(p) => p.reboot(serial_port, id),
+            ProtocolKind::Herkulex(p) => p.reboot(serial_port, id),
+            ProtocolKind::Lx16a(_) | ProtocolKind::Modbus(_) => {
+                Err(Box::new(CommunicationErrorKind::Unsupported))
+            }
+        }
+    }
+
+    /// Clear instruction, resetting the multi-turn revolution count.
+    ///
+    /// The present position goes back within one revolution. Only protocol v2 servos support it, and only when stopped.
+    pub fn clear_multi_turn(
+        &self,
+        serial_port: &mut dyn serialport::SerialPort,
+        id: u8,
+    ) -> Result<()> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 5, 0, 1);
+        let serial_port = &mut self.echoing(serial_port);
+        match &self.protocol {
+            ProtocolKind::V1(_)
+            | ProtocolKind::Lx16a(_)
+            | ProtocolKind::Herkulex(_)
+            | ProtocolKind::Modbus(_) => Err(Box::new(CommunicationErrorKind::Unsupported)),
+            ProtocolKind::V2(p) => p.clear_multi_turn(serial_port, id),
         }
     }
 
@@ -140,6 +327,9 @@ impl DynamixelProtocolHandler {
         conserve_id_only: bool,
         conserve_id_and_baudrate: bool,
     ) -> Result<()> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting_slow(serial_port);
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
             ProtocolKind::V1(p) => {
                 if conserve_id_only || conserve_id_and_baudrate {
@@ -150,6 +340,12 @@ impl DynamixelProtocolHandler {
             ProtocolKind::V2(p) => {
                 p.factory_reset(serial_port, id, conserve_id_only, conserve_id_and_baudrate)
             }
+            ProtocolKind::Herkulex(p) => {
+                p.factory_reset(serial_port, id, conserve_id_only, conserve_id_and_baudrate)
+            }
+            ProtocolKind::Lx16a(_) | ProtocolKind::Modbus(_) => {
+                Err(Box::new(CommunicationErrorKind::Unsupported))
+            }
         }
     }
 
@@ -188,12 +384,35 @@ impl DynamixelProtocolHandler {
         &self,
         serial_port: &mut dyn serialport::SerialPort,
         id: u8,
-        addr: u8,
-        length: u8,
+        addr: u16,
+        length: u16,
     ) -> Result<Vec<u8>> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 4, length.into(), 1);
+        let serial_port = &mut self.echoing(serial_port);
         let res = match &self.protocol {
-            ProtocolKind::V1(p) => p.read(serial_port, id, addr, length),
+            ProtocolKind::V1(p) => {
+                check_v1_range(addr, length)?;
+                p.read(serial_port, id, addr, length)
+            }
             ProtocolKind::V2(p) => p.read(serial_port, id, addr, length),
+            ProtocolKind::Herkulex(p) => {
+                check_herkulex_range(addr, length)?;
+                p.read(serial_port, id, addr, length)
+            }
+            ProtocolKind::Modbus(p) => {
+                check_modbus_range(addr, length, modbus::MAX_READ_WORDS)?;
+                p.read(serial_port, id, addr, length)
+            }
+            ProtocolKind::Lx16a(p) => {
+                check_v1_range(addr, length)?;
+                p.read(serial_port, id, addr, length).and_then(|data| {
+                    match data.len() == length as usize {
+                        true => Ok(data),
+                        false => Err(Box::new(CommunicationErrorKind::ParsingError).into()),
+                    }
+                })
+            }
         };
         if let Some(delay) = self.post_delay {
             std::thread::sleep(delay);
@@ -234,12 +453,35 @@ impl DynamixelProtocolHandler {
         &self,
         serial_port: &mut dyn serialport::SerialPort,
         id: u8,
-        addr: u8,
+        addr: u16,
         data: &[u8],
     ) -> Result<()> {
+        self.check_idle(serial_port)?;
+        // Modbus may read the edge registers before writing
+        let exchanges = match self.protocol {
+            ProtocolKind::Modbus(_) => 3,
+            _ => 1,
+        };
+        let serial_port = &mut self.expecting(serial_port, 2 + data.len(), 0, exchanges);
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
-            ProtocolKind::V1(p) => p.write(serial_port, id, addr, data),
+            ProtocolKind::V1(p) => {
+                check_v1_range(addr, data.len() as u16)?;
+                p.write(serial_port, id, addr, data)
+            }
             ProtocolKind::V2(p) => p.write(serial_port, id, addr, data),
+            ProtocolKind::Herkulex(p) => {
+                check_herkulex_range(addr, data.len() as u16)?;
+                p.write(serial_port, id, addr, data)
+            }
+            ProtocolKind::Modbus(p) => {
+                check_modbus_range(addr, data.len() as u16, modbus::MAX_WRITE_WORDS)?;
+                p.write(serial_port, id, addr, data)
+            }
+            ProtocolKind::Lx16a(p) => {
+                check_v1_range(addr, data.len() as u16)?;
+                p.write(serial_port, id, addr, data)
+            }
         }?;
         if let Some(delay) = self.post_delay {
             std::thread::sleep(delay);
@@ -251,18 +493,25 @@ impl DynamixelProtocolHandler {
         &self,
         serial_port: &mut dyn serialport::SerialPort,
         id: u8,
-        addr: u8,
+        addr: u16,
         data: &[u8],
     ) -> Result<Vec<u8>> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 2 + data.len(), data.len(), 1);
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
             ProtocolKind::V1(p) => {
+                check_v1_range(addr, data.len() as u16)?;
                 let res = p.write_fb(serial_port, id, addr, data);
                 if let Some(delay) = self.post_delay {
                     std::thread::sleep(delay);
                 }
                 res
             }
-            ProtocolKind::V2(_) => Err(Box::new(CommunicationErrorKind::Unsupported)),
+            ProtocolKind::V2(_)
+            | ProtocolKind::Lx16a(_)
+            | ProtocolKind::Herkulex(_)
+            | ProtocolKind::Modbus(_) => Err(Box::new(CommunicationErrorKind::Unsupported)),
         }
     }
 
@@ -307,12 +556,21 @@ impl DynamixelProtocolHandler {
         &self,
         serial_port: &mut dyn serialport::SerialPort,
         ids: &[u8],
-        addr: u8,
-        length: u8,
+        addr: u16,
+        length: u16,
     ) -> Result<Vec<Vec<u8>>> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 4 + ids.len(), length.into(), ids.len());
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
-            ProtocolKind::V1(p) => p.sync_read(serial_port, ids, addr, length),
+            ProtocolKind::V1(p) => {
+                check_v1_range(addr, length)?;
+                p.sync_read(serial_port, ids, addr, length)
+            }
             ProtocolKind::V2(p) => p.sync_read(serial_port, ids, addr, length),
+            ProtocolKind::Lx16a(_) | ProtocolKind::Herkulex(_) | ProtocolKind::Modbus(_) => {
+                Err(Box::new(CommunicationErrorKind::Unsupported))
+            }
         }
     }
 
@@ -351,16 +609,95 @@ impl DynamixelProtocolHandler {
         &self,
         serial_port: &mut dyn serialport::SerialPort,
         ids: &[u8],
-        addr: u8,
+        addr: u16,
         data: &[Vec<u8>],
     ) -> Result<()> {
+        self.check_idle(serial_port)?;
+        let sent = 4 + data.iter().map(|d| d.len() + 1).sum::<usize>();
+        let serial_port = &mut self.expecting(serial_port, sent, 0, 0);
+        let serial_port = &mut self.echoing(serial_port);
         match &self.protocol {
-            ProtocolKind::V1(p) => p.sync_write(serial_port, ids, addr, data),
+            ProtocolKind::V1(p) => {
+                check_v1_range(addr, data.first().map_or(0, |d| d.len()) as u16)?;
+                p.sync_write(serial_port, ids, addr, data)
+            }
             ProtocolKind::V2(p) => p.sync_write(serial_port, ids, addr, data),
+            ProtocolKind::Lx16a(_) | ProtocolKind::Herkulex(_) | ProtocolKind::Modbus(_) => {
+                Err(Box::new(CommunicationErrorKind::Unsupported))
+            }
+        }
+    }
+
+    /// Herkulex I_JOG: moves several servos at once, each with its own play time.
+    ///
+    /// A packet carries at most 43 servos, longer lists fail with [CommunicationErrorKind::AddressOutOfRange].
+    /// Only supported by the Herkulex protocol.
+    pub fn i_jog(
+        &self,
+        serial_port: &mut dyn serialport::SerialPort,
+        jogs: &[HerkulexJog],
+    ) -> Result<()> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 5 * jogs.len(), 0, 0);
+        let serial_port = &mut self.echoing(serial_port);
+        match &self.protocol {
+            ProtocolKind::Herkulex(p) => p.i_jog(serial_port, jogs),
+            _ => Err(Box::new(CommunicationErrorKind::Unsupported)),
+        }
+    }
+
+    /// Herkulex S_JOG: moves several servos at once with a common play time (11.2 ms units).
+    ///
+    /// A packet carries at most 53 servos, longer lists fail with [CommunicationErrorKind::AddressOutOfRange].
+    /// Only supported by the Herkulex protocol.
+    pub fn s_jog(
+        &self,
+        serial_port: &mut dyn serialport::SerialPort,
+        playtime: u8,
+        jogs: &[HerkulexJog],
+    ) -> Result<()> {
+        self.check_idle(serial_port)?;
+        let serial_port = &mut self.expecting(serial_port, 1 + 4 * jogs.len(), 0, 0);
+        let serial_port = &mut self.echoing(serial_port);
+        match &self.protocol {
+            ProtocolKind::Herkulex(p) => p.s_jog(serial_port, playtime, jogs),
+            _ => Err(Box::new(CommunicationErrorKind::Unsupported)),
         }
     }
 }
 
+/// Protocol v1 (and LX-16A) packets encode addresses and lengths on a single byte
+fn check_v1_range(addr: u16, length: u16) -> Result<()> {
+    match u8::try_from(addr).is_ok() && u8::try_from(length).is_ok() {
+        true => Ok(()),
+        false => Err(Box::new(CommunicationErrorKind::AddressOutOfRange(
+            addr, length,
+        ))),
+    }
+}
+
+/// Herkulex packets encode addresses and lengths on a single byte, in the RAM or the EEP area
+fn check_herkulex_range(addr: u16, length: u16) -> Result<()> {
+    check_v1_range(addr % herkulex::EEP_BANK, length)?;
+    match addr < 2 * herkulex::EEP_BANK {
+        true => Ok(()),
+        false => Err(Box::new(CommunicationErrorKind::AddressOutOfRange(
+            addr, length,
+        ))),
+    }
+}
+
+/// Modbus requests address at most `max_words` holding registers, within the 16-bit register space
+fn check_modbus_range(addr: u16, length: u16, max_words: u16) -> Result<()> {
+    let (_, count) = modbus::words(addr, length);
+    match length > 0 && count <= max_words {
+        true => Ok(()),
+        false => Err(Box::new(CommunicationErrorKind::AddressOutOfRange(
+            addr, length,
+        ))),
+    }
+}
+
 trait Protocol<P: Packet> {
     fn ping(&self, port: &mut dyn SerialPort, id: u8) -> Result<bool> {
         self.send_instruction_packet(port, P::ping_packet(id).as_ref())?;
@@ -368,6 +705,34 @@ trait Protocol<P: Packet> {
         Ok(self.read_status_packet(port, id).is_ok())
     }
 
+    fn ping_status(&self, port: &mut dyn SerialPort, id: u8) -> Result<PingStatus> {
+        self.send_instruction_packet(port, P::ping_packet(id).as_ref())?;
+        let reply = match self.read_status_packet(port, id) {
+            Err(e)
+                if matches!(
+                    e.downcast_ref::<std::io::Error>(),
+                    Some(e) if e.kind() == std::io::ErrorKind::TimedOut
+                ) =>
+            {
+                return Ok(PingStatus::Absent)
+            }
+            reply => reply,
+        };
+
+        // A reply, possibly corrupted, was received: leave time to any other motor to finish its own
+        std::thread::sleep(COLLISION_WINDOW);
+        let extra_bytes = !self.is_input_buffer_empty(port)?;
+        if extra_bytes {
+            self.flush(port)?;
+        }
+
+        match reply {
+            _ if extra_bytes => Ok(PingStatus::Collision),
+            Ok(_) => Ok(PingStatus::Present),
+            Err(e) => Err(e),
+        }
+    }
+
     fn reboot(&self, port: &mut dyn SerialPort, id: u8) -> Result<bool> {
         self.send_instruction_packet(port, P::reboot_packet(id).as_ref())?;
 
@@ -388,12 +753,12 @@ trait Protocol<P: Packet> {
         self.read_status_packet(port, id).map(|_| ())
     }
 
-    fn read(&self, port: &mut dyn SerialPort, id: u8, addr: u8, length: u8) -> Result<Vec<u8>> {
+    fn read(&self, port: &mut dyn SerialPort, id: u8, addr: u16, length: u16) -> Result<Vec<u8>> {
         self.send_instruction_packet(port, P::read_packet(id, addr, length).as_ref())?;
         self.read_status_packet(port, id)
             .map(|sp| sp.params().to_vec())
     }
-    fn write(&self, port: &mut dyn SerialPort, id: u8, addr: u8, data: &[u8]) -> Result<()> {
+    fn write(&self, port: &mut dyn SerialPort, id: u8, addr: u16, data: &[u8]) -> Result<()> {
         self.send_instruction_packet(port, P::write_packet(id, addr, data).as_ref())?;
         self.read_status_packet(port, id).map(|_| ())
     }
@@ -402,7 +767,7 @@ trait Protocol<P: Packet> {
         &self,
         port: &mut dyn SerialPort,
         id: u8,
-        addr: u8,
+        addr: u16,
         data: &[u8],
     ) -> Result<Vec<u8>> {
         self.send_instruction_packet(port, P::write_packet(id, addr, data).as_ref())?;
@@ -414,8 +779,8 @@ trait Protocol<P: Packet> {
         &self,
         port: &mut dyn SerialPort,
         ids: &[u8],
-        addr: u8,
-        length: u8,
+        addr: u16,
+        length: u16,
     ) -> Result<Vec<Vec<u8>>> {
         self.send_instruction_packet(port, P::sync_read_packet(ids, addr, length).as_ref())?;
         let mut result = Vec::new();
@@ -429,7 +794,7 @@ trait Protocol<P: Packet> {
         &self,
         port: &mut dyn SerialPort,
         ids: &[u8],
-        addr: u8,
+        addr: u16,
         data: &[Vec<u8>],
     ) -> Result<()> {
         self.send_instruction_packet(port, P::sync_write_packet(ids, addr, data).as_ref())?;
@@ -453,7 +818,14 @@ trait Protocol<P: Packet> {
 
         match port.write_all(&packet.to_bytes()) {
             Ok(_) => Ok(()),
-            Err(_) => Err(Box::new(CommunicationErrorKind::TimeoutError)),
+            // e.g. a bus collision detected in the echo
+            Err(e) => match e
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<CommunicationErrorKind>())
+            {
+                Some(kind) => Err(Box::new(*kind)),
+                None => Err(Box::new(CommunicationErrorKind::TimeoutError)),
+            },
         }
     }
     fn read_status_packet(
@@ -494,7 +866,38 @@ trait Protocol<P: Packet> {
     }
 }
 
-use std::{fmt, time::Duration};
+use std::{
+    fmt,
+    sync::{atomic::AtomicU64, Arc, Mutex},
+    time::Duration,
+};
+
+/// Time waited after a ping reply to detect other motors answering with the same id
+const COLLISION_WINDOW: Duration = Duration::from_millis(2);
+
+/// Whether a ping failed on a reply it could not decode, e.g. overlapping replies or line noise
+fn is_corrupted_reply(e: &(dyn std::error::Error + 'static)) -> bool {
+    matches!(
+        e.downcast_ref::<CommunicationErrorKind>(),
+        Some(CommunicationErrorKind::ChecksumError | CommunicationErrorKind::ParsingError)
+    )
+}
+
+/// Outcome of [DynamixelProtocolHandler::ping_status]
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PingStatus {
+    /// No motor answered
+    Absent,
+    /// A single motor answered
+    Present,
+    /// Several motors answered with this id
+    Collision,
+}
 
 /// Dynamixel Communication Error
 #[derive(Debug, Clone, Copy)]
@@ -510,6 +913,16 @@ pub enum CommunicationErrorKind {
 
     /// Operation not supported
     Unsupported,
+    /// Register address or length beyond what the protocol can encode (address, length)
+    AddressOutOfRange(u16, u16),
+    /// Modbus exception response (exception code)
+    ModbusException(u8),
+    /// A split-phase transaction of the handler is still pending
+    TransactionPending,
+    /// The transaction is not the pending one of the handler (already collected, or sent by another handler)
+    NoPendingTransaction,
+    /// The echo of the sent bytes differs from them: another device was driving the bus
+    BusCollision,
 }
 impl fmt::Display for CommunicationErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -521,6 +934,23 @@ impl fmt::Display for CommunicationErrorKind {
                 write!(f, "Incorrect id ({resp_id} instead of {sender_id})")
             }
             CommunicationErrorKind::Unsupported => write!(f, "Operation not supported"),
+            CommunicationErrorKind::AddressOutOfRange(addr, length) => write!(
+                f,
+                "Address {addr} (length {length}) out of the protocol range"
+            ),
+            CommunicationErrorKind::ModbusException(code) => {
+                write!(f, "Modbus exception {code}")
+            }
+            CommunicationErrorKind::TransactionPending => write!(
+                f,
+                "A split-phase transaction is pending, collect it before sending another request"
+            ),
+            CommunicationErrorKind::NoPendingTransaction => {
+                write!(f, "Not the pending transaction of this handler")
+            }
+            CommunicationErrorKind::BusCollision => {
+                write!(f, "Bus collision (the echo differs from the sent bytes)")
+            }
         }
     }
 }
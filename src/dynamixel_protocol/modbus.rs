@@ -0,0 +1,436 @@
+//! Modbus-RTU protocol.
+//!
+//! Frames are `slave_id function data... crc_lo crc_hi` (CRC-16/MODBUS), using the functions
+//! * 03: read holding registers
+//! * 06: write single register
+//! * 16: write multiple registers
+//!
+//! Control tables are byte addressed: the byte address `addr` lives in the holding register `addr / 2`, whose value is
+//! made of the bytes `2n` (low) and `2n + 1` (high), sent big-endian as usual with Modbus. Reads of unaligned
+//! ranges fetch the whole words, writes of unaligned ranges read the edge words first to keep their other byte.
+
+use serialport::SerialPort;
+
+use crate::Result;
+
+use super::{
+    packet::{InstructionPacket, Packet, StatusPacket},
+    CommunicationErrorKind, Protocol,
+};
+
+/// Most holding registers read by a single request
+pub(crate) const MAX_READ_WORDS: u16 = 125;
+/// Most holding registers written by a single request
+pub(crate) const MAX_WRITE_WORDS: u16 = 123;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Modbus;
+
+impl Protocol<PacketModbus> for Modbus {
+    fn read(&self, port: &mut dyn SerialPort, id: u8, addr: u16, length: u16) -> Result<Vec<u8>> {
+        let (first, count) = words(addr, length);
+        let bytes = self.read_words(port, id, first, count)?;
+        let offset = (addr % 2) as usize;
+        Ok(bytes[offset..offset + length as usize].to_vec())
+    }
+
+    fn write(&self, port: &mut dyn SerialPort, id: u8, addr: u16, data: &[u8]) -> Result<()> {
+        let (first, count) = words(addr, data.len() as u16);
+        let offset = (addr % 2) as usize;
+
+        let mut bytes = match offset != 0 || !data.len().is_multiple_of(2) {
+            true => self.read_words(port, id, first, count)?,
+            false => vec![0; 2 * count as usize],
+        };
+        bytes[offset..offset + data.len()].copy_from_slice(data);
+
+        let values: Vec<u16> = bytes
+            .chunks(2)
+            .map(|w| u16::from_le_bytes([w[0], w[1]]))
+            .collect();
+        let packet = match values.as_slice() {
+            [value] => PacketModbus::packet(id, Function::WriteSingle, single(first, *value)),
+            _ => PacketModbus::packet(id, Function::WriteMultiple, multiple(first, &values)),
+        };
+        self.send_instruction_packet(port, &packet)?;
+        let echo = self.read_status_packet(port, id)?;
+        match echo.params()[..2] == first.to_be_bytes() {
+            true => Ok(()),
+            false => Err(Box::new(CommunicationErrorKind::ParsingError)),
+        }
+    }
+}
+
+impl Modbus {
+    /// Reads `count` holding registers, as little-endian bytes
+    fn read_words(
+        &self,
+        port: &mut dyn SerialPort,
+        id: u8,
+        first: u16,
+        count: u16,
+    ) -> Result<Vec<u8>> {
+        self.send_instruction_packet(port, PacketModbus::read_packet(id, first, count).as_ref())?;
+        let sp = self.read_status_packet(port, id)?;
+        if sp.params().len() != 2 * count as usize {
+            return Err(Box::new(CommunicationErrorKind::ParsingError));
+        }
+        Ok(sp.params().chunks(2).flat_map(|w| [w[1], w[0]]).collect())
+    }
+}
+
+/// First holding register and number of registers covering the bytes `addr..addr + length`
+pub(crate) fn words(addr: u16, length: u16) -> (u16, u16) {
+    let first = addr / 2;
+    let last = (addr as u32 + length.max(1) as u32 - 1) / 2;
+    (first, (last - first as u32 + 1) as u16)
+}
+
+fn single(register: u16, value: u16) -> Vec<u8> {
+    let mut params = register.to_be_bytes().to_vec();
+    params.extend(value.to_be_bytes());
+    params
+}
+
+fn multiple(first: u16, values: &[u16]) -> Vec<u8> {
+    let mut params = first.to_be_bytes().to_vec();
+    params.extend((values.len() as u16).to_be_bytes());
+    params.push(2 * values.len() as u8);
+    params.extend(values.iter().flat_map(|v| v.to_be_bytes()));
+    params
+}
+
+#[derive(Debug)]
+pub(crate) struct PacketModbus;
+
+impl PacketModbus {
+    fn packet(id: u8, function: Function, params: Vec<u8>) -> InstructionPacketModbus {
+        InstructionPacketModbus {
+            id,
+            function,
+            params,
+        }
+    }
+}
+
+impl Packet for PacketModbus {
+    /// Slave id, function and the byte count (03), the register high byte (06, 16) or the exception code
+    const HEADER_SIZE: usize = 3;
+
+    type ErrorKind = u8;
+    type InstructionKind = Function;
+
+    fn get_payload_size(header: &[u8]) -> Result<usize> {
+        match header[1] {
+            0x03 => Ok(header[2] as usize + 2),
+            0x06 | 0x10 => Ok(5),
+            f if f & 0x80 != 0 => Ok(2),
+            _ => Err(Box::new(CommunicationErrorKind::ParsingError)),
+        }
+    }
+
+    /// Reads the first holding register (where the model number usually is)
+    fn ping_packet(id: u8) -> Box<dyn InstructionPacket<Self>> {
+        Self::read_packet(id, 0, 1)
+    }
+
+    fn reboot_packet(_id: u8) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("Modbus has no reboot function")
+    }
+
+    fn factory_reset_packet(
+        _id: u8,
+        _conserve_id_only: bool,
+        _conserve_id_and_baudrate: bool,
+    ) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("Modbus has no factory reset function")
+    }
+
+    /// Reads `length` holding registers from `addr` (in words)
+    fn read_packet(id: u8, addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>> {
+        Box::new(Self::packet(
+            id,
+            Function::ReadHolding,
+            single(addr, length),
+        ))
+    }
+
+    /// Writes the holding registers from `addr` (in words)
+    fn write_packet(id: u8, addr: u16, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
+        let values: Vec<u16> = data
+            .chunks(2)
+            .map(|w| u16::from_le_bytes([w[0], *w.get(1).unwrap_or(&0)]))
+            .collect();
+        Box::new(Self::packet(
+            id,
+            Function::WriteMultiple,
+            multiple(addr, &values),
+        ))
+    }
+
+    fn sync_read_packet(_ids: &[u8], _addr: u16, _length: u16) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("Modbus has no sync read function")
+    }
+
+    fn sync_write_packet(
+        _ids: &[u8],
+        _addr: u16,
+        _data: &[Vec<u8>],
+    ) -> Box<dyn InstructionPacket<Self>> {
+        unreachable!("Modbus has no sync write function")
+    }
+
+    fn status_packet(data: &[u8], sender_id: u8) -> Result<Box<dyn StatusPacket<Self>>> {
+        Ok(Box::new(StatusPacketModbus::from_bytes(data, sender_id)?))
+    }
+}
+
+#[derive(Debug)]
+struct InstructionPacketModbus {
+    id: u8,
+    function: Function,
+    params: Vec<u8>,
+}
+
+impl InstructionPacket<PacketModbus> for InstructionPacketModbus {
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn instruction(&self) -> Function {
+        self.function
+    }
+
+    fn params(&self) -> &Vec<u8> {
+        &self.params
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.id, self.function.value()];
+        bytes.extend(self.params.iter());
+        bytes.extend(crc(&bytes).to_le_bytes());
+
+        bytes
+    }
+}
+
+#[derive(Debug)]
+struct StatusPacketModbus {
+    id: u8,
+    errors: Vec<u8>,
+    params: Vec<u8>,
+}
+
+impl StatusPacket<PacketModbus> for StatusPacketModbus {
+    fn from_bytes(data: &[u8], sender_id: u8) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        if data.len() < PacketModbus::HEADER_SIZE + 2 {
+            return Err(Box::new(CommunicationErrorKind::ParsingError));
+        }
+
+        let (frame, read_crc) = data.split_at(data.len() - 2);
+        if crc(frame).to_le_bytes() != read_crc {
+            return Err(Box::new(CommunicationErrorKind::ChecksumError));
+        }
+
+        let id = frame[0];
+        if id != sender_id {
+            return Err(Box::new(CommunicationErrorKind::IncorrectId(sender_id, id)));
+        }
+
+        let params = match frame[1] {
+            f if f & 0x80 != 0 => {
+                return Err(Box::new(CommunicationErrorKind::ModbusException(frame[2])))
+            }
+            // Skips the byte count
+            0x03 => frame[3..].to_vec(),
+            _ => frame[2..].to_vec(),
+        };
+
+        Ok(StatusPacketModbus {
+            id,
+            errors: vec![],
+            params,
+        })
+    }
+
+    fn id(&self) -> u8 {
+        self.id
+    }
+
+    fn errors(&self) -> &Vec<u8> {
+        &self.errors
+    }
+
+    fn params(&self) -> &Vec<u8> {
+        &self.params
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Function {
+    ReadHolding,
+    WriteSingle,
+    WriteMultiple,
+}
+
+impl Function {
+    fn value(&self) -> u8 {
+        match self {
+            Function::ReadHolding => 0x03,
+            Function::WriteSingle => 0x06,
+            Function::WriteMultiple => 0x10,
+        }
+    }
+}
+
+/// CRC-16/MODBUS
+pub(crate) fn crc(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &b| {
+        (0..8).fold(crc ^ b as u16, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xA001,
+            _ => crc >> 1,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc_modbus() {
+        assert_eq!(crc(b"123456789"), 0x4B37);
+    }
+
+    #[test]
+    fn create_packets() {
+        // Read 3 holding registers from 0x006B of slave 0x11
+        let p = PacketModbus::read_packet(0x11, 0x6B, 3);
+        assert_eq!(
+            p.to_bytes(),
+            [0x11, 0x03, 0x00, 0x6B, 0x00, 0x03, 0x76, 0x87]
+        );
+
+        // Write 0x0003 to register 1 of slave 0x11
+        let p = PacketModbus::packet(0x11, Function::WriteSingle, single(1, 3));
+        assert_eq!(
+            p.to_bytes(),
+            [0x11, 0x06, 0x00, 0x01, 0x00, 0x03, 0x9A, 0x9B]
+        );
+
+        let p = PacketModbus::packet(
+            0x11,
+            Function::WriteMultiple,
+            multiple(1, &[0x000A, 0x0102]),
+        );
+        assert_eq!(
+            p.to_bytes()[..11],
+            [0x11, 0x10, 0x00, 0x01, 0x00, 0x02, 0x04, 0x00, 0x0A, 0x01, 0x02]
+        );
+    }
+
+    #[test]
+    fn parse_status_packet() {
+        let mut bytes = vec![0x11, 0x03, 0x04, 0x02, 0x2B, 0x00, 0x64];
+        bytes.extend(crc(&bytes).to_le_bytes());
+        let sp = StatusPacketModbus::from_bytes(&bytes, 0x11).unwrap();
+        assert_eq!(sp.params, [0x02, 0x2B, 0x00, 0x64]);
+        assert_eq!(PacketModbus::get_payload_size(&bytes[..3]).unwrap(), 6);
+
+        assert!(StatusPacketModbus::from_bytes(&bytes, 0x12).is_err());
+
+        let mut exception = vec![0x11, 0x83, 0x02];
+        exception.extend(crc(&exception).to_le_bytes());
+        let err = StatusPacketModbus::from_bytes(&exception, 0x11).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CommunicationErrorKind>(),
+            Some(CommunicationErrorKind::ModbusException(2))
+        ));
+    }
+
+    #[test]
+    fn servo_table() {
+        use crate::{
+            dynamixel_protocol::mock::{FakeServosModbus, MockSerialPort},
+            servo::{dynamixel::xl330, ServoKind},
+            DynamixelProtocolHandler,
+        };
+
+        let dph = DynamixelProtocolHandler::modbus();
+        let mut port = MockSerialPort::new(FakeServosModbus::default().with_servo(1, 1200));
+
+        assert!(dph.ping(&mut port, 1).unwrap());
+        assert!(!dph.ping(&mut port, 2).unwrap());
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 1).unwrap(),
+            ServoKind::dynamixel_XL330M288
+        );
+
+        // Aligned (16), single register (06) and unaligned writes sharing a register
+        xl330::write_raw_goal_position(&dph, &mut port, 1, -70000).unwrap();
+        xl330::write_torque_enable(&dph, &mut port, 1, true).unwrap();
+        xl330::write_led(&dph, &mut port, 1, 1).unwrap();
+        xl330::write_return_delay_time(&dph, &mut port, 1, 250).unwrap();
+        assert_eq!(
+            xl330::read_raw_goal_position(&dph, &mut port, 1).unwrap(),
+            -70000
+        );
+        assert!(xl330::read_torque_enable(&dph, &mut port, 1).unwrap());
+        assert_eq!(xl330::read_led(&dph, &mut port, 1).unwrap(), 1);
+        assert_eq!(
+            xl330::read_return_delay_time(&dph, &mut port, 1).unwrap(),
+            250
+        );
+        assert_eq!(xl330::read_id(&dph, &mut port, 1).unwrap(), 1);
+        assert_eq!(dph.read(&mut port, 1, 64, 2).unwrap(), [1, 1]);
+
+        let mut c = xl330::Xl330Controller::new()
+            .with_serial_port(Box::new(port))
+            .with_protocol_modbus();
+        c.write_raw_goal_position(1, 1234).unwrap();
+        assert_eq!(c.read_raw_goal_position(1).unwrap(), [1234]);
+        assert!(c.sync_read_raw_goal_position(&[1]).is_err());
+    }
+
+    #[test]
+    fn exceptions_and_limits() {
+        use crate::{
+            dynamixel_protocol::mock::{FakeServosModbus, MockSerialPort},
+            DynamixelProtocolHandler,
+        };
+
+        let dph = DynamixelProtocolHandler::modbus();
+        let mut port = MockSerialPort::new(FakeServosModbus::default().with_servo(1, 1200));
+
+        let err = dph.read(&mut port, 1, 1022, 4).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CommunicationErrorKind>(),
+            Some(CommunicationErrorKind::ModbusException(2))
+        ));
+        let err = dph
+            .read(&mut port, 1, 0, 2 * MAX_READ_WORDS + 1)
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CommunicationErrorKind>(),
+            Some(CommunicationErrorKind::AddressOutOfRange(..))
+        ));
+        assert!(dph.write(&mut port, 1, 0, &[0; 248]).is_err());
+        assert_eq!(
+            dph.read(&mut port, 1, 0, 2 * MAX_READ_WORDS).unwrap().len(),
+            250
+        );
+        assert!(dph.reboot(&mut port, 1).is_err());
+    }
+
+    #[test]
+    fn word_ranges() {
+        assert_eq!(words(0, 2), (0, 1));
+        assert_eq!(words(1, 1), (0, 1));
+        assert_eq!(words(1, 2), (0, 2));
+        assert_eq!(words(64, 4), (32, 2));
+        assert_eq!(words(65, 4), (32, 3));
+    }
+}
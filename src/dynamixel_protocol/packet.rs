@@ -17,12 +17,12 @@ pub trait Packet {
         conserve_id_and_baudrate: bool,
     ) -> Box<dyn InstructionPacket<Self>>;
 
-    fn read_packet(id: u8, addr: u8, length: u8) -> Box<dyn InstructionPacket<Self>>;
-    fn write_packet(id: u8, addr: u8, data: &[u8]) -> Box<dyn InstructionPacket<Self>>;
-    fn sync_read_packet(ids: &[u8], addr: u8, length: u8) -> Box<dyn InstructionPacket<Self>>;
+    fn read_packet(id: u8, addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>>;
+    fn write_packet(id: u8, addr: u16, data: &[u8]) -> Box<dyn InstructionPacket<Self>>;
+    fn sync_read_packet(ids: &[u8], addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>>;
     fn sync_write_packet(
         ids: &[u8],
-        addr: u8,
+        addr: u16,
         data: &[Vec<u8>],
     ) -> Box<dyn InstructionPacket<Self>>;
 
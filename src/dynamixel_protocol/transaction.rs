@@ -0,0 +1,448 @@
+//! Split-phase transactions: the instruction is sent right away and its status packets are collected later,
+//! leaving the caller free to work while the servos answer.
+//!
+//! A pending transaction only blocks the requests of its own handler (and of the copies made with
+//! [DynamixelProtocolHandler::deadline]). Controllers sharing a [SharedBus](crate::bus::SharedBus), or the workers of a
+//! [BusManager](crate::bus::BusManager), use their own handlers: they are not protected from the replies of a
+//! transaction sent on the same bus, which must be collected before the bus is released to them.
+
+use std::{
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use serialport::SerialPort;
+
+use crate::Result;
+
+use super::{
+    check_v1_range, packet::Packet, v1::PacketV1, v2::PacketV2, CommunicationErrorKind,
+    DeadlinePort, DynamixelProtocolHandler, Protocol, ProtocolKind,
+};
+
+/// Sequence numbers of the transactions, 0 meaning no transaction is pending
+static NEXT_TRANSACTION: AtomicU64 = AtomicU64::new(1);
+
+/// Instruction sent by a [DynamixelProtocolHandler], whose status packets are yet to be read.
+///
+/// Collect them with [DynamixelProtocolHandler::poll] or [DynamixelProtocolHandler::wait]. Dropping the
+/// transaction cancels it: the handler accepts new requests, the next one first draining the late replies
+/// (until the deadline of the transaction, or until no byte is received for the port timeout).
+#[derive(Debug)]
+#[must_use = "the replies must be collected with `poll` or `wait`"]
+pub struct PendingTransaction {
+    seq: u64,
+    /// Pending transaction of the handler which sent it
+    pending: Arc<AtomicU64>,
+    /// Cancelled transaction of the handler which sent it
+    late_replies: Arc<Mutex<Option<LateReplies>>>,
+    ids: Vec<u8>,
+    replies: Vec<Vec<u8>>,
+    /// Received bytes, not forming a complete status packet yet
+    buffer: Vec<u8>,
+    /// Time by which all the replies are expected, if the call sending it had a deadline
+    deadline: Option<Instant>,
+}
+
+impl PendingTransaction {
+    /// Ids of the motors expected to answer, in the order of the replies
+    pub fn ids(&self) -> &[u8] {
+        &self.ids
+    }
+
+    /// Whether all the replies were received
+    pub fn is_complete(&self) -> bool {
+        self.replies.len() == self.ids.len()
+    }
+}
+
+impl Drop for PendingTransaction {
+    fn drop(&mut self) {
+        // Recorded before releasing the handler, so that its next request drains the replies still in flight
+        let mut late_replies = self.late_replies.lock().unwrap();
+        if self.pending.load(Ordering::SeqCst) == self.seq {
+            *late_replies = Some(LateReplies {
+                deadline: self.deadline,
+            });
+            self.pending.store(0, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Replies of a cancelled transaction, which may still be received
+#[derive(Debug, Clone, Copy)]
+pub(super) struct LateReplies {
+    /// Deadline of the transaction, or `None` to drain until no byte is received for the port timeout
+    deadline: Option<Instant>,
+}
+
+#[derive(Clone, Copy)]
+enum Request<'a> {
+    Read {
+        id: u8,
+        addr: u16,
+        length: u16,
+    },
+    SyncRead {
+        ids: &'a [u8],
+        addr: u16,
+        length: u16,
+    },
+}
+
+impl DynamixelProtocolHandler {
+    /// Sends a read instruction without waiting for the status packet.
+    ///
+    /// The register bytes are then collected with [DynamixelProtocolHandler::poll] or
+    /// [DynamixelProtocolHandler::wait]. Only supported by protocols v1 and v2.
+    pub fn send_read(
+        &self,
+        serial_port: &mut dyn SerialPort,
+        id: u8,
+        addr: u16,
+        length: u16,
+    ) -> Result<PendingTransaction> {
+        self.send(serial_port, Request::Read { id, addr, length })
+    }
+
+    /// Sends a sync read instruction without waiting for the status packets.
+    ///
+    /// A single transaction can be pending per handler: any other request (split-phase or not) fails with
+    /// [CommunicationErrorKind::TransactionPending] until it is collected or dropped.
+    /// Only supported by protocols v1 and v2.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// use rustypot::DynamixelProtocolHandler;
+    /// use std::time::Duration;
+    ///
+    /// let mut serial_port = serialport::new("/dev/ttyACM0", 1_000_000)
+    ///     .timeout(Duration::from_millis(10))
+    ///     .open()
+    ///     .expect("Failed to open port");
+    ///
+    /// let dph = DynamixelProtocolHandler::v2();
+    ///
+    /// // Present positions of motors 1 to 3, collected once the next command is computed
+    /// let transaction = dph
+    ///     .send_sync_read(serial_port.as_mut(), &[1, 2, 3], 132, 4)
+    ///     .expect("Communication error");
+    /// // ...
+    /// let positions = dph
+    ///     .wait(serial_port.as_mut(), transaction)
+    ///     .expect("Communication error");
+    /// assert_eq!(positions.len(), 3);
+    /// ```
+    pub fn send_sync_read(
+        &self,
+        serial_port: &mut dyn SerialPort,
+        ids: &[u8],
+        addr: u16,
+        length: u16,
+    ) -> Result<PendingTransaction> {
+        self.send(serial_port, Request::SyncRead { ids, addr, length })
+    }
+
+    /// Reads the status packets received so far, without blocking.
+    ///
+    /// Returns the parameters of the replies (in the order of [PendingTransaction::ids]) once all of them were
+    /// received, `None` otherwise. A communication error ends the transaction.
+    pub fn poll(
+        &self,
+        serial_port: &mut dyn SerialPort,
+        transaction: &mut PendingTransaction,
+    ) -> Result<Option<Vec<Vec<u8>>>> {
+        self.check_transaction(transaction)?;
+        let res = self.collect(serial_port, transaction);
+        if !matches!(res, Ok(None)) {
+            self.pending.store(0, Ordering::SeqCst);
+        }
+        res
+    }
+
+    /// Waits for all the status packets of the transaction, until the deadline of the call sending it
+    /// (without deadline, each read waits for the port timeout).
+    ///
+    /// Returns their parameters in the order of [PendingTransaction::ids].
+    pub fn wait(
+        &self,
+        serial_port: &mut dyn SerialPort,
+        mut transaction: PendingTransaction,
+    ) -> Result<Vec<Vec<u8>>> {
+        let serial_port: &mut dyn SerialPort =
+            &mut DeadlinePort::new(serial_port, transaction.deadline);
+        loop {
+            if let Some(replies) = self.poll(serial_port, &mut transaction)? {
+                return Ok(replies);
+            }
+
+            let mut byte = [0u8];
+            if let Err(e) = serial_port.read_exact(&mut byte) {
+                self.pending.store(0, Ordering::SeqCst);
+                return Err(e.into());
+            }
+            transaction.buffer.push(byte[0]);
+        }
+    }
+
+    /// Fails while a split-phase transaction is pending, its replies would be mistaken for the next ones.
+    ///
+    /// Once idle, the late replies of a cancelled transaction are drained from `port`.
+    pub(super) fn check_idle(&self, port: &mut dyn SerialPort) -> Result<()> {
+        match self.pending.load(Ordering::SeqCst) {
+            0 => self.drain_late_replies(port),
+            _ => Err(Box::new(CommunicationErrorKind::TransactionPending)),
+        }
+    }
+
+    /// Reads and discards the replies of a cancelled transaction
+    fn drain_late_replies(&self, port: &mut dyn SerialPort) -> Result<()> {
+        let Some(late) = self.late_replies.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        let port = &mut DeadlinePort::new(port, late.deadline);
+        let mut buf = [0u8; 64];
+        loop {
+            match port.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => log::debug!("<<< late {:?}", &buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => return Ok(()),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn check_transaction(&self, transaction: &PendingTransaction) -> Result<()> {
+        match Arc::ptr_eq(&self.pending, &transaction.pending)
+            && self.pending.load(Ordering::SeqCst) == transaction.seq
+        {
+            true => Ok(()),
+            false => Err(Box::new(CommunicationErrorKind::NoPendingTransaction)),
+        }
+    }
+
+    fn send(&self, port: &mut dyn SerialPort, request: Request) -> Result<PendingTransaction> {
+        self.drain_late_replies(port)?;
+
+        // The handler is reserved before sending, so that no other request can be sent in between
+        let seq = NEXT_TRANSACTION.fetch_add(1, Ordering::SeqCst);
+        if self
+            .pending
+            .compare_exchange(0, seq, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(Box::new(CommunicationErrorKind::TransactionPending));
+        }
+
+        let (ids, sent, length) = match request {
+            Request::Read { id, length, .. } => (vec![id], 4, length),
+            Request::SyncRead { ids, length, .. } => (ids.to_vec(), 4 + ids.len(), length),
+        };
+        let deadline = self
+            .call_deadline(port, sent, length.into(), ids.len())
+            .map(|d| Instant::now() + d);
+        let port = &mut DeadlinePort::new(port, deadline);
+        let port = &mut self.echoing(port);
+        let sent: Result<()> = match &self.protocol {
+            ProtocolKind::V1(p) => {
+                let (Request::Read { addr, length, .. } | Request::SyncRead { addr, length, .. }) =
+                    request;
+                check_v1_range(addr, length).and_then(|_| send_request(p, port, request))
+            }
+            ProtocolKind::V2(p) => send_request(p, port, request),
+            ProtocolKind::Lx16a(_) | ProtocolKind::Herkulex(_) | ProtocolKind::Modbus(_) => {
+                Err(Box::new(CommunicationErrorKind::Unsupported))
+            }
+        };
+        if let Err(e) = sent {
+            let _ = self
+                .pending
+                .compare_exchange(seq, 0, Ordering::SeqCst, Ordering::SeqCst);
+            return Err(e);
+        }
+
+        Ok(PendingTransaction {
+            seq,
+            pending: self.pending.clone(),
+            late_replies: self.late_replies.clone(),
+            ids,
+            replies: Vec::new(),
+            buffer: Vec::new(),
+            deadline,
+        })
+    }
+
+    fn collect(
+        &self,
+        port: &mut dyn SerialPort,
+        transaction: &mut PendingTransaction,
+    ) -> Result<Option<Vec<Vec<u8>>>> {
+        let n = port.bytes_to_read()? as usize;
+        if n > 0 {
+            let mut received = vec![0u8; n];
+            port.read_exact(&mut received)?;
+            transaction.buffer.extend(received);
+        }
+
+        while !transaction.is_complete() {
+            let id = transaction.ids[transaction.replies.len()];
+            let reply: Result<_> = match &self.protocol {
+                ProtocolKind::V1(_) => take_status_packet::<PacketV1>(&mut transaction.buffer, id),
+                ProtocolKind::V2(_) => take_status_packet::<PacketV2>(&mut transaction.buffer, id),
+                ProtocolKind::Lx16a(_) | ProtocolKind::Herkulex(_) | ProtocolKind::Modbus(_) => {
+                    Err(Box::new(CommunicationErrorKind::Unsupported))
+                }
+            };
+            match reply? {
+                Some(params) => transaction.replies.push(params),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(std::mem::take(&mut transaction.replies)))
+    }
+}
+
+fn send_request<P: Packet>(
+    protocol: &impl Protocol<P>,
+    port: &mut dyn SerialPort,
+    request: Request,
+) -> Result<()> {
+    let packet = match request {
+        Request::Read { id, addr, length } => P::read_packet(id, addr, length),
+        Request::SyncRead { ids, addr, length } => P::sync_read_packet(ids, addr, length),
+    };
+    protocol.send_instruction_packet(port, packet.as_ref())
+}
+
+/// Removes the status packet at the start of `buffer` and returns its parameters, once all its bytes were received
+fn take_status_packet<P: Packet>(buffer: &mut Vec<u8>, sender_id: u8) -> Result<Option<Vec<u8>>> {
+    if buffer.len() < P::HEADER_SIZE {
+        return Ok(None);
+    }
+    let size = P::HEADER_SIZE + P::get_payload_size(&buffer[..P::HEADER_SIZE])?;
+    if buffer.len() < size {
+        return Ok(None);
+    }
+
+    let data: Vec<u8> = buffer.drain(..size).collect();
+    log::debug!("<<< {data:?}");
+    Ok(Some(P::status_packet(&data, sender_id)?.params().to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamixel_protocol::mock::{FakeServosV2, MockSerialPort};
+
+    fn bus() -> MockSerialPort {
+        let mut servos = FakeServosV2::default()
+            .with_servo(1, 1200)
+            .with_servo(2, 1200);
+        servos.servos[0].write(132, &[1, 0, 0, 0]);
+        servos.servos[1].write(132, &[2, 0, 0, 0]);
+        MockSerialPort::new(servos)
+    }
+
+    fn error_kind(e: Box<dyn std::error::Error>) -> CommunicationErrorKind {
+        *e.downcast::<CommunicationErrorKind>().unwrap()
+    }
+
+    #[test]
+    fn split_phase_sync_read() {
+        let mut port = bus();
+        let dph = DynamixelProtocolHandler::v2();
+
+        let mut transaction = dph.send_sync_read(&mut port, &[2, 1], 132, 4).unwrap();
+        assert_eq!(transaction.ids(), [2, 1]);
+        let replies = dph.poll(&mut port, &mut transaction).unwrap().unwrap();
+        assert_eq!(replies, [vec![2, 0, 0, 0], vec![1, 0, 0, 0]]);
+
+        // Completed: the handler is free again and the token is stale
+        assert!(matches!(
+            error_kind(dph.poll(&mut port, &mut transaction).unwrap_err()),
+            CommunicationErrorKind::NoPendingTransaction
+        ));
+        let transaction = dph.send_read(&mut port, 1, 132, 4).unwrap();
+        assert_eq!(
+            dph.wait(&mut port, transaction).unwrap(),
+            [vec![1, 0, 0, 0]]
+        );
+        assert_eq!(dph.read(&mut port, 2, 132, 4).unwrap(), [2, 0, 0, 0]);
+    }
+
+    #[test]
+    fn partial_replies() {
+        let mut port = bus();
+        let dph = DynamixelProtocolHandler::v2();
+
+        // Motor 3 does not answer: polling keeps waiting, waiting times out
+        let mut transaction = dph.send_sync_read(&mut port, &[1, 3], 132, 4).unwrap();
+        assert!(dph.poll(&mut port, &mut transaction).unwrap().is_none());
+        assert!(!transaction.is_complete());
+        assert!(dph.poll(&mut port, &mut transaction).unwrap().is_none());
+        assert!(dph.wait(&mut port, transaction).is_err());
+
+        // The failure ended the transaction
+        assert_eq!(dph.read(&mut port, 1, 132, 4).unwrap(), [1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn cancelled_replies_are_drained() {
+        // The replies are still on the wire: only a blocking read receives them
+        let mut port = bus().in_flight();
+        let dph = DynamixelProtocolHandler::v2();
+
+        let transaction = dph.send_sync_read(&mut port, &[1, 2], 132, 4).unwrap();
+        drop(transaction);
+        assert_eq!(dph.read(&mut port, 2, 132, 4).unwrap(), [2, 0, 0, 0]);
+
+        let transaction = dph.send_read(&mut port, 1, 132, 4).unwrap();
+        drop(transaction);
+        let transaction = dph.send_read(&mut port, 2, 132, 4).unwrap();
+        assert_eq!(
+            dph.wait(&mut port, transaction).unwrap(),
+            [vec![2, 0, 0, 0]]
+        );
+    }
+
+    #[test]
+    fn misuse_is_reported() {
+        let mut port = bus();
+        let dph = DynamixelProtocolHandler::v2();
+        let other = DynamixelProtocolHandler::v2();
+
+        let mut transaction = dph.send_sync_read(&mut port, &[1, 2], 132, 4).unwrap();
+        assert!(matches!(
+            error_kind(dph.send_read(&mut port, 1, 132, 4).unwrap_err()),
+            CommunicationErrorKind::TransactionPending
+        ));
+        assert!(matches!(
+            error_kind(dph.read(&mut port, 1, 132, 4).unwrap_err()),
+            CommunicationErrorKind::TransactionPending
+        ));
+        assert!(matches!(
+            error_kind(other.poll(&mut port, &mut transaction).unwrap_err()),
+            CommunicationErrorKind::NoPendingTransaction
+        ));
+        // The rejected requests did not disturb the pending replies
+        assert_eq!(dph.wait(&mut port, transaction).unwrap().len(), 2);
+
+        // Dropping a transaction cancels it
+        let transaction = dph.send_read(&mut port, 1, 132, 4).unwrap();
+        drop(transaction);
+        assert_eq!(dph.read(&mut port, 2, 132, 4).unwrap(), [2, 0, 0, 0]);
+
+        // A failed send releases the handler
+        let herkulex = DynamixelProtocolHandler::herkulex();
+        assert!(matches!(
+            error_kind(herkulex.send_read(&mut port, 1, 0, 1).unwrap_err()),
+            CommunicationErrorKind::Unsupported
+        ));
+        assert!(herkulex.check_idle(&mut port).is_ok());
+    }
+}
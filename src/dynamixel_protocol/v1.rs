@@ -43,32 +43,32 @@ impl Packet for PacketV1 {
         })
     }
 
-    fn read_packet(id: u8, addr: u8, length: u8) -> Box<dyn InstructionPacket<Self>> {
+    fn read_packet(id: u8, addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV1 {
             id,
             instruction: InstructionKindV1::Read,
-            params: vec![addr, length],
+            params: vec![addr as u8, length as u8],
         })
     }
 
-    fn write_packet(id: u8, addr: u8, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
+    fn write_packet(id: u8, addr: u16, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV1 {
             id,
             instruction: InstructionKindV1::Write,
             params: {
-                let mut params = vec![addr];
+                let mut params = vec![addr as u8];
                 params.extend(data);
                 params
             },
         })
     }
 
-    fn sync_read_packet(ids: &[u8], addr: u8, length: u8) -> Box<dyn InstructionPacket<Self>> {
+    fn sync_read_packet(ids: &[u8], addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV1 {
             id: BROADCAST_ID,
             instruction: InstructionKindV1::SyncRead,
             params: {
-                let mut params = vec![addr, length];
+                let mut params = vec![addr as u8, length as u8];
                 params.extend(ids);
                 params
             },
@@ -77,14 +77,14 @@ impl Packet for PacketV1 {
 
     fn sync_write_packet(
         ids: &[u8],
-        addr: u8,
+        addr: u16,
         data: &[Vec<u8>],
     ) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV1 {
             id: BROADCAST_ID,
             instruction: InstructionKindV1::SyncWrite,
             params: {
-                let mut params = vec![addr];
+                let mut params = vec![addr as u8];
                 let values: Vec<u8> = ids
                     .iter()
                     .zip(data.iter())
@@ -262,7 +262,7 @@ impl InstructionKindV1 {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct V1;
 impl Protocol<PacketV1> for V1 {}
 
@@ -1,3 +1,5 @@
+use serialport::SerialPort;
+
 use crate::Result;
 
 use super::{
@@ -5,10 +7,17 @@ use super::{
     CommunicationErrorKind, Protocol,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct V2;
 impl Protocol<PacketV2> for V2 {}
 
+impl V2 {
+    pub(crate) fn clear_multi_turn(&self, port: &mut dyn SerialPort, id: u8) -> Result<()> {
+        self.send_instruction_packet(port, PacketV2::clear_multi_turn_packet(id).as_ref())?;
+        self.read_status_packet(port, id).map(|_| ())
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct PacketV2;
 impl Packet for PacketV2 {
@@ -67,40 +76,40 @@ impl Packet for PacketV2 {
         })
     }
 
-    fn read_packet(id: u8, addr: u8, length: u8) -> Box<dyn InstructionPacket<Self>> {
+    fn read_packet(id: u8, addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV2 {
             id,
             instruction: InstructionKindV2::Read,
             params: {
                 let mut params = Vec::new();
-                params.extend((addr as u16).to_le_bytes());
-                params.extend((length as u16).to_le_bytes());
+                params.extend(addr.to_le_bytes());
+                params.extend(length.to_le_bytes());
                 params
             },
         })
     }
 
-    fn write_packet(id: u8, addr: u8, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
+    fn write_packet(id: u8, addr: u16, data: &[u8]) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV2 {
             id,
             instruction: InstructionKindV2::Write,
             params: {
                 let mut params = Vec::new();
-                params.extend((addr as u16).to_le_bytes());
+                params.extend(addr.to_le_bytes());
                 params.extend(data);
                 params
             },
         })
     }
 
-    fn sync_read_packet(ids: &[u8], addr: u8, length: u8) -> Box<dyn InstructionPacket<Self>> {
+    fn sync_read_packet(ids: &[u8], addr: u16, length: u16) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV2 {
             id: BROADCAST_ID,
             instruction: InstructionKindV2::SyncRead,
             params: {
                 let mut params = Vec::new();
-                params.extend((addr as u16).to_le_bytes());
-                params.extend((length as u16).to_le_bytes());
+                params.extend(addr.to_le_bytes());
+                params.extend(length.to_le_bytes());
                 params.extend(ids);
                 params
             },
@@ -109,7 +118,7 @@ impl Packet for PacketV2 {
 
     fn sync_write_packet(
         ids: &[u8],
-        addr: u8,
+        addr: u16,
         data: &[Vec<u8>],
     ) -> Box<dyn InstructionPacket<Self>> {
         Box::new(InstructionPacketV2 {
@@ -117,7 +126,7 @@ impl Packet for PacketV2 {
             instruction: InstructionKindV2::SyncWrite,
             params: {
                 let mut params = Vec::new();
-                params.extend((addr as u16).to_le_bytes());
+                params.extend(addr.to_le_bytes());
                 params.extend((data[0].len() as u16).to_le_bytes());
 
                 for (&id, value) in ids.iter().zip(data) {
@@ -135,6 +144,17 @@ impl Packet for PacketV2 {
     }
 }
 
+impl PacketV2 {
+    /// CLEAR instruction resetting the multi-turn revolution count
+    fn clear_multi_turn_packet(id: u8) -> Box<dyn InstructionPacket<Self>> {
+        Box::new(InstructionPacketV2 {
+            id,
+            instruction: InstructionKindV2::Clear,
+            params: vec![0x01, 0x44, 0x58, 0x4C, 0x22],
+        })
+    }
+}
+
 #[derive(Debug)]
 struct InstructionPacketV2 {
     id: u8,
@@ -244,6 +264,7 @@ pub(crate) enum InstructionKindV2 {
     Write,
     FactoryReset,
     Reboot,
+    Clear,
     SyncRead,
     SyncWrite,
 }
@@ -256,6 +277,7 @@ impl InstructionKindV2 {
             InstructionKindV2::Write => 0x03,
             InstructionKindV2::FactoryReset => 0x06,
             InstructionKindV2::Reboot => 0x08,
+            InstructionKindV2::Clear => 0x10,
             InstructionKindV2::SyncRead => 0x82,
             InstructionKindV2::SyncWrite => 0x83,
         }
@@ -294,7 +316,7 @@ impl DynamixelErrorV2 {
     }
 }
 
-fn crc(data: &[u8]) -> u16 {
+pub(crate) fn crc(data: &[u8]) -> u16 {
     let mut crc_accum: u16 = 0;
 
     for byte in data {
@@ -363,6 +385,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn create_clear_multi_turn_packet() {
+        let p = PacketV2::clear_multi_turn_packet(1);
+        let bytes = p.to_bytes();
+        assert_eq!(
+            bytes,
+            [0xff, 0xff, 0xfd, 0x0, 0x1, 0x8, 0x0, 0x10, 0x1, 0x44, 0x58, 0x4c, 0x22, 0xb1, 0xdc]
+        );
+    }
+
     #[test]
     fn create_read_packet() {
         let p = PacketV2::read_packet(1, 0x2B, 2);
@@ -3,7 +3,7 @@
 //! ## Feature Overview
 //!
 //! * Relies on [serialport] for serial communication
-//! * Support for dynamixel protocol v1 and v2 (both can be used on the same io)
+//! * Support for dynamixel protocol v1 and v2 (both can be used on the same io), the Hiwonder LX-16A and Herkulex protocols, and Modbus-RTU
 //! * Support for sync read and sync write operations
 //! * Easy support for new type of motors (register definition through macros)
 //! * Pure Rust
@@ -55,11 +55,27 @@
 //!
 //! c.sync_write_goal_position(&vec![1, 2], &vec![0.0, 90.0_f64.to_radians()]).unwrap();
 //! ```
+//!
+//! Or in a single call, with `Sts3215Controller::open("/dev/ttyUSB0", 1_000_000, Duration::from_millis(1000))`.
+//! A controller lacking its serial port or its protocol has no communication methods:
+//! ```compile_fail
+//! use rustypot::servo::feetech::sts3215::Sts3215Controller;
+//!
+//! let serial_port = serialport::new("/dev/ttyUSB0", 1_000_000).open().unwrap();
+//! let mut c = Sts3215Controller::new().with_serial_port(serial_port);
+//!
+//! c.read_present_position(1);
+//! ```
 
+pub mod bus;
+pub mod reconnect;
 pub mod servo;
 
 mod dynamixel_protocol;
-pub use dynamixel_protocol::{CommunicationErrorKind, DynamixelProtocolHandler};
+pub use dynamixel_protocol::{
+    CommunicationErrorKind, DynamixelProtocolHandler, HerkulexJog, PendingTransaction, PingStatus,
+    DEFAULT_DEADLINE_MARGIN, DEFAULT_RETURN_DELAY, HERKULEX_EEP_BANK, SLOW_INSTRUCTION_DEADLINE,
+};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
@@ -74,6 +90,7 @@ fn rustypot(m: &Bound<'_, PyModule>) -> PyResult<()> {
     pyo3_log::init();
 
     servo::register_class(m)?;
+    servo::register_types(m)?;
 
     Ok(())
 }
@@ -0,0 +1,493 @@
+//! Serial port reopening itself after a disconnection.
+//!
+//! A [ReconnectingPort] remembers how to open its port (path and settings). When the port fails with a fatal
+//! io error (e.g. the USB adapter is unplugged or browns out), it is dropped and the failing call returns the error.
+//! The next calls try to reopen it, waiting longer and longer between attempts (exponential backoff), and
+//! communication resumes as soon as the port is back, with the settings changed meanwhile (e.g. a new baudrate).
+//!
+//! As a [SerialPort], it can be given to any controller. Its [ConnectionMonitor] stays available to watch the
+//! connection state.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{
+//!     reconnect::{ConnectionState, ReconnectingPort},
+//!     servo::dynamixel::xl330::Xl330Controller,
+//! };
+//! use std::time::Duration;
+//!
+//! let port = ReconnectingPort::open("/dev/ttyUSB0", 1_000_000, Duration::from_millis(10)).unwrap();
+//! let monitor = port.monitor();
+//! monitor.on_state_change(|state| println!("Bus {state:?}"));
+//!
+//! let mut c = Xl330Controller::new()
+//!     .with_serial_port(Box::new(port))
+//!     .with_protocol_v2();
+//!
+//! loop {
+//!     if monitor.state() == ConnectionState::Connected {
+//!         let _ = c.read_present_position(1);
+//!     }
+//!     std::thread::sleep(Duration::from_millis(10));
+//! }
+//! ```
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+/// First delay before reopening a disconnected port
+pub const DEFAULT_MIN_BACKOFF: Duration = Duration::from_millis(100);
+/// Longest delay between two reopening attempts
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+/// Opens the underlying port, on creation and after each disconnection
+pub type Opener = Box<dyn FnMut() -> serialport::Result<Box<dyn SerialPort>> + Send>;
+
+/// State of a [ReconnectingPort]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The port is open
+    Connected,
+    /// The port was lost, it is reopened at the next call once the backoff delay has elapsed
+    Disconnected,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    disconnected: bool,
+    reconnections: usize,
+    callbacks: Vec<Box<dyn FnMut(ConnectionState) + Send>>,
+}
+
+/// Shared view of the connection state of a [ReconnectingPort], with callbacks on each change
+#[derive(Clone, Default)]
+pub struct ConnectionMonitor(Arc<Mutex<MonitorState>>);
+
+impl ConnectionMonitor {
+    pub fn state(&self) -> ConnectionState {
+        match self.0.lock().unwrap().disconnected {
+            true => ConnectionState::Disconnected,
+            false => ConnectionState::Connected,
+        }
+    }
+
+    /// Number of times the port was reopened
+    pub fn reconnections(&self) -> usize {
+        self.0.lock().unwrap().reconnections
+    }
+
+    /// Calls `callback` with the new state on each disconnection and reconnection.
+    ///
+    /// Callbacks run on the thread using the port, during the failing or reconnecting call.
+    pub fn on_state_change(&self, callback: impl FnMut(ConnectionState) + Send + 'static) {
+        self.0.lock().unwrap().callbacks.push(Box::new(callback));
+    }
+
+    fn set(&self, state: ConnectionState) {
+        let mut callbacks = {
+            let mut monitor = self.0.lock().unwrap();
+            let disconnected = state == ConnectionState::Disconnected;
+            if monitor.disconnected == disconnected {
+                return;
+            }
+            monitor.disconnected = disconnected;
+            if !disconnected {
+                monitor.reconnections += 1;
+            }
+            std::mem::take(&mut monitor.callbacks)
+        };
+
+        // Called without the lock, so the callbacks can query the monitor
+        for callback in callbacks.iter_mut() {
+            callback(state);
+        }
+        let mut monitor = self.0.lock().unwrap();
+        callbacks.append(&mut monitor.callbacks);
+        monitor.callbacks = callbacks;
+    }
+}
+
+/// Port settings, restored after reopening
+#[derive(Debug, Clone, Copy)]
+struct Settings {
+    baud_rate: u32,
+    timeout: Duration,
+    data_bits: DataBits,
+    flow_control: FlowControl,
+    parity: Parity,
+    stop_bits: StopBits,
+}
+
+impl Settings {
+    fn of(port: &dyn SerialPort) -> serialport::Result<Self> {
+        Ok(Settings {
+            baud_rate: port.baud_rate()?,
+            timeout: port.timeout(),
+            data_bits: port.data_bits()?,
+            flow_control: port.flow_control()?,
+            parity: port.parity()?,
+            stop_bits: port.stop_bits()?,
+        })
+    }
+
+    fn apply(&self, port: &mut dyn SerialPort) -> serialport::Result<()> {
+        port.set_baud_rate(self.baud_rate)?;
+        port.set_timeout(self.timeout)?;
+        port.set_data_bits(self.data_bits)?;
+        port.set_flow_control(self.flow_control)?;
+        port.set_parity(self.parity)?;
+        port.set_stop_bits(self.stop_bits)
+    }
+}
+
+struct Inner {
+    port: Option<Box<dyn SerialPort>>,
+    opener: Opener,
+    settings: Settings,
+    backoff: Duration,
+    next_attempt: Instant,
+}
+
+/// Serial port reopened with backoff after fatal io errors, see the [module](self) documentation
+pub struct ReconnectingPort {
+    name: Option<String>,
+    inner: Mutex<Inner>,
+    monitor: ConnectionMonitor,
+    min_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl ReconnectingPort {
+    /// Opens the serial port at `path`, and reopens it there after disconnections
+    pub fn open(path: &str, baudrate: u32, timeout: Duration) -> serialport::Result<Self> {
+        let builder = serialport::new(path, baudrate).timeout(timeout);
+        let mut port = Self::with_opener(Box::new(move || builder.clone().open()))?;
+        port.name = Some(path.to_string());
+        Ok(port)
+    }
+
+    /// Opens the port with `opener`, also used to reopen it (e.g. to reconnect a virtual transport)
+    pub fn with_opener(mut opener: Opener) -> serialport::Result<Self> {
+        let port = opener()?;
+        Ok(ReconnectingPort {
+            name: port.name(),
+            inner: Mutex::new(Inner {
+                settings: Settings::of(port.as_ref())?,
+                port: Some(port),
+                opener,
+                backoff: DEFAULT_MIN_BACKOFF,
+                next_attempt: Instant::now(),
+            }),
+            monitor: ConnectionMonitor::default(),
+            min_backoff: DEFAULT_MIN_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+        })
+    }
+
+    /// Sets the first and the longest delay between reopening attempts (doubling after each failure)
+    pub fn with_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.min_backoff = min;
+        self.max_backoff = max.max(min);
+        self.inner.get_mut().unwrap().backoff = min;
+        self
+    }
+
+    pub fn monitor(&self) -> ConnectionMonitor {
+        self.monitor.clone()
+    }
+
+    pub fn state(&self) -> ConnectionState {
+        self.monitor.state()
+    }
+
+    /// Runs `f` on the port, reopening it first if it was lost, and dropping it on fatal errors
+    fn with_port<T>(
+        &self,
+        f: impl FnOnce(&mut dyn SerialPort) -> serialport::Result<T>,
+    ) -> serialport::Result<T> {
+        let mut inner = self.inner.lock().unwrap();
+
+        if inner.port.is_none() {
+            self.reopen(&mut inner)?;
+        }
+
+        let res = f(inner.port.as_mut().unwrap().as_mut());
+        if let Err(e) = &res {
+            if is_fatal(e) {
+                log::warn!("Serial port {:?} lost: {e}", self.name);
+                inner.port = None;
+                inner.backoff = self.min_backoff;
+                inner.next_attempt = Instant::now() + inner.backoff;
+                self.monitor.set(ConnectionState::Disconnected);
+            }
+        }
+        res
+    }
+
+    fn reopen(&self, inner: &mut Inner) -> serialport::Result<()> {
+        let now = Instant::now();
+        if now < inner.next_attempt {
+            return Err(disconnected());
+        }
+
+        let settings = inner.settings;
+        match (inner.opener)().and_then(|mut port| {
+            settings.apply(port.as_mut())?;
+            Ok(port)
+        }) {
+            Ok(port) => {
+                log::info!("Serial port {:?} reconnected", self.name);
+                inner.port = Some(port);
+                inner.backoff = self.min_backoff;
+                self.monitor.set(ConnectionState::Connected);
+                Ok(())
+            }
+            Err(e) => {
+                log::debug!("Reopening serial port {:?} failed: {e}", self.name);
+                inner.next_attempt = now + inner.backoff;
+                inner.backoff = (2 * inner.backoff).min(self.max_backoff);
+                Err(disconnected())
+            }
+        }
+    }
+
+    /// Updates a setting, applied right away when connected and after reopening
+    fn set(
+        &mut self,
+        update: impl FnOnce(&mut Settings),
+        apply: impl FnOnce(&mut dyn SerialPort) -> serialport::Result<()>,
+    ) -> serialport::Result<()> {
+        let inner = self.inner.get_mut().unwrap();
+        update(&mut inner.settings);
+        match inner.port.as_mut() {
+            Some(port) => apply(port.as_mut()),
+            None => Ok(()),
+        }
+    }
+
+    fn settings(&self) -> Settings {
+        self.inner.lock().unwrap().settings
+    }
+}
+
+fn disconnected() -> serialport::Error {
+    serialport::Error::new(serialport::ErrorKind::NoDevice, "Serial port disconnected")
+}
+
+/// Whether the error means the port is gone (rather than a timeout or an invalid call)
+fn is_fatal(e: &serialport::Error) -> bool {
+    match e.kind() {
+        serialport::ErrorKind::NoDevice => true,
+        serialport::ErrorKind::Io(kind) => !matches!(
+            kind,
+            io::ErrorKind::TimedOut
+                | io::ErrorKind::WouldBlock
+                | io::ErrorKind::Interrupted
+                | io::ErrorKind::InvalidInput
+        ),
+        serialport::ErrorKind::InvalidInput | serialport::ErrorKind::Unknown => false,
+    }
+}
+
+impl io::Read for ReconnectingPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.with_port(|port| port.read(buf).map_err(serialport::Error::from))
+            .map_err(io::Error::from)
+    }
+}
+
+impl io::Write for ReconnectingPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.with_port(|port| port.write(buf).map_err(serialport::Error::from))
+            .map_err(io::Error::from)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.with_port(|port| port.flush().map_err(serialport::Error::from))
+            .map_err(io::Error::from)
+    }
+}
+
+impl SerialPort for ReconnectingPort {
+    fn name(&self) -> Option<String> {
+        self.name.clone()
+    }
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.settings().baud_rate)
+    }
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(self.settings().data_bits)
+    }
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(self.settings().flow_control)
+    }
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(self.settings().parity)
+    }
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(self.settings().stop_bits)
+    }
+    fn timeout(&self) -> Duration {
+        self.settings().timeout
+    }
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.set(|s| s.baud_rate = baud_rate, |p| p.set_baud_rate(baud_rate))
+    }
+    fn set_data_bits(&mut self, data_bits: DataBits) -> serialport::Result<()> {
+        self.set(|s| s.data_bits = data_bits, |p| p.set_data_bits(data_bits))
+    }
+    fn set_flow_control(&mut self, flow_control: FlowControl) -> serialport::Result<()> {
+        self.set(
+            |s| s.flow_control = flow_control,
+            |p| p.set_flow_control(flow_control),
+        )
+    }
+    fn set_parity(&mut self, parity: Parity) -> serialport::Result<()> {
+        self.set(|s| s.parity = parity, |p| p.set_parity(parity))
+    }
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> serialport::Result<()> {
+        self.set(|s| s.stop_bits = stop_bits, |p| p.set_stop_bits(stop_bits))
+    }
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.set(|s| s.timeout = timeout, |p| p.set_timeout(timeout))
+    }
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.with_port(|port| port.write_request_to_send(level))
+    }
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.with_port(|port| port.write_data_terminal_ready(level))
+    }
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        self.with_port(|port| port.read_clear_to_send())
+    }
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        self.with_port(|port| port.read_data_set_ready())
+    }
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        self.with_port(|port| port.read_ring_indicator())
+    }
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        self.with_port(|port| port.read_carrier_detect())
+    }
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        self.with_port(|port| port.bytes_to_read())
+    }
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        self.with_port(|port| port.bytes_to_write())
+    }
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        self.with_port(|port| port.clear(buffer_to_clear))
+    }
+    /// Clones the current underlying port, which does not reconnect
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        self.with_port(|port| port.try_clone())
+    }
+    fn set_break(&self) -> serialport::Result<()> {
+        self.with_port(|port| port.set_break())
+    }
+    fn clear_break(&self) -> serialport::Result<()> {
+        self.with_port(|port| port.clear_break())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV2, MockSerialPort},
+        servo::dynamixel::xl330::Xl330Controller,
+    };
+
+    /// Virtual transport to a bus of servos, which can be unplugged and plugged back
+    fn virtual_port(plugged: Arc<AtomicBool>) -> ReconnectingPort {
+        let bus = MockSerialPort::new(FakeServosV2::default().with_servo(1, 1200));
+        ReconnectingPort::with_opener(Box::new(move || match plugged.load(Ordering::SeqCst) {
+            true => Ok(Box::new(bus.with_link(plugged.clone()))),
+            false => Err(serialport::Error::new(
+                serialport::ErrorKind::NoDevice,
+                "No such device",
+            )),
+        }))
+        .unwrap()
+        .with_backoff(Duration::from_millis(5), Duration::from_millis(20))
+    }
+
+    #[test]
+    fn reconnects_after_disconnection() {
+        let plugged = Arc::new(AtomicBool::new(true));
+        let port = virtual_port(plugged.clone());
+        let monitor = port.monitor();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let log = changes.clone();
+        monitor.on_state_change(move |state| log.lock().unwrap().push(state));
+
+        let mut c = Xl330Controller::new()
+            .with_serial_port(Box::new(port))
+            .with_protocol_v2();
+        c.write_raw_goal_position(1, 1000).unwrap();
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+
+        plugged.store(false, Ordering::SeqCst);
+        assert!(c.read_raw_goal_position(1).is_err());
+        assert_eq!(monitor.state(), ConnectionState::Disconnected);
+        // Still unplugged, and then within the backoff delay
+        std::thread::sleep(Duration::from_millis(10));
+        assert!(c.ping(1).is_err());
+        plugged.store(true, Ordering::SeqCst);
+        assert!(c.ping(1).is_err());
+
+        std::thread::sleep(Duration::from_millis(25));
+        assert_eq!(c.read_raw_goal_position(1).unwrap(), [1000]);
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+        assert_eq!(monitor.reconnections(), 1);
+        assert_eq!(
+            *changes.lock().unwrap(),
+            [ConnectionState::Disconnected, ConnectionState::Connected]
+        );
+    }
+
+    #[test]
+    fn restores_settings() {
+        let plugged = Arc::new(AtomicBool::new(true));
+        let mut port = virtual_port(plugged.clone());
+
+        plugged.store(false, Ordering::SeqCst);
+        assert!(port.bytes_to_read().is_err());
+        port.set_baud_rate(57_600).unwrap();
+        assert_eq!(port.baud_rate().unwrap(), 57_600);
+
+        plugged.store(true, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(port.bytes_to_read().unwrap(), 0);
+        assert_eq!(
+            port.inner
+                .lock()
+                .unwrap()
+                .port
+                .as_ref()
+                .unwrap()
+                .baud_rate()
+                .unwrap(),
+            57_600
+        );
+    }
+
+    #[test]
+    fn timeouts_are_not_disconnections() {
+        let port = virtual_port(Arc::new(AtomicBool::new(true)));
+        let monitor = port.monitor();
+        let mut c = Xl330Controller::new()
+            .with_serial_port(Box::new(port))
+            .with_protocol_v2();
+
+        assert!(!c.ping(2).unwrap());
+        assert_eq!(monitor.state(), ConnectionState::Connected);
+    }
+}
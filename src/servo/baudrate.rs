@@ -0,0 +1,184 @@
+//! Baudrate change of a servo along with the serial port.
+//!
+//! Each family encodes the baudrate differently in its control table (Dynamixel v1 divider, X-series index, Feetech index...).
+//! The typed baudrate registers implement [BaudrateTable], which maps bits per second to the register code.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::servo::dynamixel::xl330::Xl330Controller;
+//! use std::time::Duration;
+//!
+//! let serial_port = serialport::new("/dev/ttyUSB0", 57_600)
+//!     .timeout(Duration::from_millis(10))
+//!     .open()
+//!     .unwrap();
+//! let mut c = Xl330Controller::new()
+//!     .with_protocol_v2()
+//!     .with_serial_port(serial_port);
+//!
+//! // Both the servo and the port now run at 1Mbps
+//! c.change_baudrate(1, 1_000_000).unwrap();
+//! ```
+
+use std::{fmt, thread, time::Duration};
+
+use crate::{
+    servo::{
+        control_table::{self, ControlTableError},
+        conversion::RegisterConversion,
+        ServoKind,
+    },
+    DynamixelProtocolHandler, Result,
+};
+
+/// Time left to the servo to switch to its new baudrate
+const SETTLE_DELAY: Duration = Duration::from_millis(20);
+
+/// Mapping between a baudrate register code and the corresponding speed
+pub trait BaudrateTable: Sized + Copy + 'static {
+    /// All supported rates
+    const ALL: &'static [Self];
+
+    /// Speed in bits per second
+    fn bps(&self) -> u32;
+
+    /// Finds the rate matching exactly `bps`
+    fn from_bps(bps: u32) -> Option<Self> {
+        Self::ALL.iter().find(|b| b.bps() == bps).copied()
+    }
+}
+
+/// Baudrate change errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaudrateError {
+    /// The requested rate is not supported by this servo model
+    Unsupported(u32),
+    /// The servo did not answer at the requested rate (and was set back to its original rate if `rolled_back`)
+    VerificationFailed { target: u32, rolled_back: bool },
+}
+
+impl fmt::Display for BaudrateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BaudrateError::Unsupported(bps) => write!(f, "Unsupported baudrate {bps}"),
+            BaudrateError::VerificationFailed {
+                target,
+                rolled_back,
+            } => {
+                write!(f, "Servo did not answer at {target}bps")?;
+                match rolled_back {
+                    true => write!(f, " (rolled back to the original baudrate)"),
+                    false => write!(f, " (rollback failed, the servo is unreachable)"),
+                }
+            }
+        }
+    }
+}
+impl std::error::Error for BaudrateError {}
+
+/// Changes the baudrate of the servo `id` and of the serial port.
+///
+/// `B` is the typed baudrate register of the servo model (e.g. `x_series::Baudrate`).
+/// The torque is disabled (and Feetech EEPROM unlocked) while writing, then the servo is pinged at the new rate.
+/// If it does not answer, both the servo and the port are set back to their original rate.
+pub fn change_baudrate<B>(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    target_bps: u32,
+) -> Result<()>
+where
+    B: BaudrateTable + RegisterConversion<RegisterType = u8, UsiType = B>,
+{
+    let target = B::from_bps(target_bps).ok_or(BaudrateError::Unsupported(target_bps))?;
+
+    let kind = ServoKind::identify(dph, serial_port, id)?;
+    let reg = control_table::baudrate_register(kind.registers())
+        .ok_or_else(|| ControlTableError::UnknownRegister("baudrate".to_string()))?;
+
+    let original_bps = serial_port.baud_rate()?;
+    let original_code = dph.read(serial_port, id, reg.addr, 1)?[0];
+    let target_code = B::encode(target);
+    if original_code == target_code && original_bps == target_bps {
+        return Ok(());
+    }
+
+    let previous_torque = control_table::disable_torque(dph, serial_port, id, kind)?;
+    control_table::unlock_eeprom(dph, serial_port, id, kind)?;
+
+    log::debug!("Servo {id}: baudrate {original_bps} -> {target_bps}");
+    dph.write(serial_port, id, reg.addr, &[target_code])?;
+    let verified = switch_port(dph, serial_port, id, target_bps)?;
+
+    let rolled_back = if verified {
+        false
+    } else {
+        log::warn!(
+            "Servo {id} did not answer at {target_bps}bps, rolling back to {original_bps}bps"
+        );
+        // The servo may have switched while the port can not reach it, try to set it back anyway
+        let _ = dph.write(serial_port, id, reg.addr, &[original_code]);
+        switch_port(dph, serial_port, id, original_bps)?
+    };
+
+    if verified || rolled_back {
+        control_table::lock_eeprom(dph, serial_port, id, kind)?;
+        if let (Some(torque), Some(previous)) = (
+            control_table::find(kind.registers(), "torque_enable"),
+            previous_torque,
+        ) {
+            torque.write(dph, serial_port, id, &previous)?;
+        }
+    }
+
+    match verified {
+        true => Ok(()),
+        false => Err(Box::new(BaudrateError::VerificationFailed {
+            target: target_bps,
+            rolled_back,
+        })),
+    }
+}
+
+/// Reconfigures the port and checks that the servo answers
+fn switch_port(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    bps: u32,
+) -> Result<bool> {
+    thread::sleep(SETTLE_DELAY);
+    serial_port.set_baud_rate(bps)?;
+    serial_port.clear(serialport::ClearBuffer::All)?;
+
+    dph.ping(serial_port, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo::{
+        dynamixel::{ax, mx, x_series, xl320},
+        feetech,
+    };
+
+    fn code<B>(bps: u32) -> Option<u8>
+    where
+        B: BaudrateTable + RegisterConversion<RegisterType = u8, UsiType = B>,
+    {
+        B::from_bps(bps).map(B::encode)
+    }
+
+    #[test]
+    fn model_specific_codes() {
+        assert_eq!(code::<mx::Baudrate>(1_000_000), Some(1));
+        assert_eq!(code::<mx::Baudrate>(3_000_000), Some(252));
+        assert_eq!(code::<ax::Baudrate>(1_000_000), Some(1));
+        assert_eq!(code::<ax::Baudrate>(3_000_000), None);
+        assert_eq!(code::<x_series::Baudrate>(1_000_000), Some(3));
+        assert_eq!(code::<xl320::Baudrate>(1_000_000), Some(3));
+        assert_eq!(code::<feetech::Baudrate>(1_000_000), Some(0));
+        assert_eq!(code::<feetech::Baudrate>(115_200), Some(4));
+        assert_eq!(code::<x_series::Baudrate>(500_000), None);
+    }
+}
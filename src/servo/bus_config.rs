@@ -0,0 +1,201 @@
+//! Declarative bus configuration.
+//!
+//! A [BusConfig] declares, for each servo id, its expected model and the desired raw value of some registers
+//! (gains, limits, return delay, operating mode, ...). It can be compared with the live values ([diff]) and
+//! applied, writing only what changed ([apply]).
+//!
+//! ## Example
+//! ```yaml
+//! servos:
+//!   - id: 1
+//!     model: dynamixel_XL330M288
+//!     registers:
+//!       return_delay_time: 0
+//!       operating_mode: 3
+//!       position_p_gain: 800
+//!   - id: 2
+//!     model: feetech_STS3215
+//!     registers:
+//!       p_coefficient: 32
+//! ```
+//!
+//! ```no_run
+//! use rustypot::{servo::bus_config::{self, BusConfig}, DynamixelProtocolHandler};
+//! use std::time::Duration;
+//!
+//! let mut serial_port = serialport::new("/dev/ttyUSB0", 1_000_000)
+//!     .timeout(Duration::from_millis(10))
+//!     .open()
+//!     .expect("Failed to open port");
+//! let dph = DynamixelProtocolHandler::v1();
+//!
+//! let config = BusConfig::load("robot.yaml").unwrap();
+//! for servo in bus_config::apply(&dph, serial_port.as_mut(), &config).unwrap() {
+//!     println!("{servo}");
+//! }
+//! ```
+
+use std::{collections::BTreeMap, fmt, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    servo::{
+        control_table::{self, ControlTableError, RegisterChange, RegisterValue},
+        file_format, ServoKind,
+    },
+    DynamixelProtocolHandler, Result,
+};
+
+/// Expected configuration of a bus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BusConfig {
+    pub servos: Vec<ServoConfig>,
+}
+
+/// Expected configuration of a servo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServoConfig {
+    pub id: u8,
+    /// Servo kind (see [ServoKind]), e.g. `dynamixel_XL330M288`
+    pub model: String,
+    /// Raw register values, by register name
+    #[serde(default)]
+    pub registers: BTreeMap<String, RegisterValue>,
+}
+
+/// Differences between the configuration and the live values of a servo
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServoDiff {
+    pub id: u8,
+    pub kind: ServoKind,
+    pub changes: Vec<RegisterChange>,
+}
+
+impl fmt::Display for ServoDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Motor {} ({:?}):", self.id, self.kind)?;
+        if self.changes.is_empty() {
+            write!(f, " up to date")?;
+        }
+        for change in self.changes.iter() {
+            write!(f, "\n  {change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl BusConfig {
+    /// Saves the configuration (format deduced from the file extension)
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        file_format::save(self, path)
+    }
+
+    /// Loads a configuration (format deduced from the file extension)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        file_format::load(path)
+    }
+}
+
+/// Compares the configuration with the live values, without writing anything.
+///
+/// Fails if a servo does not answer, if its model differs from the expected one,
+/// or if a register is unknown, read-only or out of range.
+pub fn diff(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    config: &BusConfig,
+) -> Result<Vec<ServoDiff>> {
+    config
+        .servos
+        .iter()
+        .map(|servo| servo_diff(dph, serial_port, servo))
+        .collect()
+}
+
+/// Applies the configuration, writing only the registers that differ from the live values.
+///
+/// All servos are checked first (see [diff]): nothing is written if any of them does not match the configuration.
+/// EEPROM safety is handled (see [control_table::write_changes]).
+///
+/// Returns the applied changes.
+pub fn apply(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    config: &BusConfig,
+) -> Result<Vec<ServoDiff>> {
+    let diffs = diff(dph, serial_port, config)?;
+    for servo in diffs.iter() {
+        control_table::write_changes(dph, serial_port, servo.id, servo.kind, &servo.changes)?;
+    }
+    Ok(diffs)
+}
+
+fn servo_diff(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    servo: &ServoConfig,
+) -> Result<ServoDiff> {
+    let expected = ServoKind::from_name(&servo.model)
+        .ok_or_else(|| format!("Unknown servo model {}", servo.model))?;
+
+    let kind = ServoKind::identify(dph, serial_port, servo.id)?;
+    if kind != expected {
+        return Err(Box::new(ControlTableError::ModelMismatch(
+            expected.model_number(),
+            kind.model_number(),
+        )));
+    }
+
+    let mut values = Vec::new();
+    for (name, value) in servo.registers.iter() {
+        let reg = control_table::find(kind.registers(), name)
+            .ok_or_else(|| ControlTableError::UnknownRegister(name.clone()))?;
+        if !reg.access.is_writable() {
+            return Err(Box::new(ControlTableError::ReadOnly(reg.name)));
+        }
+        if control_table::LINK_REGISTERS.contains(&reg.name) {
+            return Err(Box::new(ControlTableError::Protected(reg.name)));
+        }
+        values.push((*reg, value.clone()));
+    }
+
+    Ok(ServoDiff {
+        id: servo.id,
+        kind,
+        changes: control_table::diff(dph, serial_port, servo.id, &values)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo::file_format::FileFormat;
+
+    #[test]
+    fn parse_config() {
+        let yaml = "
+servos:
+  - id: 1
+    model: dynamixel_XL330M288
+    registers:
+      return_delay_time: 0
+      homing_offset: -512
+  - id: 2
+    model: feetech_STS3215
+";
+        let config: BusConfig = FileFormat::Yaml.deserialize(yaml).unwrap();
+        assert_eq!(config.servos.len(), 2);
+        assert_eq!(
+            config.servos[0].registers["homing_offset"],
+            RegisterValue::Int(-512)
+        );
+        assert!(config.servos[1].registers.is_empty());
+
+        let toml = FileFormat::Toml.serialize(&config).unwrap();
+        assert_eq!(
+            FileFormat::Toml.deserialize::<BusConfig>(&toml).unwrap(),
+            config
+        );
+    }
+}
@@ -0,0 +1,532 @@
+//! Control table description of the servos.
+//!
+//! Each servo defined through [generate_servo](crate::generate_servo) exposes its register list as a `REGISTERS` constant.
+//! It lets generic tooling (dump, restore, configuration) work on any registered servo without knowing its model at compile time.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{servo::ServoFamily, servo::ServoKind, DynamixelProtocolHandler, Result};
+
+/// Register access rights
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+impl Access {
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Access::Read | Access::ReadWrite)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Access::Write | Access::ReadWrite)
+    }
+}
+
+/// Byte order of the multi-byte registers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Dynamixel and most Feetech servos
+    LittleEndian,
+    /// Feetech SCS series
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// Reorders the raw bytes of a register between this byte order and little-endian (the swap works both ways)
+    ///
+    /// Only integer registers are declared big-endian, their bytes are reversed as a whole.
+    pub fn swapped<B: AsMut<[u8]>>(self, mut bytes: B) -> B {
+        if self == ByteOrder::BigEndian {
+            bytes.as_mut().reverse();
+        }
+        bytes
+    }
+}
+
+/// Raw type of a register, telling the generic tooling how to handle its value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawKind {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    /// Any other type (e.g. `f32`, Orbita's `MotorValue`), kept as raw bytes
+    Bytes,
+}
+
+/// Type usable as the raw type of a register in [generate_servo](crate::generate_servo)
+pub trait RawType {
+    const KIND: RawKind;
+}
+
+macro_rules! impl_raw_type {
+    ($($ty:ty => $kind:ident),+ $(,)?) => {
+        $(
+            impl RawType for $ty {
+                const KIND: RawKind = RawKind::$kind;
+            }
+        )+
+    };
+}
+
+impl_raw_type!(
+    u8 => U8,
+    i8 => I8,
+    u16 => U16,
+    i16 => I16,
+    u32 => U32,
+    i32 => I32,
+    f32 => Bytes,
+);
+
+/// Implements [RawType] for custom register types, kept as raw bytes by the generic tooling
+macro_rules! impl_raw_bytes {
+    ($($ty:ident $(<$param:ident>)?),+ $(,)?) => {
+        $(
+            impl$(<$param>)? $crate::servo::control_table::RawType for $ty$(<$param>)? {
+                const KIND: $crate::servo::control_table::RawKind =
+                    $crate::servo::control_table::RawKind::Bytes;
+            }
+        )+
+    };
+}
+pub(crate) use impl_raw_bytes;
+
+/// Description of a single register of a control table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterInfo {
+    pub name: &'static str,
+    pub addr: u16,
+    pub length: u8,
+    pub access: Access,
+    /// Raw register type, from the [RawType] of the servo definition
+    pub kind: RawKind,
+    /// Byte order of the servo, as declared in its definition (little-endian by default)
+    pub byte_order: ByteOrder,
+}
+
+/// Compile-time description of a register, generated for each register of a servo (in its `registers` module)
+pub trait Register {
+    const NAME: &'static str;
+    const ADDR: u16;
+    /// Length in bytes
+    const LENGTH: usize;
+    /// Value type, after conversion
+    type Value;
+}
+
+/// Register that can be read
+pub trait ReadRegister: Register {
+    fn decode(bytes: &[u8]) -> Result<Self::Value>;
+}
+
+/// Register that can be written
+pub trait WriteRegister: Register {
+    fn encode(value: Self::Value) -> Vec<u8>;
+}
+
+/// Raw value of a register
+///
+/// Integer registers are stored as their (signed or unsigned) value, decoded in the byte order of the servo.
+/// Any other register type (e.g. Orbita's `MotorValue`) is kept as its raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RegisterValue {
+    Int(i64),
+    Bytes(Vec<u8>),
+}
+
+impl fmt::Display for RegisterValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegisterValue::Int(v) => write!(f, "{v}"),
+            RegisterValue::Bytes(b) => write!(f, "{b:?}"),
+        }
+    }
+}
+
+impl RegisterInfo {
+    /// Decodes the raw bytes read from this register
+    pub fn decode(&self, bytes: &[u8]) -> Result<RegisterValue> {
+        if bytes.len() != self.length as usize {
+            return Err(Box::new(ControlTableError::InvalidLength(self.name)));
+        }
+        let le = self.byte_order.swapped(bytes.to_vec());
+        let value = match self.kind {
+            RawKind::U8 => le[0] as i64,
+            RawKind::I8 => le[0] as i8 as i64,
+            RawKind::U16 => u16::from_le_bytes(le.try_into().unwrap()) as i64,
+            RawKind::I16 => i16::from_le_bytes(le.try_into().unwrap()) as i64,
+            RawKind::U32 => u32::from_le_bytes(le.try_into().unwrap()) as i64,
+            RawKind::I32 => i32::from_le_bytes(le.try_into().unwrap()) as i64,
+            RawKind::Bytes => return Ok(RegisterValue::Bytes(bytes.to_vec())),
+        };
+        Ok(RegisterValue::Int(value))
+    }
+
+    /// Encodes a value into the raw bytes to write in this register
+    pub fn encode(&self, value: &RegisterValue) -> Result<Vec<u8>> {
+        let invalid = || ControlTableError::InvalidValue(self.name, value.clone());
+
+        let bytes = match value {
+            RegisterValue::Bytes(b) => b.clone(),
+            RegisterValue::Int(v) => {
+                let range = match self.kind {
+                    RawKind::U8 => 0..=u8::MAX as i64,
+                    RawKind::I8 => i8::MIN as i64..=i8::MAX as i64,
+                    RawKind::U16 => 0..=u16::MAX as i64,
+                    RawKind::I16 => i16::MIN as i64..=i16::MAX as i64,
+                    RawKind::U32 => 0..=u32::MAX as i64,
+                    RawKind::I32 => i32::MIN as i64..=i32::MAX as i64,
+                    RawKind::Bytes => return Err(Box::new(invalid())),
+                };
+                if !range.contains(v) {
+                    return Err(Box::new(invalid()));
+                }
+                // Little-endian two's complement truncation works for both signed and unsigned values
+                self.byte_order
+                    .swapped(v.to_le_bytes()[..self.length as usize].to_vec())
+            }
+        };
+        if bytes.len() != self.length as usize {
+            return Err(Box::new(invalid()));
+        }
+        Ok(bytes)
+    }
+
+    /// Whether the register lives in the EEPROM area of the control table
+    ///
+    /// The EEPROM area is everything located before the `torque_enable` register (Dynamixel and Feetech layout),
+    /// or the `eep_*` registers mapped at [HERKULEX_EEP_BANK](crate::HERKULEX_EEP_BANK) for Herkulex servos.
+    pub fn is_eeprom(&self, registers: &[RegisterInfo]) -> bool {
+        match find(registers, "torque_enable") {
+            Some(torque) => self.addr < torque.addr,
+            None => self.name.starts_with("eep_") && self.addr >= crate::HERKULEX_EEP_BANK,
+        }
+    }
+
+    /// Reads the register raw value
+    pub fn read(
+        &self,
+        dph: &DynamixelProtocolHandler,
+        serial_port: &mut dyn serialport::SerialPort,
+        id: u8,
+    ) -> Result<RegisterValue> {
+        let bytes = dph.read(serial_port, id, self.addr, self.length.into())?;
+        self.decode(&bytes)
+    }
+
+    /// Writes the register raw value
+    pub fn write(
+        &self,
+        dph: &DynamixelProtocolHandler,
+        serial_port: &mut dyn serialport::SerialPort,
+        id: u8,
+        value: &RegisterValue,
+    ) -> Result<()> {
+        if !self.access.is_writable() {
+            return Err(Box::new(ControlTableError::ReadOnly(self.name)));
+        }
+        let bytes = self.encode(value)?;
+        dph.write(serial_port, id, self.addr, &bytes)
+    }
+}
+
+/// Registers defining how the servo is reached on the bus.
+///
+/// They are never written by the generic tooling (restore, configuration), as it would break the communication.
+pub const LINK_REGISTERS: [&str; 5] = ["id", "eep_id", "baudrate", "baud_rate", "buad_rate"];
+
+/// Finds a register by name
+pub fn find<'a>(registers: &'a [RegisterInfo], name: &str) -> Option<&'a RegisterInfo> {
+    registers.iter().find(|r| r.name == name)
+}
+
+/// Finds the register holding the model number
+pub fn model_register(registers: &[RegisterInfo]) -> Option<&RegisterInfo> {
+    find(registers, "model_number").or_else(|| find(registers, "model"))
+}
+
+/// Finds the register holding the baudrate
+pub fn baudrate_register(registers: &[RegisterInfo]) -> Option<&RegisterInfo> {
+    ["baudrate", "baud_rate", "buad_rate"]
+        .iter()
+        .find_map(|name| find(registers, name))
+}
+
+/// Lists the registers, skipping aliases (registers declared twice at the same address)
+pub fn unique_registers(registers: &[RegisterInfo]) -> Vec<&RegisterInfo> {
+    let mut unique: Vec<&RegisterInfo> = Vec::new();
+    for r in registers {
+        if !unique.iter().any(|u| u.addr == r.addr) {
+            unique.push(r);
+        }
+    }
+    unique
+}
+
+/// Disables the torque so the EEPROM area can be written.
+///
+/// Returns the previous raw `torque_enable` value (if the servo has such a register).
+pub fn disable_torque(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    kind: ServoKind,
+) -> Result<Option<RegisterValue>> {
+    match find(kind.registers(), "torque_enable") {
+        Some(torque) => {
+            let previous = torque.read(dph, serial_port, id)?;
+            dph.write(
+                serial_port,
+                id,
+                torque.addr,
+                &vec![0; torque.length as usize],
+            )?;
+            Ok(Some(previous))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Unlocks the EEPROM area.
+///
+/// Only Feetech servos need it: their EEPROM is write-protected by the `lock` register.
+/// On Dynamixel, the `lock` register can only be cleared by a power cycle, so it is left untouched.
+pub fn unlock_eeprom(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    kind: ServoKind,
+) -> Result<()> {
+    match (kind.family(), find(kind.registers(), "lock")) {
+        (ServoFamily::Feetech, Some(lock)) => dph.write(serial_port, id, lock.addr, &[0]),
+        _ => Ok(()),
+    }
+}
+
+/// Locks back the EEPROM area (see [unlock_eeprom]).
+pub fn lock_eeprom(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    kind: ServoKind,
+) -> Result<()> {
+    match (kind.family(), find(kind.registers(), "lock")) {
+        (ServoFamily::Feetech, Some(lock)) => dph.write(serial_port, id, lock.addr, &[1]),
+        _ => Ok(()),
+    }
+}
+
+/// Register value change on a servo
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterChange {
+    pub register: RegisterInfo,
+    pub previous: RegisterValue,
+    pub value: RegisterValue,
+}
+
+impl fmt::Display for RegisterChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.register.name, self.previous, self.value
+        )
+    }
+}
+
+/// Compares the given register values with the live ones.
+///
+/// Returns the changes needed, in the given order.
+pub fn diff(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    values: &[(RegisterInfo, RegisterValue)],
+) -> Result<Vec<RegisterChange>> {
+    let mut changes = Vec::new();
+    for (reg, value) in values {
+        // Catch invalid values before touching the servo
+        reg.encode(value)?;
+
+        let previous = reg.read(dph, serial_port, id)?;
+        if previous != *value {
+            changes.push(RegisterChange {
+                register: *reg,
+                previous,
+                value: value.clone(),
+            });
+        }
+    }
+    Ok(changes)
+}
+
+/// Writes register changes, handling EEPROM safety.
+///
+/// The torque is disabled (and Feetech EEPROM unlocked) first, then registers are written by increasing address.
+/// The torque is finally set back to its previous value, unless `torque_enable` is part of the changes: it is then written last.
+/// The EEPROM is locked back and the torque restored even if a write fails, the first error is then returned.
+pub fn write_changes(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    kind: ServoKind,
+    changes: &[RegisterChange],
+) -> Result<()> {
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    let (torque, mut changes): (Vec<_>, Vec<_>) = changes
+        .iter()
+        .partition(|c| c.register.name == "torque_enable");
+    changes.sort_by_key(|c| c.register.addr);
+
+    let previous_torque = disable_torque(dph, serial_port, id, kind)?;
+    unlock_eeprom(dph, serial_port, id, kind)?;
+
+    let written = changes.iter().try_for_each(|change| {
+        log::debug!("Servo {id}: {change}");
+        change.register.write(dph, serial_port, id, &change.value)
+    });
+
+    let locked = lock_eeprom(dph, serial_port, id, kind);
+
+    // After a failed write, the torque is only set back to its previous value
+    let torque_change = torque.first().filter(|_| written.is_ok());
+    let torque_restored = match (
+        torque_change,
+        find(kind.registers(), "torque_enable"),
+        previous_torque,
+    ) {
+        (Some(change), _, _) => change.register.write(dph, serial_port, id, &change.value),
+        (None, Some(reg), Some(previous)) => reg.write(dph, serial_port, id, &previous),
+        _ => Ok(()),
+    };
+
+    written.and(locked).and(torque_restored)
+}
+
+/// Control table related errors
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlTableError {
+    /// No register with this name in the control table
+    UnknownRegister(String),
+    /// The register can not be written
+    ReadOnly(&'static str),
+    /// The value does not fit in the register
+    InvalidValue(&'static str, RegisterValue),
+    /// Unexpected number of bytes for the register
+    InvalidLength(&'static str),
+    /// The model found on the bus differs from the expected one (expected, found)
+    ModelMismatch(u16, u16),
+    /// The register can not be changed by the generic tooling (see [LINK_REGISTERS])
+    Protected(&'static str),
+}
+
+impl fmt::Display for ControlTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ControlTableError::UnknownRegister(name) => write!(f, "Unknown register {name}"),
+            ControlTableError::ReadOnly(name) => write!(f, "Register {name} is read-only"),
+            ControlTableError::InvalidValue(name, value) => {
+                write!(f, "Invalid value {value} for register {name}")
+            }
+            ControlTableError::InvalidLength(name) => {
+                write!(f, "Invalid data length for register {name}")
+            }
+            ControlTableError::ModelMismatch(expected, found) => {
+                write!(f, "Model mismatch (found {found} instead of {expected})")
+            }
+            ControlTableError::Protected(name) => {
+                write!(f, "Register {name} can not be changed this way")
+            }
+        }
+    }
+}
+impl std::error::Error for ControlTableError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo::{dynamixel::xl330, feetech::scs0009, orbita::orbita2d_foc};
+
+    #[test]
+    fn decode_encode_registers() {
+        let homing_offset = find(xl330::REGISTERS, "homing_offset").unwrap();
+        assert_eq!(homing_offset.length, 4);
+        assert_eq!(homing_offset.kind, RawKind::I32);
+
+        let value = homing_offset.decode(&(-100_i32).to_le_bytes()).unwrap();
+        assert_eq!(value, RegisterValue::Int(-100));
+        assert_eq!(
+            homing_offset.encode(&value).unwrap(),
+            (-100_i32).to_le_bytes()
+        );
+
+        // SCS registers are big-endian
+        let max_angle_limit = find(scs0009::REGISTERS, "max_angle_limit").unwrap();
+        let value = RegisterValue::Int(1023);
+        assert_eq!(max_angle_limit.encode(&value).unwrap(), [0x03, 0xFF]);
+        assert_eq!(max_angle_limit.decode(&[0x03, 0xFF]).unwrap(), value);
+
+        // Other types are kept as raw bytes
+        let pid = find(orbita2d_foc::REGISTERS, "velocity_pid").unwrap();
+        assert_eq!(pid.kind, RawKind::Bytes);
+        assert_eq!(
+            pid.decode(&[0; 12]).unwrap(),
+            RegisterValue::Bytes(vec![0; 12])
+        );
+
+        let id = find(xl330::REGISTERS, "id").unwrap();
+        assert!(id.encode(&RegisterValue::Int(256)).is_err());
+        assert!(id.encode(&RegisterValue::Bytes(vec![1, 2])).is_err());
+    }
+
+    #[test]
+    fn eeprom_area() {
+        let registers = xl330::REGISTERS;
+        assert!(find(registers, "operating_mode")
+            .unwrap()
+            .is_eeprom(registers));
+        assert!(!find(registers, "goal_position")
+            .unwrap()
+            .is_eeprom(registers));
+    }
+
+    #[test]
+    fn torque_restored_after_failed_write() {
+        use crate::dynamixel_protocol::mock::{FakeServosV2, MockSerialPort};
+
+        let mut port = MockSerialPort::new(FakeServosV2::default().with_servo(1, 1200));
+        let dph = DynamixelProtocolHandler::v2();
+        dph.write(&mut port, 1, 64, &[1]).unwrap();
+
+        let change = |name| RegisterChange {
+            register: *find(xl330::REGISTERS, name).unwrap(),
+            previous: RegisterValue::Int(0),
+            value: RegisterValue::Int(1),
+        };
+        let kind = ServoKind::identify(&dph, &mut port, 1).unwrap();
+        let changes = [change("return_delay_time"), change("present_position")];
+        assert!(write_changes(&dph, &mut port, 1, kind, &changes).is_err());
+
+        assert_eq!(dph.read(&mut port, 1, 9, 1).unwrap(), [1]);
+        assert_eq!(dph.read(&mut port, 1, 64, 1).unwrap(), [1]);
+    }
+
+    #[test]
+    fn skip_aliases() {
+        let unique = unique_registers(xl330::REGISTERS);
+        assert!(unique.iter().any(|r| r.name == "current_limit"));
+        assert!(!unique.iter().any(|r| r.name == "torque_limit"));
+    }
+}
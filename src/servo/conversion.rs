@@ -1,10 +1,60 @@
+use std::{f64::consts::PI, fmt, marker::PhantomData};
+
 pub trait Conversion {
     type RegisterType;
     type UsiType;
     fn from_raw(raw: Self::RegisterType) -> Self::UsiType;
     fn to_raw(value: Self::UsiType) -> Self::RegisterType;
+
+    /// Fallible version of `from_raw`, for registers where only some raw values are meaningful.
+    fn try_from_raw(raw: Self::RegisterType) -> Result<Self::UsiType, ConversionError> {
+        Ok(Self::from_raw(raw))
+    }
+}
+
+/// Conversion used by the generated register functions.
+///
+/// Implemented by every [Conversion], and directly by the conversions without an infallible `from_raw`
+/// (e.g. the enums of [enum_conversion]).
+pub trait RegisterConversion {
+    type RegisterType;
+    type UsiType;
+    fn decode(raw: Self::RegisterType) -> Result<Self::UsiType, ConversionError>;
+    fn encode(value: Self::UsiType) -> Self::RegisterType;
 }
 
+impl<T: Conversion> RegisterConversion for T {
+    type RegisterType = T::RegisterType;
+    type UsiType = T::UsiType;
+
+    fn decode(raw: Self::RegisterType) -> Result<Self::UsiType, ConversionError> {
+        T::try_from_raw(raw)
+    }
+
+    fn encode(value: Self::UsiType) -> Self::RegisterType {
+        T::to_raw(value)
+    }
+}
+
+/// Error returned when a raw register value does not match its typed representation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The raw value (`.1`) is not a valid `.0`
+    InvalidRawValue(&'static str, u32),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::InvalidRawValue(ty, raw) => {
+                write!(f, "Invalid raw value {raw} for {ty}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
 impl Conversion for bool {
     type RegisterType = u8;
     type UsiType = bool;
@@ -21,3 +71,261 @@ impl Conversion for bool {
         }
     }
 }
+
+/// Integer types registers are made of, used by the generic unit conversions
+pub trait RawValue: Copy {
+    fn to_i64(self) -> i64;
+    /// Saturates out of range values
+    fn from_i64(value: i64) -> Self;
+}
+
+macro_rules! impl_raw_value {
+    ($($t:ty),+) => {
+        $(
+            impl RawValue for $t {
+                fn to_i64(self) -> i64 {
+                    self as i64
+                }
+                fn from_i64(value: i64) -> Self {
+                    value.clamp(<$t>::MIN as i64, <$t>::MAX as i64) as $t
+                }
+            }
+        )+
+    };
+}
+impl_raw_value!(u8, i8, u16, i16, u32, i32);
+
+/// Decodes a signed raw value.
+///
+/// `SIGN_BIT` is the direction bit of sign-magnitude encodings (0 for two's complement).
+fn decode_signed<R: RawValue, const SIGN_BIT: u32>(raw: R) -> f64 {
+    let raw = raw.to_i64();
+    if SIGN_BIT == 0 {
+        return raw as f64;
+    }
+    let magnitude = (raw & ((1 << SIGN_BIT) - 1)) as f64;
+    match raw & (1 << SIGN_BIT) != 0 {
+        true => -magnitude,
+        false => magnitude,
+    }
+}
+
+fn encode_signed<R: RawValue, const SIGN_BIT: u32>(value: f64) -> R {
+    let value = value.round() as i64;
+    if SIGN_BIT == 0 {
+        return R::from_i64(value);
+    }
+    let magnitude = value.abs().min((1 << SIGN_BIT) - 1);
+    match value < 0 {
+        true => R::from_i64(magnitude | (1 << SIGN_BIT)),
+        false => R::from_i64(magnitude),
+    }
+}
+
+/// Angular velocity in rad/s, from a raw unit of `MICRO_RPM` (e.g. 229_000 for 0.229 rpm)
+///
+/// Direction is given by the sign, or by `SIGN_BIT` for sign-magnitude registers (set means clockwise, negative).
+pub struct Velocity<R, const MICRO_RPM: u32, const SIGN_BIT: u32 = 0>(PhantomData<R>);
+
+impl<R: RawValue, const MICRO_RPM: u32, const SIGN_BIT: u32> Conversion
+    for Velocity<R, MICRO_RPM, SIGN_BIT>
+{
+    type RegisterType = R;
+    type UsiType = f64;
+
+    fn from_raw(raw: R) -> f64 {
+        decode_signed::<R, SIGN_BIT>(raw) * MICRO_RPM as f64 * 1e-6 * 2.0 * PI / 60.0
+    }
+
+    fn to_raw(value: f64) -> R {
+        encode_signed::<R, SIGN_BIT>(value * 60.0 / (2.0 * PI) / (MICRO_RPM as f64 * 1e-6))
+    }
+}
+
+/// Current in A, from a raw unit of `MICRO_AMPS` (e.g. 2690 for 2.69 mA)
+pub struct Current<R, const MICRO_AMPS: u32, const SIGN_BIT: u32 = 0>(PhantomData<R>);
+
+impl<R: RawValue, const MICRO_AMPS: u32, const SIGN_BIT: u32> Conversion
+    for Current<R, MICRO_AMPS, SIGN_BIT>
+{
+    type RegisterType = R;
+    type UsiType = f64;
+
+    fn from_raw(raw: R) -> f64 {
+        decode_signed::<R, SIGN_BIT>(raw) * MICRO_AMPS as f64 * 1e-6
+    }
+
+    fn to_raw(value: f64) -> R {
+        encode_signed::<R, SIGN_BIT>(value / (MICRO_AMPS as f64 * 1e-6))
+    }
+}
+
+/// Voltage in V, from a raw unit of `MILLI_VOLTS` (usually 100 for 0.1 V)
+pub struct Voltage<R, const MILLI_VOLTS: u32 = 100>(PhantomData<R>);
+
+impl<R: RawValue, const MILLI_VOLTS: u32> Conversion for Voltage<R, MILLI_VOLTS> {
+    type RegisterType = R;
+    type UsiType = f64;
+
+    fn from_raw(raw: R) -> f64 {
+        raw.to_i64() as f64 * MILLI_VOLTS as f64 * 1e-3
+    }
+
+    fn to_raw(value: f64) -> R {
+        R::from_i64((value / (MILLI_VOLTS as f64 * 1e-3)).round() as i64)
+    }
+}
+
+/// Temperature in °C (1 °C raw unit)
+pub struct Temperature;
+
+impl Conversion for Temperature {
+    type RegisterType = u8;
+    type UsiType = f64;
+
+    fn from_raw(raw: u8) -> f64 {
+        raw as f64
+    }
+
+    fn to_raw(value: f64) -> u8 {
+        u8::from_i64(value.round() as i64)
+    }
+}
+
+/// Load normalized to [-1.0, 1.0], `FULL_SCALE` being the raw value of 100% (e.g. 1000 for 0.1% units)
+pub struct Load<R, const FULL_SCALE: u32, const SIGN_BIT: u32 = 0>(PhantomData<R>);
+
+impl<R: RawValue, const FULL_SCALE: u32, const SIGN_BIT: u32> Conversion
+    for Load<R, FULL_SCALE, SIGN_BIT>
+{
+    type RegisterType = R;
+    type UsiType = f64;
+
+    fn from_raw(raw: R) -> f64 {
+        decode_signed::<R, SIGN_BIT>(raw) / FULL_SCALE as f64
+    }
+
+    fn to_raw(value: f64) -> R {
+        encode_signed::<R, SIGN_BIT>(value * FULL_SCALE as f64)
+    }
+}
+
+/// Implements [RegisterConversion] for an enum deriving `num_enum::TryFromPrimitive` and `num_enum::IntoPrimitive`.
+///
+/// There is no infallible [Conversion] as unknown raw values can not be represented.
+macro_rules! enum_conversion {
+    ($ty:ty, $raw:ty) => {
+        impl $crate::servo::conversion::RegisterConversion for $ty {
+            type RegisterType = $raw;
+            type UsiType = $ty;
+
+            fn encode(value: $ty) -> $raw {
+                value.into()
+            }
+
+            fn decode(
+                raw: $raw,
+            ) -> std::result::Result<$ty, $crate::servo::conversion::ConversionError> {
+                <$ty>::try_from(raw).map_err(|_| {
+                    $crate::servo::conversion::ConversionError::InvalidRawValue(
+                        stringify!($ty),
+                        raw.into(),
+                    )
+                })
+            }
+        }
+    };
+}
+pub(crate) use enum_conversion;
+
+/// Implements [Conversion] for a `bitflags` type.
+///
+/// Unknown bits are rejected by `try_from_raw` and dropped by `from_raw`.
+/// With the python feature, the flags type must also be a pyclass: its raw value is exposed as `bits`.
+macro_rules! flags_conversion {
+    ($ty:ty, $raw:ty) => {
+        impl $crate::servo::conversion::Conversion for $ty {
+            type RegisterType = $raw;
+            type UsiType = $ty;
+
+            // Unknown bits (reserved, or used by other models) are kept, so that they are written back unchanged
+            fn from_raw(raw: $raw) -> $ty {
+                <$ty>::from_bits_retain(raw)
+            }
+
+            fn to_raw(value: $ty) -> $raw {
+                value.bits()
+            }
+
+            fn try_from_raw(
+                raw: $raw,
+            ) -> std::result::Result<$ty, $crate::servo::conversion::ConversionError> {
+                Ok(<$ty>::from_bits_retain(raw))
+            }
+        }
+
+        #[cfg(feature = "python")]
+        #[pyo3_stub_gen::derive::gen_stub_pymethods]
+        #[pyo3::pymethods]
+        impl $ty {
+            #[new]
+            fn py_new(bits: $raw) -> pyo3::PyResult<Self> {
+                <$ty>::from_bits(bits).ok_or_else(|| {
+                    pyo3::exceptions::PyValueError::new_err(format!(
+                        "Invalid raw value {bits} for {}",
+                        stringify!($ty)
+                    ))
+                })
+            }
+
+            #[getter(bits)]
+            fn py_bits(&self) -> $raw {
+                self.bits()
+            }
+
+            fn __repr__(&self) -> String {
+                format!("{self:?}")
+            }
+
+            // `pyclass(eq)` does not work on the structs generated by `bitflags!`
+            fn __eq__(&self, other: &Self) -> bool {
+                self == other
+            }
+        }
+    };
+}
+pub(crate) use flags_conversion;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unit_conversions() {
+        type XVelocity = Velocity<i32, 229_000>;
+        assert!((XVelocity::from_raw(100) - 2.398).abs() < 1e-3);
+        assert_eq!(XVelocity::to_raw(XVelocity::from_raw(-100)), -100);
+
+        // Sign-magnitude: bit 10 set means clockwise
+        type MxVelocity = Velocity<u16, 114_000, 10>;
+        assert_eq!(MxVelocity::from_raw(1024 + 300), -MxVelocity::from_raw(300));
+        assert_eq!(
+            MxVelocity::to_raw(MxVelocity::from_raw(1024 + 300)),
+            1024 + 300
+        );
+        assert_eq!(MxVelocity::to_raw(-1000.0), 2047);
+
+        assert_eq!(Current::<i16, 2690>::from_raw(-1000), -2.69);
+        assert_eq!(Voltage::<u16>::from_raw(120), 12.0);
+        assert_eq!(Voltage::<u8>::to_raw(7.4), 74);
+        assert_eq!(Temperature::to_raw(300.0), 255);
+        assert_eq!(Load::<u16, 1023, 10>::from_raw(1024 + 1023), -1.0);
+        assert_eq!(Load::<i16, 1000>::to_raw(0.5), 500);
+    }
+
+    #[test]
+    fn default_try_from_raw() {
+        assert_eq!(bool::try_from_raw(2), Ok(true));
+        assert_eq!(bool::try_from_raw(0), Ok(false));
+    }
+}
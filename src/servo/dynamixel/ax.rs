@@ -9,21 +9,28 @@
 //!
 //! See <https://emanual.robotis.com/docs/en/dxl/ax/ax-12a/> for example.
 
-use crate::{generate_servo, servo::conversion::Conversion};
+use crate::{
+    generate_servo,
+    servo::{
+        baudrate::BaudrateTable,
+        conversion::{self, Conversion, Temperature},
+        dynamixel::mx::divider_bps,
+    },
+};
 
 generate_servo!(
     AX, v1,
     reg: (model_number, r, 0, u16, None),
     reg: (firmware_version, r, 2, u8, None),
     reg: (id, rw, 3, u8, None),
-    reg: (baudrate, rw, 4, u8, None),
+    reg: (baudrate, rw, 4, u8, Baudrate),
     reg: (return_delay_time, rw, 5, u8, None),
-    reg: (cw_angle_limit, rw, 6, u16, AnglePosition),
-    reg: (ccw_angle_limit, rw, 8, u16, AnglePosition),
-    reg: (temperature_limit, rw, 11, u8, None),
-    reg: (min_voltage_limit, rw, 12, u8, None),
-    reg: (max_voltage_limit, rw, 13, u8, None),
-    reg: (max_torque, rw, 14, u16, None),
+    reg: (cw_angle_limit, rw, 6, u16, AnglePosition, unit),
+    reg: (ccw_angle_limit, rw, 8, u16, AnglePosition, unit),
+    reg: (temperature_limit, rw, 11, u8, Temperature, unit),
+    reg: (min_voltage_limit, rw, 12, u8, Voltage, unit),
+    reg: (max_voltage_limit, rw, 13, u8, Voltage, unit),
+    reg: (max_torque, rw, 14, u16, TorqueLimit),
     reg: (status_return_level, rw, 16, u8, None),
     reg: (alarm_led, rw, 17, u8, None),
     reg: (shutdown, rw, 18, u8, None),
@@ -33,42 +40,29 @@ generate_servo!(
     reg: (ccw_compliance_margin, rw, 27, u8, None),
     reg: (cw_compliance_slope, rw, 28, u8, None),
     reg: (ccw_compliance_slope, rw, 29, u8, None),
-    reg: (goal_position, rw, 30, u16, AnglePosition),
+    reg: (goal_position, rw, 30, u16, AnglePosition, unit),
     reg: (moving_speed, rw, 32, u16, None),
-    reg: (torque_limit, rw, 34, u16, None),
-    reg: (present_position, r, 36, u16, AnglePosition),
-    reg: (present_speed, r, 38, u16, None),
-    reg: (present_load, r, 40, u16, None),
-    reg: (present_voltage, r, 42, u8, None),
-    reg: (present_temperature, r, 43, u8, None),
+    reg: (torque_limit, rw, 34, u16, TorqueLimit),
+    reg: (present_position, r, 36, u16, AnglePosition, unit),
+    reg: (present_speed, r, 38, u16, Velocity, unit),
+    reg: (present_load, r, 40, u16, Load),
+    reg: (present_voltage, r, 42, u8, Voltage, unit),
+    reg: (present_temperature, r, 43, u8, Temperature, unit),
     reg: (registered, r, 44, u8, None),
     reg: (moving, r, 46, u8, None),
     reg: (lock, rw, 47, u8, None),
     reg: (punch, rw, 48, u16, None),
+    group: (present_position_speed_load, r, [present_position, present_speed, present_load]),
 );
 
-/// Sync read present_position, present_speed and present_load in one message
-///
-/// reg_read_only!(present_position_speed_load, 36, (i16, u16, u16))
-pub fn sync_read_present_position_speed_load(
-    dph: &crate::DynamixelProtocolHandler,
-    serial_port: &mut dyn serialport::SerialPort,
-    ids: &[u8],
-) -> crate::Result<Vec<(i16, u16, u16)>> {
-    let val = dph.sync_read(serial_port, ids, 36, 2 + 2 + 2)?;
-    let val = val
-        .iter()
-        .map(|v| {
-            (
-                i16::from_le_bytes(v[0..2].try_into().unwrap()),
-                u16::from_le_bytes(v[2..4].try_into().unwrap()),
-                u16::from_le_bytes(v[4..6].try_into().unwrap()),
-            )
-        })
-        .collect();
-
-    Ok(val)
-}
+/// Velocity in rad/s (0.111 rpm unit, bit 10 is the direction)
+pub type Velocity = conversion::Velocity<u16, 111_000, 10>;
+/// Load normalized to [-1.0, 1.0] (bit 10 is the direction)
+pub type Load = conversion::Load<u16, 1023, 10>;
+/// Torque limit normalized to [0.0, 1.0]
+pub type TorqueLimit = conversion::Load<u16, 1023>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u8>;
 
 pub struct AnglePosition;
 const MAX_DEFLECTION: f64 = 150f64.to_radians(); // -150 to 150 deg (exclusive)
@@ -85,6 +79,89 @@ impl Conversion for AnglePosition {
     }
 }
 
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
+
+/// Communication speed (*baudrate* register)
+///
+/// Any raw value is valid: the speed is `2_000_000 / (raw + 1)` bps, the supported rates go from 1Mbps (1) to 9600bps (207).
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass,
+    pyo3::pyclass(eq, name = "AxBaudrate")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Baudrate(pub u8);
+
+impl Baudrate {
+    pub const B9600: Baudrate = Baudrate(207);
+    pub const B19200: Baudrate = Baudrate(103);
+    pub const B57600: Baudrate = Baudrate(34);
+    pub const B115200: Baudrate = Baudrate(16);
+    pub const B200000: Baudrate = Baudrate(9);
+    pub const B250000: Baudrate = Baudrate(7);
+    pub const B400000: Baudrate = Baudrate(4);
+    pub const B500000: Baudrate = Baudrate(3);
+    pub const B1000000: Baudrate = Baudrate(1);
+}
+
+impl Conversion for Baudrate {
+    type RegisterType = u8;
+    type UsiType = Baudrate;
+
+    fn from_raw(raw: u8) -> Baudrate {
+        Baudrate(raw)
+    }
+
+    fn to_raw(value: Baudrate) -> u8 {
+        value.0
+    }
+}
+
+impl BaudrateTable for Baudrate {
+    const ALL: &'static [Self] = &[
+        Baudrate::B9600,
+        Baudrate::B19200,
+        Baudrate::B57600,
+        Baudrate::B115200,
+        Baudrate::B200000,
+        Baudrate::B250000,
+        Baudrate::B400000,
+        Baudrate::B500000,
+        Baudrate::B1000000,
+    ];
+
+    fn bps(&self) -> u32 {
+        divider_bps(self.0)
+    }
+}
+
+#[cfg(feature = "python")]
+#[pyo3_stub_gen::derive::gen_stub_pymethods]
+#[pyo3::pymethods]
+impl Baudrate {
+    #[new]
+    fn py_new(raw: u8) -> Self {
+        Baudrate(raw)
+    }
+
+    #[getter(raw)]
+    fn py_raw(&self) -> u8 {
+        self.0
+    }
+
+    #[getter(bps)]
+    fn py_bps(&self) -> u32 {
+        self.bps()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
 /// Unit conversion for AX motors
 pub mod conv {
 
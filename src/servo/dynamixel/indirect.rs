@@ -0,0 +1,307 @@
+//! Indirect addressing (Dynamixel X-series).
+//!
+//! Registers scattered in the control table can be mapped onto the contiguous indirect data area,
+//! so that they are all read with a single `sync_read` per cycle.
+//! Each byte of a mapped register takes one entry of the indirect address table.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{
+//!     servo::dynamixel::{
+//!         indirect::IndirectReader,
+//!         xl330::{self, registers},
+//!     },
+//!     DynamixelProtocolHandler,
+//! };
+//!
+//! let mut serial_port = serialport::new("/dev/ttyUSB0", 1_000_000).open().unwrap();
+//! let dph = DynamixelProtocolHandler::v2();
+//! let ids = [1, 2, 3];
+//!
+//! let reader = IndirectReader::<(
+//!     registers::present_position,
+//!     registers::present_velocity,
+//!     registers::present_current,
+//!     registers::present_temperature,
+//! )>::new(xl330::REGISTERS)
+//! .unwrap();
+//! reader.program(&dph, serial_port.as_mut(), &ids).unwrap();
+//!
+//! loop {
+//!     // Converted values: rad, rad/s, A and °C
+//!     for (position, velocity, current, temperature) in reader.sync_read(&dph, serial_port.as_mut(), &ids).unwrap() {
+//!         // ...
+//!     }
+//! }
+//! ```
+
+use std::{fmt, marker::PhantomData};
+
+use crate::{
+    servo::control_table::{self, ControlTableError, ReadRegister, RegisterInfo},
+    CommunicationErrorKind, DynamixelProtocolHandler, Result,
+};
+
+/// Number of entries of the indirect address table (first area of the X-series control table)
+pub const INDIRECT_ENTRIES: usize = 20;
+
+/// Indirect mapping errors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndirectError {
+    /// The control table has no indirect address and data areas
+    Unsupported,
+    /// The mapped registers need more bytes than there are indirect entries
+    TooLong(usize),
+    /// The register types do not match the registers of the control table (expected and found lengths)
+    LayoutMismatch(Vec<usize>, Vec<usize>),
+}
+
+impl fmt::Display for IndirectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndirectError::Unsupported => write!(f, "No indirect addressing in this control table"),
+            IndirectError::TooLong(len) => write!(
+                f,
+                "Mapping {len} bytes, only {INDIRECT_ENTRIES} indirect entries are available"
+            ),
+            IndirectError::LayoutMismatch(expected, found) => write!(
+                f,
+                "Register lengths {found:?} do not match the register types {expected:?}"
+            ),
+        }
+    }
+}
+impl std::error::Error for IndirectError {}
+
+/// Registers read together, a tuple of register types (e.g. `xl330::registers::present_position`)
+pub trait IndirectBlock {
+    /// Converted values of the registers
+    type Values;
+    /// Name of each register
+    fn names() -> Vec<&'static str>;
+    /// Size of each register
+    fn sizes() -> Vec<usize>;
+    fn decode(bytes: &[u8]) -> Result<Self::Values>;
+}
+
+macro_rules! impl_indirect_block {
+    ($($t:ident),+) => {
+        impl<$($t: ReadRegister),+> IndirectBlock for ($($t,)+) {
+            type Values = ($($t::Value,)+);
+
+            fn names() -> Vec<&'static str> {
+                vec![$($t::NAME),+]
+            }
+
+            fn sizes() -> Vec<usize> {
+                vec![$($t::LENGTH),+]
+            }
+
+            #[allow(unused_assignments)]
+            fn decode(bytes: &[u8]) -> Result<Self::Values> {
+                let mut offset = 0;
+                Ok(($({
+                    let field = $t::decode(&bytes[offset..offset + $t::LENGTH])?;
+                    offset += $t::LENGTH;
+                    field
+                },)+))
+            }
+        }
+    };
+}
+impl_indirect_block!(A);
+impl_indirect_block!(A, B);
+impl_indirect_block!(A, B, C);
+impl_indirect_block!(A, B, C, D);
+impl_indirect_block!(A, B, C, D, E);
+impl_indirect_block!(A, B, C, D, E, F);
+
+/// Reads a set of registers `T`, mapped on the indirect data area, as their converted values per servo
+pub struct IndirectReader<T> {
+    registers: Vec<RegisterInfo>,
+    address_table: u16,
+    data: u16,
+    length: usize,
+    block: PhantomData<T>,
+}
+
+impl<T: IndirectBlock> IndirectReader<T> {
+    /// Maps the registers of `T`, in this order, using the control table `registers` of the same model.
+    pub fn new(registers: &[RegisterInfo]) -> Result<Self> {
+        let (Some(address_table), Some(data)) = (
+            control_table::find(registers, "indirect_address_1"),
+            control_table::find(registers, "indirect_data_1"),
+        ) else {
+            return Err(Box::new(IndirectError::Unsupported));
+        };
+
+        let registers = T::names()
+            .into_iter()
+            .map(|name| {
+                control_table::find(registers, name)
+                    .copied()
+                    .ok_or_else(|| ControlTableError::UnknownRegister(name.to_string()))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let lengths: Vec<usize> = registers.iter().map(|r| r.length as usize).collect();
+        if lengths != T::sizes() {
+            return Err(Box::new(IndirectError::LayoutMismatch(T::sizes(), lengths)));
+        }
+        let length = lengths.iter().sum();
+        if length > INDIRECT_ENTRIES {
+            return Err(Box::new(IndirectError::TooLong(length)));
+        }
+
+        Ok(IndirectReader {
+            registers,
+            address_table: address_table.addr,
+            data: data.addr,
+            length,
+            block: PhantomData,
+        })
+    }
+
+    /// The mapped registers
+    pub fn registers(&self) -> &[RegisterInfo] {
+        &self.registers
+    }
+
+    /// Programs the indirect address table of the servos, one entry per byte of the mapped registers
+    pub fn program(
+        &self,
+        dph: &DynamixelProtocolHandler,
+        serial_port: &mut dyn serialport::SerialPort,
+        ids: &[u8],
+    ) -> Result<()> {
+        let table: Vec<u8> = self
+            .registers
+            .iter()
+            .flat_map(|r| (0..r.length as u16).map(move |i| r.addr + i))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+
+        for &id in ids {
+            dph.write(serial_port, id, self.address_table, &table)?;
+        }
+        Ok(())
+    }
+
+    /// Reads the mapped registers of the servos, with a single sync read
+    pub fn sync_read(
+        &self,
+        dph: &DynamixelProtocolHandler,
+        serial_port: &mut dyn serialport::SerialPort,
+        ids: &[u8],
+    ) -> Result<Vec<T::Values>> {
+        let blocks = dph.sync_read(serial_port, ids, self.data, self.length as u16)?;
+        if blocks.iter().any(|b| b.len() != self.length) {
+            return Err(Box::new(CommunicationErrorKind::ParsingError));
+        }
+        blocks.iter().map(|b| T::decode(b)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV2, MockSerialPort},
+        servo::{
+            conversion::Conversion,
+            dynamixel::{mx, xl330},
+        },
+    };
+
+    #[test]
+    fn packed_sync_read() {
+        use xl330::registers::{
+            present_current, present_position, present_temperature, present_velocity,
+        };
+
+        let dph = DynamixelProtocolHandler::v2();
+        let mut port = MockSerialPort::new(
+            FakeServosV2::default()
+                .with_servo(1, 1200)
+                .with_servo(2, 1200),
+        );
+
+        let reader = IndirectReader::<(
+            present_position,
+            present_velocity,
+            present_current,
+            present_temperature,
+        )>::new(xl330::REGISTERS)
+        .unwrap();
+        reader.program(&dph, &mut port, &[1, 2]).unwrap();
+
+        // Indirect address 1 points to the first byte of present_position
+        assert_eq!(dph.read(&mut port, 1, 168, 4).unwrap(), [132, 0, 133, 0]);
+
+        for (id, position) in [(1, 1000_i32), (2, -5000)] {
+            dph.write(&mut port, id, 132, &position.to_le_bytes())
+                .unwrap();
+            dph.write(&mut port, id, 128, &(-12_i32).to_le_bytes())
+                .unwrap();
+            dph.write(&mut port, id, 126, &(300_i16).to_le_bytes())
+                .unwrap();
+            dph.write(&mut port, id, 146, &[40 + id]).unwrap();
+        }
+
+        let velocity = xl330::Velocity::from_raw(-12);
+        let current = xl330::Current::from_raw(300);
+        assert_eq!(
+            reader.sync_read(&dph, &mut port, &[1, 2]).unwrap(),
+            [
+                (
+                    xl330::AnglePosition::from_raw(1000),
+                    velocity,
+                    current,
+                    41.0
+                ),
+                (
+                    xl330::AnglePosition::from_raw(-5000),
+                    velocity,
+                    current,
+                    42.0
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_mappings() {
+        fn err<T: IndirectBlock>(r: Result<IndirectReader<T>>) -> IndirectError {
+            *r.err().unwrap().downcast::<IndirectError>().unwrap()
+        }
+
+        assert_eq!(
+            err(IndirectReader::<(
+                xl330::registers::present_position,
+                mx::registers::present_position,
+            )>::new(xl330::REGISTERS)),
+            IndirectError::LayoutMismatch(vec![4, 2], vec![4, 4])
+        );
+        assert_eq!(
+            err(IndirectReader::<(
+                mx::registers::present_position,
+                mx::registers::present_temperature,
+            )>::new(mx::REGISTERS)),
+            IndirectError::Unsupported
+        );
+
+        use xl330::registers::{
+            goal_position, goal_velocity, homing_offset, present_position, present_velocity,
+            velocity_limit,
+        };
+        assert!(IndirectReader::<(
+            present_position,
+            goal_position,
+            present_velocity,
+            goal_velocity,
+            homing_offset,
+            velocity_limit,
+        )>::new(xl330::REGISTERS)
+        .is_err());
+    }
+}
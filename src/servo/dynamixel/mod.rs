@@ -1,5 +1,37 @@
 pub mod ax;
+pub mod indirect;
 pub mod mx;
+pub mod p_series;
+pub mod ph42;
+pub mod ph54;
+pub mod pm42;
+pub mod pm54;
+pub mod x_series;
+pub mod xc330;
+pub mod xh430;
 pub mod xl320;
 pub mod xl330;
 pub mod xl430;
+pub mod xm;
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::servo::conversion::enum_conversion;
+
+/// Which instructions get a status packet back (*status_return_level* register)
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum StatusReturnLevel {
+    /// Only PING gets a reply
+    PingOnly = 0,
+    /// PING and READ get a reply
+    ReadOnly = 1,
+    /// All instructions get a reply
+    All = 2,
+}
+enum_conversion!(StatusReturnLevel, u8);
@@ -9,24 +9,32 @@
 
 use std::f64::consts::PI;
 
-use crate::{generate_servo, servo::conversion::Conversion};
+use crate::{
+    generate_servo,
+    servo::{
+        baudrate::BaudrateTable,
+        conversion::{self, flags_conversion, Conversion, Temperature},
+        dynamixel::StatusReturnLevel,
+        multi_turn::impl_multi_turn,
+    },
+};
 
 generate_servo!(
     MX, v1,
     reg: (model_number, r, 0, u16, None),
     reg: (firmware_version, r, 2, u8, None),
     reg: (id, rw, 3, u8, None),
-    reg: (baudrate, rw, 4, u8, None),
+    reg: (baudrate, rw, 4, u8, Baudrate),
     reg: (return_delay_time, rw, 5, u8, None),
-    reg: (cw_angle_limit, rw, 6, i16, AnglePosition),
-    reg: (ccw_angle_limit, rw, 8, i16, AnglePosition),
-    reg: (temperature_limit, rw, 11, u8, None),
-    reg: (min_voltage_limit, rw, 12, u8, None),
-    reg: (max_voltage_limit, rw, 13, u8, None),
-    reg: (max_torque, rw, 14, u16, None),
-    reg: (status_return_level, rw, 16, u8, None),
-    reg: (alarm_led, rw, 17, u8, None),
-    reg: (shutdown, rw, 18, u8, None),
+    reg: (cw_angle_limit, rw, 6, i16, AnglePosition, unit),
+    reg: (ccw_angle_limit, rw, 8, i16, AnglePosition, unit),
+    reg: (temperature_limit, rw, 11, u8, Temperature, unit),
+    reg: (min_voltage_limit, rw, 12, u8, Voltage, unit),
+    reg: (max_voltage_limit, rw, 13, u8, Voltage, unit),
+    reg: (max_torque, rw, 14, u16, TorqueLimit),
+    reg: (status_return_level, rw, 16, u8, StatusReturnLevel),
+    reg: (alarm_led, rw, 17, u8, Alarm),
+    reg: (shutdown, rw, 18, u8, Alarm),
     reg: (multi_turn_offset, rw, 20, i16, None),
     reg: (resolution_divider, rw, 22, u8, None),
     reg: (torque_enable, rw, 24, u8, None),
@@ -34,44 +42,34 @@ generate_servo!(
     reg: (d_gain, rw, 26, u8, None),
     reg: (i_gain, rw, 27, u8, None),
     reg: (p_gain, rw, 28, u8, None),
-    reg: (goal_position, rw, 30, i16, AnglePosition),
+    reg: (goal_position, rw, 30, i16, AnglePosition, unit),
     reg: (moving_speed, rw, 32, u16, None),
-    reg: (torque_limit, rw, 34, u16, None),
-    reg: (present_position, r, 36, i16, AnglePosition),
-    reg: (present_speed, r, 38, u16, None),
-    reg: (present_load, r, 40, u16, None),
-    reg: (present_voltage, r, 42, u8, None),
-    reg: (present_temperature, r, 43, u8, None),
+    reg: (torque_limit, rw, 34, u16, TorqueLimit),
+    reg: (present_position, r, 36, i16, AnglePosition, unit),
+    reg: (present_speed, r, 38, u16, Velocity, unit),
+    reg: (present_load, r, 40, u16, Load),
+    reg: (present_voltage, r, 42, u8, Voltage, unit),
+    reg: (present_temperature, r, 43, u8, Temperature, unit),
     reg: (registered, r, 44, u8, None),
     reg: (moving, r, 46, u8, None),
     reg: (lock, rw, 47, u8, None),
     reg: (punch, rw, 48, u16, None),
     reg: (realtime_tick, r, 50, u16, None),
     reg: (goal_acceleration, rw, 73, u8, None),
+    group: (goal_position_speed_torque, rw, [goal_position, moving_speed, torque_limit]),
+    group: (present_position_speed_load, r, [present_position, present_speed, present_load]),
 );
 
-/// Sync read present_position, present_speed and present_load in one message
-///
-/// reg_read_only!(present_position_speed_load, 36, (i16, u16, u16))
-pub fn sync_read_present_position_speed_load(
-    dph: &crate::DynamixelProtocolHandler,
-    serial_port: &mut dyn serialport::SerialPort,
-    ids: &[u8],
-) -> crate::Result<Vec<(i16, u16, u16)>> {
-    let val = dph.sync_read(serial_port, ids, 36, 2 + 2 + 2)?;
-    let val = val
-        .iter()
-        .map(|v| {
-            (
-                i16::from_le_bytes(v[0..2].try_into().unwrap()),
-                u16::from_le_bytes(v[2..4].try_into().unwrap()),
-                u16::from_le_bytes(v[4..6].try_into().unwrap()),
-            )
-        })
-        .collect();
-
-    Ok(val)
-}
+impl_multi_turn!(Mx, present: 36, goal: 30, i16);
+
+/// Velocity in rad/s (0.114 rpm unit, bit 10 is the direction)
+pub type Velocity = conversion::Velocity<u16, 114_000, 10>;
+/// Load normalized to [-1.0, 1.0] (bit 10 is the direction)
+pub type Load = conversion::Load<u16, 1023, 10>;
+/// Torque limit normalized to [0.0, 1.0]
+pub type TorqueLimit = conversion::Load<u16, 1023>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u8>;
 
 pub struct AnglePosition;
 
@@ -88,6 +86,132 @@ impl Conversion for AnglePosition {
     }
 }
 
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
+
+/// Communication speed (*baudrate* register)
+///
+/// Any raw value is valid: the speed is `2_000_000 / (raw + 1)` bps, except for the 2.25M, 2.5M and 3M rates (250 to 252).
+/// The usual rates are available as constants, AX servos have their own table (up to 1Mbps, see [ax::Baudrate](super::ax::Baudrate)).
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass,
+    pyo3::pyclass(eq, name = "MxBaudrate")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Baudrate(pub u8);
+
+impl Baudrate {
+    pub const B9600: Baudrate = Baudrate(207);
+    pub const B19200: Baudrate = Baudrate(103);
+    pub const B57600: Baudrate = Baudrate(34);
+    pub const B115200: Baudrate = Baudrate(16);
+    pub const B200000: Baudrate = Baudrate(9);
+    pub const B250000: Baudrate = Baudrate(7);
+    pub const B400000: Baudrate = Baudrate(4);
+    pub const B500000: Baudrate = Baudrate(3);
+    pub const B1000000: Baudrate = Baudrate(1);
+    pub const B2000000: Baudrate = Baudrate(0);
+    pub const B2250000: Baudrate = Baudrate(250);
+    pub const B2500000: Baudrate = Baudrate(251);
+    pub const B3000000: Baudrate = Baudrate(252);
+}
+
+impl Conversion for Baudrate {
+    type RegisterType = u8;
+    type UsiType = Baudrate;
+
+    fn from_raw(raw: u8) -> Baudrate {
+        Baudrate(raw)
+    }
+
+    fn to_raw(value: Baudrate) -> u8 {
+        value.0
+    }
+}
+
+impl BaudrateTable for Baudrate {
+    const ALL: &'static [Self] = &[
+        Baudrate::B9600,
+        Baudrate::B19200,
+        Baudrate::B57600,
+        Baudrate::B115200,
+        Baudrate::B200000,
+        Baudrate::B250000,
+        Baudrate::B400000,
+        Baudrate::B500000,
+        Baudrate::B1000000,
+        Baudrate::B2000000,
+        Baudrate::B2250000,
+        Baudrate::B2500000,
+        Baudrate::B3000000,
+    ];
+
+    fn bps(&self) -> u32 {
+        match self.0 {
+            250 => 2_250_000,
+            251 => 2_500_000,
+            252 => 3_000_000,
+            raw => divider_bps(raw),
+        }
+    }
+}
+
+/// Speed of the protocol v1 `2_000_000 / (raw + 1)` encoding, rounded to the usual rates
+pub(crate) fn divider_bps(raw: u8) -> u32 {
+    match raw {
+        16 => 115_200,
+        34 => 57_600,
+        103 => 19_200,
+        207 => 9_600,
+        raw => 2_000_000 / (raw as u32 + 1),
+    }
+}
+
+#[cfg(feature = "python")]
+#[pyo3_stub_gen::derive::gen_stub_pymethods]
+#[pyo3::pymethods]
+impl Baudrate {
+    #[new]
+    fn py_new(raw: u8) -> Self {
+        Baudrate(raw)
+    }
+
+    #[getter(raw)]
+    fn py_raw(&self) -> u8 {
+        self.0
+    }
+
+    #[getter(bps)]
+    fn py_bps(&self) -> u32 {
+        self.bps()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+bitflags::bitflags! {
+    /// Errors triggering the LED (*alarm_led* register) or a torque *shutdown*
+    ///
+    /// Same bits as the error field of v1 status packets.
+    #[cfg_attr(feature = "python", pyo3_stub_gen::derive::gen_stub_pyclass, pyo3::pyclass(name = "MxAlarm"))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Alarm: u8 {
+        const INPUT_VOLTAGE = 1 << 0;
+        const ANGLE_LIMIT = 1 << 1;
+        const OVERHEATING = 1 << 2;
+        const RANGE = 1 << 3;
+        const CHECKSUM = 1 << 4;
+        const OVERLOAD = 1 << 5;
+        const INSTRUCTION = 1 << 6;
+    }
+}
+flags_conversion!(Alarm, u8);
+
 /// Unit conversion for MX motors
 pub mod conv {
     /// Dynamixel absolute speed to radians per second
@@ -183,6 +307,57 @@ mod tests {
         assert_eq!(AnglePosition::from_raw(2048), 0.0);
     }
 
+    #[test]
+    fn configuration_types() {
+        use super::{Alarm, Baudrate, StatusReturnLevel};
+        use crate::servo::{baudrate::BaudrateTable, conversion::RegisterConversion};
+
+        assert_eq!(Baudrate::from_raw(1), Baudrate::B1000000);
+        assert_eq!(Baudrate::to_raw(Baudrate::B57600), 34);
+        assert_eq!(Baudrate::from_raw(0).bps(), 2_000_000);
+        assert_eq!(Baudrate::from_raw(2).bps(), 666_666);
+        assert_eq!(Baudrate::from_raw(252).bps(), 3_000_000);
+        assert_eq!(
+            Alarm::try_from_raw(0b100100),
+            Ok(Alarm::OVERHEATING | Alarm::OVERLOAD)
+        );
+        assert_eq!(Alarm::try_from_raw(0x80).map(|a| a.bits()), Ok(0x80));
+        assert_eq!(StatusReturnLevel::decode(2), Ok(StatusReturnLevel::All));
+        assert!(StatusReturnLevel::decode(3).is_err());
+    }
+
+    #[test]
+    fn register_groups() {
+        use super::*;
+        use crate::{
+            dynamixel_protocol::mock::{FakeServosV1, MockSerialPort},
+            DynamixelProtocolHandler,
+        };
+
+        let dph = DynamixelProtocolHandler::v1();
+        let mut port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 29, 3));
+
+        let goal = MxGoalPositionSpeedTorque {
+            goal_position: 0.0,
+            moving_speed: 100,
+            torque_limit: 1.0,
+        };
+        write_goal_position_speed_torque(&dph, &mut port, 1, &goal).unwrap();
+        assert_eq!(read_raw_goal_position(&dph, &mut port, 1).unwrap(), 2048);
+        assert_eq!(read_moving_speed(&dph, &mut port, 1).unwrap(), 100);
+        assert_eq!(read_raw_torque_limit(&dph, &mut port, 1).unwrap(), 1023);
+        assert_eq!(
+            read_goal_position_speed_torque(&dph, &mut port, 1).unwrap(),
+            goal
+        );
+
+        dph.write(&mut port, 1, 36, &[0, 8, 44, 5, 0, 0]).unwrap();
+        let present = read_present_position_speed_load(&dph, &mut port, 1).unwrap();
+        assert_eq!(present.present_position, 0.0);
+        assert_eq!(present.present_speed, Velocity::from_raw(1024 + 300));
+        assert_eq!(present.present_load, 0.0);
+    }
+
     #[test]
     fn abs_speed_conversions() {
         assert_eq!(rad_per_sec_to_dxl_abs_speed(0.0), 0);
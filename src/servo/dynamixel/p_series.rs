@@ -0,0 +1,177 @@
+//! Control table shared by the Dynamixel P series (protocol v2).
+//!
+//! The P series (formerly PRO) keep the X-series registers but move the RAM area above address 255 (`torque_enable` at 512),
+//! so they can only be used in protocol v2. Positions are signed, 0 being the center, and their resolution depends on the model:
+//! see [ph54](super::ph54), [ph42](super::ph42), [pm54](super::pm54) and [pm42](super::pm42).
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/p/ph54-200-s500-r/> for details.
+
+use std::f64::consts::PI;
+
+use crate::servo::conversion::{self, Conversion};
+
+/// Control table of the P series.
+///
+/// The model position conversion (`AnglePosition` alias) and the configuration types are taken from the calling module.
+macro_rules! p_control_table {
+    ($servo_name:ident) => {
+        $crate::generate_servo!(
+            $servo_name, v2,
+            reg: (model_number, r, 0, u16, None),
+            reg: (model_information, r, 2, u32, None),
+            reg: (firmware_version, r, 6, u8, None),
+            reg: (id, rw, 7, u8, None),
+            reg: (baud_rate, rw, 8, u8, Baudrate),
+            reg: (return_delay_time, rw, 9, u8, None),
+            reg: (drive_mode, rw, 10, u8, DriveMode),
+            reg: (operating_mode, rw, 11, u8, OperatingMode),
+            reg: (secondary_id, rw, 12, u8, None),
+            reg: (protocol_type, rw, 13, u8, None),
+            reg: (homing_offset, rw, 20, i32, None),
+            reg: (moving_threshold, rw, 24, u32, None),
+            reg: (temperature_limit, rw, 31, u8, Temperature, unit),
+            reg: (max_voltage_limit, rw, 32, u16, Voltage, unit),
+            reg: (min_voltage_limit, rw, 34, u16, Voltage, unit),
+            reg: (pwm_limit, rw, 36, u16, None),
+            reg: (current_limit, rw, 38, u16, CurrentLimit, unit),
+            reg: (acceleration_limit, rw, 40, u32, None),
+            reg: (velocity_limit, rw, 44, u32, VelocityLimit, unit),
+            reg: (max_position_limit, rw, 48, i32, AnglePosition, unit),
+            reg: (min_position_limit, rw, 52, i32, AnglePosition, unit),
+            reg: (external_port_mode_1, rw, 56, u8, None),
+            reg: (external_port_mode_2, rw, 57, u8, None),
+            reg: (external_port_mode_3, rw, 58, u8, None),
+            reg: (external_port_mode_4, rw, 59, u8, None),
+            reg: (startup_configuration, rw, 60, u8, None),
+            reg: (shutdown, rw, 63, u8, HardwareError),
+            reg: (indirect_address_1, rw, 168, u16, None),
+            reg: (indirect_address_2, rw, 170, u16, None),
+            reg: (indirect_address_3, rw, 172, u16, None),
+            reg: (indirect_address_4, rw, 174, u16, None),
+            reg: (indirect_address_5, rw, 176, u16, None),
+            reg: (indirect_address_6, rw, 178, u16, None),
+            reg: (torque_enable, rw, 512, u8, bool),
+            reg: (led_red, rw, 513, u8, None),
+            reg: (led_green, rw, 514, u8, None),
+            reg: (led_blue, rw, 515, u8, None),
+            reg: (status_return_level, rw, 516, u8, StatusReturnLevel),
+            reg: (registered_instruction, r, 517, u8, None),
+            reg: (hardware_error_status, r, 518, u8, HardwareError),
+            reg: (velocity_i_gain, rw, 524, u16, None),
+            reg: (velocity_p_gain, rw, 526, u16, None),
+            reg: (position_d_gain, rw, 528, u16, None),
+            reg: (position_i_gain, rw, 530, u16, None),
+            reg: (position_p_gain, rw, 532, u16, None),
+            reg: (feedforward_2nd_gain, rw, 536, u16, None),
+            reg: (feedforward_1st_gain, rw, 538, u16, None),
+            reg: (bus_watchdog, rw, 546, u8, None),
+            reg: (goal_pwm, rw, 548, i16, None),
+            reg: (goal_current, rw, 550, i16, Current, unit),
+            reg: (goal_velocity, rw, 552, i32, Velocity, unit),
+            reg: (profile_acceleration, rw, 556, u32, None),
+            reg: (profile_velocity, rw, 560, u32, None),
+            reg: (goal_position, rw, 564, i32, AnglePosition, unit),
+            reg: (realtime_tick, r, 568, u16, None),
+            reg: (moving, r, 570, u8, None),
+            reg: (moving_status, r, 571, u8, None),
+            reg: (present_pwm, r, 572, i16, None),
+            reg: (present_current, r, 574, i16, Current, unit),
+            reg: (present_velocity, r, 576, i32, Velocity, unit),
+            reg: (present_position, r, 580, i32, AnglePosition, unit),
+            reg: (velocity_trajectory, r, 584, i32, Velocity, unit),
+            reg: (position_trajectory, r, 588, i32, AnglePosition, unit),
+            reg: (present_input_voltage, r, 592, u16, Voltage, unit),
+            reg: (present_temperature, r, 594, u8, Temperature, unit),
+            reg: (external_port_data_1, rw, 600, u16, None),
+            reg: (external_port_data_2, rw, 602, u16, None),
+            reg: (external_port_data_3, rw, 604, u16, None),
+            reg: (external_port_data_4, rw, 606, u16, None),
+            reg: (indirect_data_1, rw, 634, u8, None),
+            reg: (indirect_data_2, rw, 635, u8, None),
+            reg: (indirect_data_3, rw, 636, u8, None),
+            reg: (indirect_data_4, rw, 637, u8, None),
+            reg: (indirect_data_5, rw, 638, u8, None),
+            reg: (indirect_data_6, rw, 639, u8, None),
+        );
+    };
+}
+pub(crate) use p_control_table;
+
+/// Velocity in rad/s (0.01 rpm unit)
+pub type Velocity = conversion::Velocity<i32, 10_000>;
+/// Velocity limit in rad/s (0.01 rpm unit)
+pub type VelocityLimit = conversion::Velocity<u32, 10_000>;
+/// Current in A (1 mA unit)
+pub type Current = conversion::Current<i16, 1_000>;
+/// Current limit in A (1 mA unit)
+pub type CurrentLimit = conversion::Current<u16, 1_000>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u16>;
+
+/// Position in radians, 0 being the center position
+///
+/// `HALF_TURN` is the number of counts per half revolution of the model (e.g. 501,923 for the PH54).
+pub struct AnglePosition<const HALF_TURN: i32>;
+
+impl<const HALF_TURN: i32> Conversion for AnglePosition<HALF_TURN> {
+    type RegisterType = i32;
+    type UsiType = f64;
+
+    fn from_raw(raw: i32) -> f64 {
+        raw as f64 * PI / HALF_TURN as f64
+    }
+
+    fn to_raw(value: f64) -> i32 {
+        (value * HALF_TURN as f64 / PI).round() as i32
+    }
+}
+
+#[cfg(feature = "units")]
+impl<const HALF_TURN: i32> crate::servo::units::UnitConversion for AnglePosition<HALF_TURN> {
+    type Unit = crate::servo::units::Radians;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV2, MockSerialPort},
+        servo::{
+            dynamixel::{ph54, pm42},
+            ServoKind,
+        },
+        CommunicationErrorKind, DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn high_addresses() {
+        let dph = DynamixelProtocolHandler::v2();
+        // PH54-200-S500-R
+        let mut port = MockSerialPort::new(FakeServosV2::default().with_servo(1, 2020));
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 1).unwrap(),
+            ServoKind::dynamixel_PH54200S500R
+        );
+
+        dph.write(&mut port, 1, 580, &(-501_923_i32).to_le_bytes())
+            .unwrap();
+        let position = ph54::read_present_position(&dph, &mut port, 1).unwrap();
+        assert!((position + std::f64::consts::PI).abs() < 1e-9);
+
+        ph54::write_goal_position(&dph, &mut port, 1, std::f64::consts::FRAC_PI_2).unwrap();
+        assert_eq!(
+            ph54::read_raw_goal_position(&dph, &mut port, 1).unwrap(),
+            250_962
+        );
+        assert_eq!(pm42::AnglePosition::to_raw(std::f64::consts::PI), 131_593);
+
+        // Protocol v1 can not reach the RAM area
+        let err = DynamixelProtocolHandler::v1()
+            .read(&mut port, 1, 580, 4)
+            .unwrap_err();
+        assert!(matches!(
+            *err.downcast::<CommunicationErrorKind>().unwrap(),
+            CommunicationErrorKind::AddressOutOfRange(580, 4)
+        ));
+    }
+}
@@ -0,0 +1,21 @@
+//! PH42 robotis register (protocol v2)
+//!
+//! Dynamixel P series control table (see [p_series](super::p_series)), for the PH42-020-S300-R.
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/p/ph42-020-s300-r/> for details.
+
+use crate::servo::{
+    conversion::Temperature,
+    dynamixel::{
+        p_series::{self, p_control_table},
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        StatusReturnLevel,
+    },
+};
+
+pub use super::p_series::{Current, CurrentLimit, Velocity, VelocityLimit, Voltage};
+
+p_control_table!(PH42);
+
+/// Position in radians (303,454 counts per half revolution)
+pub type AnglePosition = p_series::AnglePosition<303_454>;
@@ -0,0 +1,21 @@
+//! PH54 robotis register (protocol v2)
+//!
+//! Dynamixel P series control table (see [p_series](super::p_series)), for the PH54-100-S500-R and PH54-200-S500-R.
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/p/ph54-200-s500-r/> for details.
+
+use crate::servo::{
+    conversion::Temperature,
+    dynamixel::{
+        p_series::{self, p_control_table},
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        StatusReturnLevel,
+    },
+};
+
+pub use super::p_series::{Current, CurrentLimit, Velocity, VelocityLimit, Voltage};
+
+p_control_table!(PH54);
+
+/// Position in radians (501,923 counts per half revolution)
+pub type AnglePosition = p_series::AnglePosition<501_923>;
@@ -0,0 +1,21 @@
+//! PM42 robotis register (protocol v2)
+//!
+//! Dynamixel P series control table (see [p_series](super::p_series)), for the PM42-010-S260-R.
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/p/pm42-010-s260-r/> for details.
+
+use crate::servo::{
+    conversion::Temperature,
+    dynamixel::{
+        p_series::{self, p_control_table},
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        StatusReturnLevel,
+    },
+};
+
+pub use super::p_series::{Current, CurrentLimit, Velocity, VelocityLimit, Voltage};
+
+p_control_table!(PM42);
+
+/// Position in radians (131,593 counts per half revolution)
+pub type AnglePosition = p_series::AnglePosition<131_593>;
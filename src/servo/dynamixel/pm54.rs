@@ -0,0 +1,21 @@
+//! PM54 robotis register (protocol v2)
+//!
+//! Dynamixel P series control table (see [p_series](super::p_series)), for the PM54-040-S250-R and PM54-060-S250-R.
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/p/pm54-060-s250-r/> for details.
+
+use crate::servo::{
+    conversion::Temperature,
+    dynamixel::{
+        p_series::{self, p_control_table},
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        StatusReturnLevel,
+    },
+};
+
+pub use super::p_series::{Current, CurrentLimit, Velocity, VelocityLimit, Voltage};
+
+p_control_table!(PM54);
+
+/// Position in radians (251,173 counts per half revolution)
+pub type AnglePosition = p_series::AnglePosition<251_173>;
@@ -0,0 +1,150 @@
+//! Configuration types shared by the X-series (protocol v2) control tables.
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/x/xl330-m288/#control-table-of-eeprom-area> for details.
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::servo::{
+    baudrate::BaudrateTable,
+    conversion::{enum_conversion, flags_conversion},
+};
+
+/// Control mode (*operating_mode* register)
+///
+/// Not every model supports every mode (e.g. the XL430 has no current based modes).
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum OperatingMode {
+    Current = 0,
+    Velocity = 1,
+    Position = 3,
+    ExtendedPosition = 4,
+    CurrentBasedPosition = 5,
+    Pwm = 16,
+}
+enum_conversion!(OperatingMode, u8);
+
+/// Communication speed (*baud_rate* register)
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int)
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum Baudrate {
+    B9600 = 0,
+    B57600 = 1,
+    B115200 = 2,
+    B1000000 = 3,
+    B2000000 = 4,
+    B3000000 = 5,
+    B4000000 = 6,
+    B4500000 = 7,
+}
+enum_conversion!(Baudrate, u8);
+
+impl BaudrateTable for Baudrate {
+    const ALL: &'static [Self] = &[
+        Baudrate::B9600,
+        Baudrate::B57600,
+        Baudrate::B115200,
+        Baudrate::B1000000,
+        Baudrate::B2000000,
+        Baudrate::B3000000,
+        Baudrate::B4000000,
+        Baudrate::B4500000,
+    ];
+
+    fn bps(&self) -> u32 {
+        match self {
+            Baudrate::B9600 => 9_600,
+            Baudrate::B57600 => 57_600,
+            Baudrate::B115200 => 115_200,
+            Baudrate::B1000000 => 1_000_000,
+            Baudrate::B2000000 => 2_000_000,
+            Baudrate::B3000000 => 3_000_000,
+            Baudrate::B4000000 => 4_000_000,
+            Baudrate::B4500000 => 4_500_000,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Drive options (*drive_mode* register)
+    #[cfg_attr(feature = "python", pyo3_stub_gen::derive::gen_stub_pyclass, pyo3::pyclass)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DriveMode: u8 {
+        /// Reverse the direction of rotation
+        const REVERSE = 1 << 0;
+        /// Slave of a dual joint, following the master servo (master/slave mode, XM540 and XH540 only)
+        const DUAL_JOINT_SLAVE = 1 << 1;
+        /// Time-based profile (velocity-based when unset)
+        const TIME_BASED_PROFILE = 1 << 2;
+        /// Enable the torque when a goal is written (XL330 only)
+        const TORQUE_ON_BY_GOAL_UPDATE = 1 << 3;
+    }
+}
+flags_conversion!(DriveMode, u8);
+
+bitflags::bitflags! {
+    /// Hardware errors (*hardware_error_status* register), also used to select the errors triggering a *shutdown*
+    #[cfg_attr(feature = "python", pyo3_stub_gen::derive::gen_stub_pyclass, pyo3::pyclass)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct HardwareError: u8 {
+        const INPUT_VOLTAGE = 1 << 0;
+        const OVERHEATING = 1 << 2;
+        const MOTOR_ENCODER = 1 << 3;
+        const ELECTRICAL_SHOCK = 1 << 4;
+        const OVERLOAD = 1 << 5;
+    }
+}
+flags_conversion!(HardwareError, u8);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo::conversion::{Conversion, ConversionError, RegisterConversion};
+
+    #[test]
+    fn operating_mode() {
+        assert_eq!(
+            OperatingMode::decode(4),
+            Ok(OperatingMode::ExtendedPosition)
+        );
+        assert_eq!(OperatingMode::encode(OperatingMode::Pwm), 16);
+        assert_eq!(
+            OperatingMode::decode(2),
+            Err(ConversionError::InvalidRawValue("OperatingMode", 2))
+        );
+    }
+
+    #[test]
+    fn baudrate() {
+        assert_eq!(Baudrate::decode(3), Ok(Baudrate::B1000000));
+        assert_eq!(Baudrate::B1000000.bps(), 1_000_000);
+        assert!(Baudrate::decode(8).is_err());
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(
+            HardwareError::try_from_raw(0b100100),
+            Ok(HardwareError::OVERHEATING | HardwareError::OVERLOAD)
+        );
+        // Unknown bits are kept, not rejected
+        let reserved = HardwareError::try_from_raw(0b11).unwrap();
+        assert!(reserved.contains(HardwareError::INPUT_VOLTAGE));
+        assert_eq!(HardwareError::to_raw(reserved), 0b11);
+        assert_eq!(
+            DriveMode::try_from_raw(0b110),
+            Ok(DriveMode::DUAL_JOINT_SLAVE | DriveMode::TIME_BASED_PROFILE)
+        );
+        assert_eq!(DriveMode::to_raw(DriveMode::REVERSE), 1);
+    }
+}
@@ -0,0 +1,82 @@
+//! XC-330 robotis register (protocol v2)
+//!
+//! The XC330 shares the [XL430](super::xl430) control table, with the signed positions and current sensing of the XL330
+//! (1 mA unit, current based control modes).
+//! It should work for
+//! * XC330-M181, XC330-M288
+//! * XC330-T181, XC330-T288
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/x/xc330-m288/> for details.
+
+use crate::servo::{
+    conversion::Temperature,
+    dynamixel::{
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        xl430::xl430_control_table,
+        StatusReturnLevel,
+    },
+    multi_turn::impl_multi_turn,
+};
+
+pub use super::xl330::{AnglePosition, Current, CurrentLimit, Velocity, VelocityLimit, Voltage};
+
+xl430_control_table!(
+    XC330,
+    reg: (current_limit, rw, 38, u16, CurrentLimit, unit),
+    reg: (max_position_limit, rw, 48, i32, AnglePosition, unit),
+    reg: (min_position_limit, rw, 52, i32, AnglePosition, unit),
+    reg: (pwm_slope, rw, 62, u8, None),
+    reg: (goal_current, rw, 102, i16, Current, unit),
+    reg: (goal_position, rw, 116, i32, AnglePosition, unit),
+    reg: (present_current, r, 126, i16, Current, unit),
+    reg: (present_position, r, 132, i32, AnglePosition, unit),
+);
+
+impl_multi_turn!(
+    Xc330, present: 132, goal: 116, i32,
+    extended: |c: &mut Xc330Controller<_>, id| Ok(c.read_operating_mode(id)?[0] != OperatingMode::Position)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV2, MockSerialPort},
+        servo::ServoKind,
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn shared_table() {
+        let dph = DynamixelProtocolHandler::v2();
+        // XC330-M288 and 2XC430-W250
+        let mut port = MockSerialPort::new(
+            FakeServosV2::default()
+                .with_servo(1, 1240)
+                .with_servo(2, 1160)
+                .with_servo(3, 1160),
+        );
+
+        let kind = ServoKind::identify(&dph, &mut port, 1).unwrap();
+        assert_eq!(kind, ServoKind::dynamixel_XC330M288);
+        assert_eq!(kind.axes(), 1);
+        let kind = ServoKind::identify(&dph, &mut port, 2).unwrap();
+        assert_eq!(kind, ServoKind::dynamixel_XC430W2502);
+        assert_eq!(kind.axes(), 2);
+
+        dph.write(&mut port, 1, 126, &(-250_i16).to_le_bytes())
+            .unwrap();
+        assert_eq!(read_present_current(&dph, &mut port, 1).unwrap(), -0.25);
+        write_goal_position(&dph, &mut port, 1, 0.0).unwrap();
+        assert_eq!(read_raw_goal_position(&dph, &mut port, 1).unwrap(), 2048);
+
+        // Registers of the shared subset
+        dph.write(&mut port, 1, 146, &[45]).unwrap();
+        assert_eq!(read_present_temperature(&dph, &mut port, 1).unwrap(), 45.0);
+        assert!(crate::servo::control_table::find(REGISTERS, "present_load").is_none());
+
+        // Model specific register, like on the XL330
+        write_pwm_slope(&dph, &mut port, 1, 140).unwrap();
+        assert_eq!(dph.read(&mut port, 1, 62, 1).unwrap(), [140]);
+    }
+}
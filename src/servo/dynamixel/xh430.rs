@@ -0,0 +1,31 @@
+//! XH430 robotis register (protocol v2 and v1)
+//!
+//! Same control table as the [XM](super::xm) series, with a 1.34 mA current unit. It should work for
+//! * XH430-W210, XH430-W350
+//! * XH430-V210, XH430-V350
+//!
+//! See <https://emanual.robotis.com/docs/en/dxl/x/xh430-w350/> for details.
+
+use crate::servo::{
+    conversion::{self, Temperature},
+    dynamixel::{
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        xm::xm_control_table,
+        StatusReturnLevel,
+    },
+    multi_turn::impl_multi_turn,
+};
+
+pub use super::xm::{AnglePosition, Velocity, VelocityLimit, Voltage};
+
+xm_control_table!(XH430);
+
+impl_multi_turn!(
+    Xh430, present: 132, goal: 116, i32,
+    extended: |c: &mut Xh430Controller<_>, id| Ok(c.read_operating_mode(id)?[0] != OperatingMode::Position)
+);
+
+/// Current in A (1.34 mA unit)
+pub type Current = conversion::Current<i16, 1_340>;
+/// Current limit in A (1.34 mA unit)
+pub type CurrentLimit = conversion::Current<u16, 1_340>;
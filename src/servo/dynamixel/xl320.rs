@@ -2,64 +2,127 @@
 //!
 //! See <https://emanual.robotis.com/docs/en/dxl/x/xl320/> for details.
 
-use crate::generate_servo;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    generate_servo,
+    servo::{
+        baudrate::BaudrateTable,
+        conversion::{self, enum_conversion, flags_conversion, Conversion, Temperature},
+    },
+};
 
 generate_servo!(
     XL320, v2,
     reg: (model_number, r, 0, u16, None),
     reg: (firmware_version, r, 2, u8, None),
     reg: (id, rw, 3, u8, None),
-    reg: (baudrate, rw, 4, u8, None),
+    reg: (baudrate, rw, 4, u8, Baudrate),
     reg: (return_delay_time, rw, 5, u8, None),
-    reg: (cw_angle_limit, rw, 6, u16, None),
-    reg: (ccw_angle_limit, rw, 8, u16, None),
+    reg: (cw_angle_limit, rw, 6, i16, AnglePosition, unit),
+    reg: (ccw_angle_limit, rw, 8, i16, AnglePosition, unit),
     reg: (control_mode, rw, 11, u8, None),
-    reg: (temperature_limit, rw, 12, u8, None),
-    reg: (min_voltage_limit, rw, 13, u8, None),
-    reg: (max_voltage_limit, rw, 14, u8, None),
-    reg: (max_torque, rw, 15, u16, None),
+    reg: (temperature_limit, rw, 12, u8, Temperature, unit),
+    reg: (min_voltage_limit, rw, 13, u8, Voltage, unit),
+    reg: (max_voltage_limit, rw, 14, u8, Voltage, unit),
+    reg: (max_torque, rw, 15, u16, TorqueLimit),
     reg: (status_return_level, rw, 17, u8, None),
-    reg: (shutdown, rw, 18, u8, None),
+    reg: (shutdown, rw, 18, u8, HardwareError),
     reg: (torque_enable, rw, 24, u8, None),
     reg: (led, rw, 25, u8, None),
     reg: (d_gain, rw, 27, u8, None),
     reg: (i_gain, rw, 28, u8, None),
     reg: (p_gain, rw, 29, u8, None),
-    reg: (goal_position, rw, 30, i16, None),
+    reg: (goal_position, rw, 30, i16, AnglePosition, unit),
     reg: (moving_speed, rw, 32, u16, None),
-    reg: (torque_limit, rw, 35, u16, None),
-    reg: (present_position, r, 37, i16, None),
-    reg: (present_speed, r, 39, u16, None),
-    reg: (present_load, r, 41, u16, None),
-    reg: (present_voltage, r, 45, u8, None),
-    reg: (present_temperature, r, 46, u8, None),
+    reg: (torque_limit, rw, 35, u16, TorqueLimit),
+    reg: (present_position, r, 37, i16, AnglePosition, unit),
+    reg: (present_speed, r, 39, u16, Velocity, unit),
+    reg: (present_load, r, 41, u16, Load),
+    reg: (present_voltage, r, 45, u8, Voltage, unit),
+    reg: (present_temperature, r, 46, u8, Temperature, unit),
     reg: (registered, r, 47, u8, None),
     reg: (moving, r, 49, u8, None),
-    reg: (hardware_error_status, r, 50, u8, None),
+    reg: (hardware_error_status, r, 50, u8, HardwareError),
     reg: (punch, rw, 51, u16, None),
+    group: (present_position_speed_load, r, [present_position, present_speed, present_load]),
 );
-/// Sync read present_position, present_speed and present_load in one message
-///
-/// reg_read_only!(present_position_speed_load, 36, (i16, u16, u16))
-pub fn sync_read_present_position_speed_load(
-    dph: &crate::DynamixelProtocolHandler,
-    serial_port: &mut dyn serialport::SerialPort,
-    ids: &[u8],
-) -> crate::Result<Vec<(i16, u16, u16)>> {
-    let val = dph.sync_read(serial_port, ids, 37, 2 + 2 + 2)?;
-    let val = val
-        .iter()
-        .map(|v| {
-            (
-                i16::from_le_bytes(v[0..2].try_into().unwrap()),
-                u16::from_le_bytes(v[2..4].try_into().unwrap()),
-                u16::from_le_bytes(v[4..6].try_into().unwrap()),
-            )
-        })
-        .collect();
-
-    Ok(val)
+
+/// Velocity in rad/s (0.111 rpm unit, bit 10 is the direction)
+pub type Velocity = conversion::Velocity<u16, 111_000, 10>;
+/// Load normalized to [-1.0, 1.0] (bit 10 is the direction)
+pub type Load = conversion::Load<u16, 1023, 10>;
+/// Torque limit normalized to [0.0, 1.0]
+pub type TorqueLimit = conversion::Load<u16, 1023>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u8>;
+
+/// Position in radians, 0 being the center position (300° range over 1024 steps)
+pub struct AnglePosition;
+
+impl Conversion for AnglePosition {
+    type RegisterType = i16;
+    type UsiType = f64;
+
+    fn from_raw(raw: i16) -> f64 {
+        conv::xl320_pos_to_radians(raw)
+    }
+
+    fn to_raw(value: f64) -> i16 {
+        conv::radians_to_xl320_pos(value)
+    }
+}
+
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
+
+/// Communication speed (*baudrate* register)
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int, name = "Xl320Baudrate")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum Baudrate {
+    B9600 = 0,
+    B57600 = 1,
+    B115200 = 2,
+    B1000000 = 3,
 }
+enum_conversion!(Baudrate, u8);
+
+impl BaudrateTable for Baudrate {
+    const ALL: &'static [Self] = &[
+        Baudrate::B9600,
+        Baudrate::B57600,
+        Baudrate::B115200,
+        Baudrate::B1000000,
+    ];
+
+    fn bps(&self) -> u32 {
+        match self {
+            Baudrate::B9600 => 9_600,
+            Baudrate::B57600 => 57_600,
+            Baudrate::B115200 => 115_200,
+            Baudrate::B1000000 => 1_000_000,
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Hardware errors (*hardware_error_status* register), also used to select the errors triggering a *shutdown*
+    #[cfg_attr(feature = "python", pyo3_stub_gen::derive::gen_stub_pyclass, pyo3::pyclass(name = "Xl320HardwareError"))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct HardwareError: u8 {
+        const OVERLOAD = 1 << 0;
+        const OVERHEATING = 1 << 1;
+        const INPUT_VOLTAGE = 1 << 2;
+    }
+}
+flags_conversion!(HardwareError, u8);
 
 /// Unit conversion for XL-320 motors
 pub mod conv {
@@ -169,6 +232,17 @@ mod tests {
         assert_eq!(xl320_pos_to_radians(1024), 150.0_f64.to_radians());
     }
 
+    #[test]
+    fn register_conversions() {
+        use crate::servo::conversion::Conversion;
+        use crate::servo::dynamixel::xl320::{AnglePosition, Load, Velocity};
+
+        assert_eq!(AnglePosition::from_raw(512), 0.0);
+        assert_eq!(AnglePosition::to_raw(0.0), 512);
+        assert!(Velocity::from_raw(1024 + 99) < 0.0);
+        assert_eq!(Load::from_raw(1023), 1.0);
+    }
+
     #[test]
     fn abs_speed_conversions() {
         assert_eq!(rad_per_sec_to_xl320_abs_speed(0.0), 0);
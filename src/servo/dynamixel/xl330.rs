@@ -4,41 +4,51 @@
 
 use std::f64::consts::PI;
 
-use crate::{generate_servo, servo::conversion::Conversion};
+use crate::{
+    generate_servo,
+    servo::{
+        conversion::{self, Conversion, Temperature},
+        dynamixel::{
+            x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+            StatusReturnLevel,
+        },
+        multi_turn::impl_multi_turn,
+    },
+};
 
 generate_servo!(
     XL330, v2,
     reg: (model_number, r, 0, u16, None),
     reg: (model_information, r, 2, u32, None),
-    reg: (firmware_version, rw, 6, u8, None),
+    reg: (firmware_version, r, 6, u8, None),
     reg: (id, rw, 7, u8, None),
-    reg: (baud_rate, rw, 8, u8, None),
+    reg: (baud_rate, rw, 8, u8, Baudrate),
     reg: (return_delay_time, rw, 9, u8, None),
-    reg: (drive_mode, rw, 10, u8, None),
-    reg: (operating_mode, rw, 11, u8, None),
+    reg: (drive_mode, rw, 10, u8, DriveMode),
+    reg: (operating_mode, rw, 11, u8, OperatingMode),
     reg: (secondary_id, rw, 12, u8, None),
     reg: (protocol_type, rw, 13, u8, None),
     reg: (homing_offset, rw, 20, i32, None),
     reg: (moving_threshold, rw, 24, u32, None),
-    reg: (temperature_limit, rw, 31, u8, None),
-    reg: (max_voltage_limit, rw, 32, u16, None),
-    reg: (min_voltage_limit, rw, 34, u16, None),
+    reg: (temperature_limit, rw, 31, u8, Temperature, unit),
+    reg: (max_voltage_limit, rw, 32, u16, Voltage, unit),
+    reg: (min_voltage_limit, rw, 34, u16, Voltage, unit),
     reg: (pwm_limit, rw, 36, u16, None),
-    reg: (current_limit, rw, 38, u16, None),
-    reg: (torque_limit, rw, 38, u16, None), //Duplicate with MX name for compatibility
+    reg: (current_limit, rw, 38, u16, CurrentLimit, unit),
+    reg: (torque_limit, rw, 38, u16, CurrentLimit, unit), //Duplicate with MX name for compatibility
     reg: (acceleration_limit, rw, 40, u32, None),
-    reg: (velocity_limit, rw, 44, u32, None), //Duplicate with MX name for compatibility
-    reg: (moving_speed, rw, 44, u32, None), //Duplicate with MX name for compatibility
-    reg: (max_position_limit, rw, 48, i32, AnglePosition),
-    reg: (min_position_limit, rw, 52, i32, AnglePosition),
+    reg: (velocity_limit, rw, 44, u32, VelocityLimit, unit), //Duplicate with MX name for compatibility
+    reg: (moving_speed, rw, 44, u32, VelocityLimit, unit), //Duplicate with MX name for compatibility
+    reg: (max_position_limit, rw, 48, i32, AnglePosition, unit),
+    reg: (min_position_limit, rw, 52, i32, AnglePosition, unit),
     reg: (startup_configuration, rw, 60, u8, None),
     reg: (pwm_slope, rw, 62, u8, None),
-    reg: (shutdown, rw, 63, u8, None),
+    reg: (shutdown, rw, 63, u8, HardwareError),
     reg: (torque_enable, rw, 64, u8, bool),
     reg: (led, rw, 65, u8, None),
-    reg: (status_return_level, rw, 68, u8, None),
-    reg: (registered_instruction, rw, 69, u8, None),
-    reg: (hardware_error_status, rw, 70, u8, None),
+    reg: (status_return_level, rw, 68, u8, StatusReturnLevel),
+    reg: (registered_instruction, r, 69, u8, None),
+    reg: (hardware_error_status, r, 70, u8, HardwareError),
     reg: (velocity_i_gain, rw, 76, u16, None),
     reg: (velocity_p_gain, rw, 78, u16, None),
     reg: (position_d_gain, rw, 80, u16, None),
@@ -48,22 +58,22 @@ generate_servo!(
     reg: (feedforward_1st_gain, rw, 90, u16, None),
     reg: (bus_watchdog, rw, 98, u8, None),
     reg: (goal_pwm, rw, 100, u16, None),
-    reg: (goal_current, rw, 102, i16, None),
-    reg: (goal_velocity, rw, 104, i32, None),
+    reg: (goal_current, rw, 102, i16, Current, unit),
+    reg: (goal_velocity, rw, 104, i32, Velocity, unit),
     reg: (profile_acceleration, rw, 108, u32, None),
     reg: (profile_velocity, rw, 112, u32, None),
-    reg: (goal_position, rw, 116, i32, AnglePosition),
+    reg: (goal_position, rw, 116, i32, AnglePosition, unit),
     reg: (realtime_tick, r, 120, u16, None),
     reg: (moving, r, 122, u8, None),
     reg: (moving_status, r, 123, u8, None),
     reg: (present_pwm, r, 124, u16, None),
-    reg: (present_current, r, 126, i16, None),
-    reg: (present_velocity, r, 128, i32, None),
-    reg: (present_position, r, 132, i32, AnglePosition),
-    reg: (velocity_trajectory, r, 136, u32, None),
+    reg: (present_current, r, 126, i16, Current, unit),
+    reg: (present_velocity, r, 128, i32, Velocity, unit),
+    reg: (present_position, r, 132, i32, AnglePosition, unit),
+    reg: (velocity_trajectory, r, 136, i32, Velocity, unit),
     reg: (position_trajectory, r, 140, u32, None),
-    reg: (present_input_voltage, r, 144, u16, None),
-    reg: (present_temperature, r, 146, u8, None),
+    reg: (present_input_voltage, r, 144, u16, Voltage, unit),
+    reg: (present_temperature, r, 146, u8, Temperature, unit),
     reg: (backup_ready, r, 147, u8, None),
     reg: (indirect_address_1, rw, 168, u16, None),
     reg: (indirect_address_2, rw, 170, u16, None),
@@ -79,6 +89,22 @@ generate_servo!(
     reg: (indirect_data_6, rw, 229, u8, None),
 );
 
+impl_multi_turn!(
+    Xl330, present: 132, goal: 116, i32,
+    extended: |c: &mut Xl330Controller<_>, id| Ok(c.read_operating_mode(id)?[0] != OperatingMode::Position)
+);
+
+/// Velocity in rad/s (0.229 rpm unit)
+pub type Velocity = conversion::Velocity<i32, 229_000>;
+/// Velocity limit in rad/s (0.229 rpm unit)
+pub type VelocityLimit = conversion::Velocity<u32, 229_000>;
+/// Current in A (1 mA unit, for both the M077 and M288 variants)
+pub type Current = conversion::Current<i16, 1_000>;
+/// Current limit in A (1 mA unit)
+pub type CurrentLimit = conversion::Current<u16, 1_000>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u16>;
+
 pub struct AnglePosition;
 
 impl Conversion for AnglePosition {
@@ -94,6 +120,11 @@ impl Conversion for AnglePosition {
     }
 }
 
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
+
 /// Unit conversion for XL330 motors (same as XM?)
 pub mod conv {
     use std::f32::consts::PI;
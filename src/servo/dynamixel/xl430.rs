@@ -1,74 +1,114 @@
 //! XL-430 robotis register (protocol v2)
 //!
+//! Also used for the models sharing the same control table:
+//! * XC430-W150, XC430-W240
+//! * the dual-axis 2XL430-W250 and 2XC430-W250, each axis answering to its own id (see `ServoKind::axes`)
+//!
 //! See <https://emanual.robotis.com/docs/en/dxl/x/xm430-w350/> for details.
 
-use crate::generate_servo;
+use crate::servo::{
+    conversion::{self, Temperature},
+    dynamixel::{
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        StatusReturnLevel,
+    },
+    multi_turn::impl_multi_turn,
+};
+
+/// Registers shared by the XL430 family and the XC330, without the position and current/load registers.
+///
+/// The model registers are appended to the table, conversions and configuration types are taken from the calling module.
+macro_rules! xl430_control_table {
+    ($servo_name:ident, $($model_reg:tt)*) => {
+        $crate::generate_servo!(
+            $servo_name, v2,
+            reg: (model_number, r, 0, u16, None),
+            reg: (model_information, r, 2, u32, None),
+            reg: (firmware_version, r, 6, u8, None),
+            reg: (id, rw, 7, u8, None),
+            reg: (baud_rate, rw, 8, u8, Baudrate),
+            reg: (return_delay_time, rw, 9, u8, None),
+            reg: (drive_mode, rw, 10, u8, DriveMode),
+            reg: (operating_mode, rw, 11, u8, OperatingMode),
+            reg: (secondary_id, rw, 12, u8, None),
+            reg: (protocol_type, rw, 13, u8, None),
+            reg: (homing_offset, rw, 20, i32, None),
+            reg: (moving_threshold, rw, 24, u32, None),
+            reg: (temperature_limit, rw, 31, u8, Temperature, unit),
+            reg: (max_voltage_limit, rw, 32, u16, Voltage, unit),
+            reg: (min_voltage_limit, rw, 34, u16, Voltage, unit),
+            reg: (pwm_limit, rw, 36, u16, None),
+            reg: (velocity_limit, rw, 44, u32, VelocityLimit, unit),
+            reg: (startup_configuration, rw, 60, u8, None),
+            reg: (shutdown, rw, 63, u8, HardwareError),
+            reg: (torque_enable, rw, 64, u8, None),
+            reg: (led, rw, 65, u8, None),
+            reg: (status_return_level, rw, 68, u8, StatusReturnLevel),
+            reg: (registered_instruction, r, 69, u8, None),
+            reg: (hardware_error_status, r, 70, u8, HardwareError),
+            reg: (velocity_i_gain, rw, 76, u16, None),
+            reg: (velocity_p_gain, rw, 78, u16, None),
+            reg: (position_d_gain, rw, 80, u16, None),
+            reg: (position_i_gain, rw, 82, u16, None),
+            reg: (position_p_gain, rw, 84, u16, None),
+            reg: (feedforward_2nd_gain, rw, 88, u16, None),
+            reg: (feedforward_1st_gain, rw, 90, u16, None),
+            reg: (bus_watchdog, rw, 98, u8, None),
+            reg: (goal_pwm, rw, 100, u16, None),
+            reg: (goal_velocity, rw, 104, i32, Velocity, unit),
+            reg: (profile_acceleration, rw, 108, u32, None),
+            reg: (profile_velocity, rw, 112, u32, None),
+            reg: (realtime_tick, r, 120, u16, None),
+            reg: (moving, r, 122, u8, None),
+            reg: (moving_status, r, 123, u8, None),
+            reg: (present_pwm, r, 124, u16, None),
+            reg: (present_velocity, r, 128, i32, Velocity, unit),
+            reg: (velocity_trajectory, r, 136, i32, Velocity, unit),
+            reg: (position_trajectory, r, 140, u32, None),
+            reg: (present_input_voltage, r, 144, u16, Voltage, unit),
+            reg: (present_temperature, r, 146, u8, Temperature, unit),
+            reg: (backup_ready, r, 147, u8, None),
+            reg: (indirect_address_1, rw, 168, u16, None),
+            reg: (indirect_address_2, rw, 170, u16, None),
+            reg: (indirect_address_3, rw, 172, u16, None),
+            reg: (indirect_address_4, rw, 174, u16, None),
+            reg: (indirect_address_5, rw, 176, u16, None),
+            reg: (indirect_address_6, rw, 178, u16, None),
+            reg: (indirect_data_1, rw, 224, u8, None),
+            reg: (indirect_data_2, rw, 225, u8, None),
+            reg: (indirect_data_3, rw, 226, u8, None),
+            reg: (indirect_data_4, rw, 227, u8, None),
+            reg: (indirect_data_5, rw, 228, u8, None),
+            reg: (indirect_data_6, rw, 229, u8, None),
+            $($model_reg)*
+        );
+    };
+}
+pub(crate) use xl430_control_table;
 
-generate_servo!(
-    XL430, v2,
-    reg: (model_number, r, 0, u16, None),
-    reg: (model_information, r, 2, u32, None),
-    reg: (firmware_version, r, 6, u8, None),
-    reg: (id, rw, 7, u8, None),
-    reg: (buad_rate, rw, 8, u8, None),
-    reg: (return_delay_time, rw, 9, u8, None),
-    reg: (drive_mode, rw, 10, u8, None),
-    reg: (operating_mode, rw, 11, u8, None),
-    reg: (secondary_id, rw, 12, u8, None),
-    reg: (protocol_type, rw, 13, u8, None),
-    reg: (homing_offset, rw, 20, i32, None),
-    reg: (moving_threshold, rw, 24, u32, None),
-    reg: (temperature_limit, rw, 31, u8, None),
-    reg: (max_voltage_limit, rw, 32, u16, None),
-    reg: (min_voltage_limit, rw, 34, u16, None),
-    reg: (pwm_limit, rw, 36, u16, None),
+xl430_control_table!(
+    XL430,
+    reg: (buad_rate, rw, 8, u8, None), //Former (misspelled) name kept for compatibility
     reg: (current_limit, rw, 38, u16, None),
-    reg: (velocity_limit, rw, 44, u32, None),
     reg: (max_position_limit, rw, 48, u32, None),
     reg: (min_position_limit, rw, 52, u32, None),
-    reg: (startup_configuration, rw, 60, u8, None),
-    reg: (shutdown, rw, 63, u8, None),
-    reg: (torque_enable, rw, 64, u8, None),
-    reg: (led, rw, 65, u8, None),
-    reg: (status_return_level, rw, 68, u8, None),
-    reg: (registered_instruction, r, 69, u8, None),
-    reg: (hardware_error_status, r, 70, u8, None),
-    reg: (velocity_i_gain, rw, 76, u16, None),
-    reg: (velocity_p_gain, rw, 78, u16, None),
-    reg: (position_d_gain, rw, 80, u16, None),
-    reg: (position_i_gain, rw, 82, u16, None),
-    reg: (position_p_gain, rw, 84, u16, None),
-    reg: (feedforward_2nd_gain, rw, 88, u16, None),
-    reg: (feedforward_1st_gain, rw, 90, u16, None),
-    reg: (bus_watchdog, rw, 98, u8, None),
-    reg: (goal_pwm, rw, 100, u16, None),
     reg: (goal_current, rw, 102, u16, None),
-    reg: (goal_velocity, rw, 104, u32, None),
-    reg: (profile_acceleration, rw, 108, u32, None),
-    reg: (profile_velocity, rw, 112, u32, None),
     reg: (goal_position, rw, 116, u32, None),
-    reg: (realtime_tick, r, 120, u16, None),
-    reg: (moving, r, 122, u8, None),
-    reg: (moving_status, r, 123, u8, None),
-    reg: (present_pwm, r, 124, u16, None),
-    reg: (present_current, r, 126, u16, None),
-    reg: (present_velocity, r, 128, u32, None),
+    reg: (present_current, r, 126, u16, None), //No current sensing on XL430, the register holds the load
+    reg: (present_load, r, 126, i16, Load),
     reg: (present_position, r, 132, u32, None),
-    reg: (velocity_trajectory, r, 136, u32, None),
-    reg: (position_trajectory, r, 140, u32, None),
-    reg: (present_input_voltage, r, 144, u16, None),
-    reg: (present_temperature, r, 146, u8, None),
-    reg: (backup_ready, r, 147, u8, None),
-    reg: (indirect_address_1, rw, 168, u16, None),
-    reg: (indirect_address_2, rw, 170, u16, None),
-    reg: (indirect_address_3, rw, 172, u16, None),
-    reg: (indirect_address_4, rw, 174, u16, None),
-    reg: (indirect_address_5, rw, 176, u16, None),
-    reg: (indirect_address_6, rw, 178, u16, None),
-    reg: (indirect_data_1, rw, 224, u8, None),
-    reg: (indirect_data_2, rw, 225, u8, None),
-    reg: (indirect_data_3, rw, 226, u8, None),
-    reg: (indirect_data_4, rw, 227, u8, None),
-    reg: (indirect_data_5, rw, 228, u8, None),
-    reg: (indirect_data_6, rw, 229, u8, None),
 );
+
+impl_multi_turn!(
+    Xl430, present: 132, goal: 116, i32,
+    extended: |c: &mut Xl430Controller<_>, id| Ok(c.read_operating_mode(id)?[0] != OperatingMode::Position)
+);
+
+/// Velocity in rad/s (0.229 rpm unit)
+pub type Velocity = conversion::Velocity<i32, 229_000>;
+/// Velocity limit in rad/s (0.229 rpm unit)
+pub type VelocityLimit = conversion::Velocity<u32, 229_000>;
+/// Load normalized to [-1.0, 1.0] (0.1 % unit)
+pub type Load = conversion::Load<i16, 1000>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u16>;
@@ -1,67 +1,147 @@
-//! XM robotis register (protocol v1)
+//! XM robotis register (protocol v2 and v1)
 //!
-//! Tested for the XM430-W210 flashed in protocol v1
+//! The X-series keep the same control table when flashed in protocol v1 (tested for the XM430-W210), use
+//! `with_protocol_v1` in this case. It should work for
+//! * XM430-W210, XM430-W350
+//! * XM540-W150, XM540-W270 (the external ports are not declared)
+//! * XH540-W150, XH540-W270, XH540-V150, XH540-V270
+//! * XW540-T140, XW540-T260, XW430-T333
+//! * XW430-T200, whose model number (1280) is also the Feetech SCS0009 one: it is not detected by `ServoKind`
+//!
+//! The XH430 use the same table with another current unit, see [xh430](super::xh430).
 //!
 //! See <https://emanual.robotis.com/docs/en/dxl/x/xm430-w210> for example.
 
-use crate::device::*;
-
-reg_read_only!(model_number, 0, u16);
-reg_read_only!(firmware_version, 6, u8);
-reg_read_write!(id, 7, u8);
-reg_read_write!(baud_rate, 8, u8);
-reg_read_write!(return_delay_time, 9, u8);
-reg_read_write!(drive_mode, 10, u8);
-reg_read_write!(operating_mode, 11, u8);
-reg_read_write!(secondary_shadow_id, 12, u8);
-reg_read_write!(protocol_type, 13, u8);
-reg_read_write!(homing_offset, 20, i32);
-reg_read_write!(moving_threshold, 24, u32);
-reg_read_write!(temperature_limit, 31, u8);
-reg_read_write!(max_voltage_limit, 32, u16);
-reg_read_write!(min_voltage_limit, 34, u16);
-reg_read_write!(pwm_limit, 36, u16);
-reg_read_write!(acceleration_limit, 40, u32);
-reg_read_write!(current_limit, 38, u16);
-reg_read_write!(torque_limit, 38, u16); //Duplicate with MX name for compatibility
-reg_read_write!(velocity_limit, 44, u32);
-reg_read_write!(moving_speed, 44, u32); //Duplicate with MX name for compatibility
-reg_read_write!(max_position_limit, 48, u32);
-reg_read_write!(min_position_limit, 52, u32);
-
-reg_read_write!(shutdown, 63, u8);
-
-reg_read_write!(torque_enable, 64, u8);
-reg_read_write!(led, 65, u8);
-reg_read_write!(status_return_level, 68, u8);
-reg_read_write!(registered_instruction, 69, u8);
-reg_read_write!(hardware_error_status, 70, u8);
-reg_read_write!(velocity_i_gain, 76, u16);
-reg_read_write!(velocity_p_gain, 78, u16);
-reg_read_write!(position_d_gain, 80, u16);
-reg_read_write!(position_i_gain, 82, u16);
-reg_read_write!(position_p_gain, 84, u16);
-reg_read_write!(feedforward_2nd_gain, 88, u16);
-reg_read_write!(feedforward_1st_gain, 90, u16);
-reg_read_write!(bus_watchdog, 98, u8);
-
-reg_read_write!(goal_pwm, 100, u16);
-reg_read_write!(goal_current, 102, i16);
-reg_read_write!(goal_velocity, 104, i32);
-reg_read_write!(profile_acceleration, 108, u32);
-reg_read_write!(profile_velocity, 112, u32);
-reg_read_write!(goal_position, 116, i32);
-reg_read_only!(realtime_tick, 120, u16);
-reg_read_only!(moving, 122, u8);
-reg_read_only!(moving_status, 123, u8);
-reg_read_only!(present_pwm, 124, u16);
-reg_read_only!(present_current, 126, i16);
-reg_read_only!(present_velocity, 128, i32);
-reg_read_only!(present_position, 132, i32);
-reg_read_only!(velocity_trajectory, 136, u32);
-reg_read_only!(position_trajectory, 140, u32);
-reg_read_only!(present_input_voltage, 144, u16);
-reg_read_only!(present_temperature, 146, u8);
+use std::f64::consts::PI;
+
+use crate::servo::{
+    conversion::{self, Conversion, Temperature},
+    dynamixel::{
+        x_series::{Baudrate, DriveMode, HardwareError, OperatingMode},
+        StatusReturnLevel,
+    },
+    multi_turn::impl_multi_turn,
+};
+
+/// Control table of the XM and XH series.
+///
+/// The model conversions (`AnglePosition`, `Velocity`, `VelocityLimit`, `Current`, `CurrentLimit` and `Voltage`)
+/// and the configuration types are taken from the calling module.
+macro_rules! xm_control_table {
+    ($servo_name:ident) => {
+        $crate::generate_servo!(
+            $servo_name, v1_v2,
+            reg: (model_number, r, 0, u16, None),
+            reg: (model_information, r, 2, u32, None),
+            reg: (firmware_version, r, 6, u8, None),
+            reg: (id, rw, 7, u8, None),
+            reg: (baud_rate, rw, 8, u8, Baudrate),
+            reg: (return_delay_time, rw, 9, u8, None),
+            reg: (drive_mode, rw, 10, u8, DriveMode),
+            reg: (operating_mode, rw, 11, u8, OperatingMode),
+            reg: (secondary_id, rw, 12, u8, None),
+            reg: (protocol_type, rw, 13, u8, None),
+            reg: (homing_offset, rw, 20, i32, None),
+            reg: (moving_threshold, rw, 24, u32, None),
+            reg: (temperature_limit, rw, 31, u8, Temperature, unit),
+            reg: (max_voltage_limit, rw, 32, u16, Voltage, unit),
+            reg: (min_voltage_limit, rw, 34, u16, Voltage, unit),
+            reg: (pwm_limit, rw, 36, u16, None),
+            reg: (current_limit, rw, 38, u16, CurrentLimit, unit),
+            reg: (torque_limit, rw, 38, u16, CurrentLimit, unit), //Duplicate with MX name for compatibility
+            reg: (acceleration_limit, rw, 40, u32, None),
+            reg: (velocity_limit, rw, 44, u32, VelocityLimit, unit),
+            reg: (moving_speed, rw, 44, u32, VelocityLimit, unit), //Duplicate with MX name for compatibility
+            reg: (max_position_limit, rw, 48, i32, AnglePosition, unit),
+            reg: (min_position_limit, rw, 52, i32, AnglePosition, unit),
+            reg: (startup_configuration, rw, 60, u8, None),
+            reg: (shutdown, rw, 63, u8, HardwareError),
+            reg: (torque_enable, rw, 64, u8, bool),
+            reg: (led, rw, 65, u8, None),
+            reg: (status_return_level, rw, 68, u8, StatusReturnLevel),
+            reg: (registered_instruction, r, 69, u8, None),
+            reg: (hardware_error_status, r, 70, u8, HardwareError),
+            reg: (velocity_i_gain, rw, 76, u16, None),
+            reg: (velocity_p_gain, rw, 78, u16, None),
+            reg: (position_d_gain, rw, 80, u16, None),
+            reg: (position_i_gain, rw, 82, u16, None),
+            reg: (position_p_gain, rw, 84, u16, None),
+            reg: (feedforward_2nd_gain, rw, 88, u16, None),
+            reg: (feedforward_1st_gain, rw, 90, u16, None),
+            reg: (bus_watchdog, rw, 98, u8, None),
+            reg: (goal_pwm, rw, 100, u16, None),
+            reg: (goal_current, rw, 102, i16, Current, unit),
+            reg: (goal_velocity, rw, 104, i32, Velocity, unit),
+            reg: (profile_acceleration, rw, 108, u32, None),
+            reg: (profile_velocity, rw, 112, u32, None),
+            reg: (goal_position, rw, 116, i32, AnglePosition, unit),
+            reg: (realtime_tick, r, 120, u16, None),
+            reg: (moving, r, 122, u8, None),
+            reg: (moving_status, r, 123, u8, None),
+            reg: (present_pwm, r, 124, u16, None),
+            reg: (present_current, r, 126, i16, Current, unit),
+            reg: (present_velocity, r, 128, i32, Velocity, unit),
+            reg: (present_position, r, 132, i32, AnglePosition, unit),
+            reg: (velocity_trajectory, r, 136, i32, Velocity, unit),
+            reg: (position_trajectory, r, 140, u32, None),
+            reg: (present_input_voltage, r, 144, u16, Voltage, unit),
+            reg: (present_temperature, r, 146, u8, Temperature, unit),
+            reg: (backup_ready, r, 147, u8, None),
+            reg: (indirect_address_1, rw, 168, u16, None),
+            reg: (indirect_address_2, rw, 170, u16, None),
+            reg: (indirect_address_3, rw, 172, u16, None),
+            reg: (indirect_address_4, rw, 174, u16, None),
+            reg: (indirect_address_5, rw, 176, u16, None),
+            reg: (indirect_address_6, rw, 178, u16, None),
+            reg: (indirect_data_1, rw, 224, u8, None),
+            reg: (indirect_data_2, rw, 225, u8, None),
+            reg: (indirect_data_3, rw, 226, u8, None),
+            reg: (indirect_data_4, rw, 227, u8, None),
+            reg: (indirect_data_5, rw, 228, u8, None),
+            reg: (indirect_data_6, rw, 229, u8, None),
+        );
+    };
+}
+pub(crate) use xm_control_table;
+
+xm_control_table!(XM);
+
+impl_multi_turn!(
+    Xm, present: 132, goal: 116, i32,
+    extended: |c: &mut XmController<_>, id| Ok(c.read_operating_mode(id)?[0] != OperatingMode::Position)
+);
+
+/// Velocity in rad/s (0.229 rpm unit)
+pub type Velocity = conversion::Velocity<i32, 229_000>;
+/// Velocity limit in rad/s (0.229 rpm unit)
+pub type VelocityLimit = conversion::Velocity<u32, 229_000>;
+/// Current in A (2.69 mA unit)
+pub type Current = conversion::Current<i16, 2_690>;
+/// Current limit in A (2.69 mA unit)
+pub type CurrentLimit = conversion::Current<u16, 2_690>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u16>;
+
+/// Position in radians, 0 being the center position (4096 steps per revolution)
+pub struct AnglePosition;
+
+impl Conversion for AnglePosition {
+    type RegisterType = i32;
+    type UsiType = f64;
+
+    fn from_raw(raw: i32) -> f64 {
+        (2.0 * PI * (raw as f64) / 4096.0) - PI
+    }
+
+    fn to_raw(value: f64) -> i32 {
+        (4096.0 * (PI + value) / (2.0 * PI)) as i32
+    }
+}
+
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
 
 /// Unit conversion for XM motors
 pub mod conv {
@@ -71,7 +151,6 @@ pub mod conv {
     ///
     /// Works in joint and multi-turn mode
     /// 2048->180° is the center position with 0.088 [deg/pulse]
-
     pub fn dxl_pos_to_radians(pos: i32) -> f32 {
         (2.0 * PI * (pos as f32) / 4096.0) - PI
     }
@@ -152,3 +231,55 @@ pub mod conv {
         (volt / 0.1) as u16
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV1, FakeServosV2, MockSerialPort},
+        servo::{dynamixel::xh430, ServoKind},
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn both_protocols() {
+        // XM430-W210
+        let port = MockSerialPort::new(FakeServosV2::default().with_servo(1, 1030));
+        let mut c = XmController::new()
+            .with_protocol_v2()
+            .with_serial_port(Box::new(port));
+        c.write_raw_data(1, 132, 2048_i32.to_le_bytes().to_vec())
+            .unwrap();
+        assert_eq!(c.read_present_position(1).unwrap(), [0.0]);
+
+        let port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 1030, 7));
+        let mut c = XmController::new()
+            .with_protocol_v1()
+            .with_serial_port(Box::new(port));
+        c.write_raw_goal_position(1, 1024).unwrap();
+        assert_eq!(c.read_model_number(1).unwrap(), [1030]);
+        assert_eq!(c.read_raw_goal_position(1).unwrap(), [1024]);
+    }
+
+    #[test]
+    fn models() {
+        let dph = DynamixelProtocolHandler::v2();
+        let mut port = MockSerialPort::new(
+            FakeServosV2::default()
+                .with_servo(1, 1120)
+                .with_servo(2, 1000),
+        );
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 1).unwrap(),
+            ServoKind::dynamixel_XM540W270
+        );
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 2).unwrap(),
+            ServoKind::dynamixel_XH430W350
+        );
+
+        // Same raw current, half the unit on the XH430
+        assert!((Current::from_raw(100) - 0.269).abs() < 1e-9);
+        assert!((xh430::Current::from_raw(100) - 0.134).abs() < 1e-9);
+    }
+}
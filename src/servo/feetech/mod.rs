@@ -1,2 +1,54 @@
 pub mod scs0009;
+pub mod sms;
 pub mod sts3215;
+pub mod sts3250;
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::servo::{baudrate::BaudrateTable, conversion::enum_conversion};
+
+/// Communication speed (*baudrate* register), shared by the STS and SCS series
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int, name = "FeetechBaudrate")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum Baudrate {
+    B1000000 = 0,
+    B500000 = 1,
+    B250000 = 2,
+    B128000 = 3,
+    B115200 = 4,
+    B76800 = 5,
+    B57600 = 6,
+    B38400 = 7,
+}
+enum_conversion!(Baudrate, u8);
+
+impl BaudrateTable for Baudrate {
+    const ALL: &'static [Self] = &[
+        Baudrate::B1000000,
+        Baudrate::B500000,
+        Baudrate::B250000,
+        Baudrate::B128000,
+        Baudrate::B115200,
+        Baudrate::B76800,
+        Baudrate::B57600,
+        Baudrate::B38400,
+    ];
+
+    fn bps(&self) -> u32 {
+        match self {
+            Baudrate::B1000000 => 1_000_000,
+            Baudrate::B500000 => 500_000,
+            Baudrate::B250000 => 250_000,
+            Baudrate::B128000 => 128_000,
+            Baudrate::B115200 => 115_200,
+            Baudrate::B76800 => 76_800,
+            Baudrate::B57600 => 57_600,
+            Baudrate::B38400 => 38_400,
+        }
+    }
+}
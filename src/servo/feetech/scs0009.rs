@@ -1,18 +1,26 @@
+//! Feetech SCS0009 register (protocol v1, big-endian registers)
+//!
+//! 1024 steps over 300°.
+
 use crate::generate_servo;
-use crate::servo::conversion::Conversion;
+use crate::servo::conversion::{self, Conversion, Temperature};
+use crate::servo::feetech::Baudrate;
 
 generate_servo!(
     SCS0009, v1,
+    byte_order: big_endian,
+    reg: (firmware_major_version, r, 0, u8, None),
+    reg: (firmware_minor_version, r, 1, u8, None),
     reg: (model, r, 3, u16, None),
     reg: (id, rw, 5, u8, None),
-    reg: (baudrate, rw, 6, u8, None),
+    reg: (baudrate, rw, 6, u8, Baudrate),
     reg: (return_delay_time, rw, 7, u8, None), //RESERVED?
     reg: (response_status_level, rw, 8, u8, None),
-    reg: (min_angle_limit, rw, 9, i16, AnglePosition),
-    reg: (max_angle_limit, rw, 11, i16, AnglePosition),
-    reg: (max_temperature_limit, rw, 13, u8, None),
-    reg: (max_voltage_limit, rw, 14, u8, None),
-    reg: (min_voltage_limit, rw, 15, u8, None),
+    reg: (min_angle_limit, rw, 9, i16, AnglePosition, unit),
+    reg: (max_angle_limit, rw, 11, i16, AnglePosition, unit),
+    reg: (max_temperature_limit, rw, 13, u8, Temperature, unit),
+    reg: (max_voltage_limit, rw, 14, u8, Voltage, unit),
+    reg: (min_voltage_limit, rw, 15, u8, Voltage, unit),
     reg: (max_torque_limit, rw, 16, u16, TorqueLimit),
     reg: (phase, rw, 18, u8, None), //SPECIAL REG
     reg: (unloading_condition, rw, 19, u8, None),
@@ -20,7 +28,7 @@ generate_servo!(
     reg: (p_coefficient, rw, 21, u8, None),
     reg: (d_coefficient, rw, 22, u8, None),
     reg: (i_coefficient, rw, 23, u8, None),
-    reg: (minimum_startup_force, rw, 24, u16, BigEndian_u16),
+    reg: (minimum_startup_force, rw, 24, u16, None),
     reg: (cw_dead_zone, rw, 26, u8, None),
     reg: (ccw_dead_zone, rw, 27, u8, None),
     reg: (hysteresis_loop, rw, 27, u8, None),
@@ -31,24 +39,27 @@ generate_servo!(
 
     reg: (torque_enable, rw, 40, u8, None),
 
-    reg: (goal_position, rw, 42, i16, AnglePosition),
-    reg: (goal_time, rw, 44, u16, BigEndian_u16),
-    reg: (goal_speed, rw, 46, u16, Velocity),
+    reg: (goal_position, rw, 42, i16, AnglePosition, unit),
+    reg: (goal_time, rw, 44, u16, None),
+    reg: (goal_speed, rw, 46, u16, Velocity, unit),
 
     reg: (lock, rw, 48, u8, bool),
-    reg: (present_position, r, 56, i16, AnglePosition),
-    reg: (present_speed, r, 58, u16, Velocity),
-    reg: (present_load, r, 60, u16, BigEndian_i16),
+    reg: (present_position, r, 56, i16, AnglePosition, unit),
+    reg: (present_speed, r, 58, u16, Velocity, unit),
+    reg: (present_load, r, 60, u16, Load),
 
-    reg: (present_voltage, r, 62, u8, None),
-    reg: (present_temperature, r, 63, u8, None),
+    reg: (present_voltage, r, 62, u8, Voltage, unit),
+    reg: (present_temperature, r, 63, u8, Temperature, unit),
 
     reg: (status, r, 65, u8, None),
 
     reg: (moving, r, 66, u8, bool),
-
 );
 
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u8>;
+
+/// Velocity in rad/s (300° over 1024 steps, bit 15 is the direction)
 pub struct Velocity;
 
 impl Conversion for Velocity {
@@ -56,19 +67,24 @@ impl Conversion for Velocity {
     type UsiType = f64;
 
     fn from_raw(raw: u16) -> f64 {
-        // println!("DEBUG SPEED: {:?}", raw.to_be());
-        if raw.to_be() > (1 << 15) {
-            -300.0_f64.to_radians() / 1024.0 * (raw.to_be() & 0x3ff) as f64
+        if raw > (1 << 15) {
+            -300.0_f64.to_radians() / 1024.0 * (raw & 0x3ff) as f64
         } else {
-            300.0_f64.to_radians() / 1024.0 * (raw.to_be() & 0x3ff) as f64
+            300.0_f64.to_radians() / 1024.0 * (raw & 0x3ff) as f64
         }
     }
 
     fn to_raw(value: f64) -> u16 {
-        ((value / (300.0_f64.to_radians() / 1024.0)) as u16).to_be()
+        (value / (300.0_f64.to_radians() / 1024.0)) as u16
     }
 }
 
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for Velocity {
+    type Unit = crate::servo::units::RadiansPerSecond;
+}
+
+/// Angle in rad (300° over 1024 steps centered on 511)
 pub struct AnglePosition;
 
 impl Conversion for AnglePosition {
@@ -76,48 +92,21 @@ impl Conversion for AnglePosition {
     type UsiType = f64;
 
     fn from_raw(raw: i16) -> f64 {
-        300.0_f64.to_radians() * (((raw.to_be() & 0x3ff) - 511) as f64) / 1024.0
+        300.0_f64.to_radians() * (((raw & 0x3ff) - 511) as f64) / 1024.0
     }
 
     fn to_raw(value: f64) -> i16 {
-        let a = (1024.0 * (value) / (300.0_f64.to_radians()) + 511.0) as i16;
-        a.to_be()
+        (1024.0 * (value) / (300.0_f64.to_radians()) + 511.0) as i16
     }
 }
 
-#[allow(non_camel_case_types)]
-pub struct BigEndian_u16;
-impl Conversion for BigEndian_u16 {
-    type RegisterType = u16;
-    type UsiType = u16;
-
-    fn from_raw(raw: u16) -> u16 {
-        raw.to_be()
-    }
-
-    fn to_raw(value: u16) -> u16 {
-        value.to_be()
-    }
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
 }
 
-#[allow(non_camel_case_types)]
-pub struct BigEndian_i16;
-impl Conversion for BigEndian_i16 {
-    type RegisterType = u16;
-    type UsiType = i16;
-
-    fn from_raw(raw: u16) -> i16 {
-        if raw.to_be() > (1 << 10) {
-            -((raw.to_be() & 0x3ff) as i16)
-        } else {
-            (raw.to_be() & 0x3ff) as i16
-        }
-    }
-
-    fn to_raw(value: i16) -> u16 {
-        value.to_be() as u16
-    }
-}
+/// Load normalized to [-1.0, 1.0] (0.1 % unit, bit 10 is the direction)
+pub type Load = conversion::Load<u16, 1000, 10>;
 
 pub struct TorqueLimit;
 impl Conversion for TorqueLimit {
@@ -125,10 +114,51 @@ impl Conversion for TorqueLimit {
     type UsiType = f64;
 
     fn from_raw(raw: u16) -> f64 {
-        raw.to_be() as f64 * 0.1
+        raw as f64 * 0.1
     }
 
     fn to_raw(value: f64) -> u16 {
-        (value.clamp(0.0, 100.0) as u16 * 10).to_be()
+        value.clamp(0.0, 100.0) as u16 * 10
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV1, MockSerialPort},
+        servo::{
+            control_table::{self, RegisterValue},
+            ServoKind,
+        },
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn big_endian_registers() {
+        let dph = DynamixelProtocolHandler::v1();
+        let mut port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 0, 5));
+        dph.write(&mut port, 1, 3, &[0x05, 0x00]).unwrap();
+
+        // The accessors, the control table and the identification agree on the model number
+        assert_eq!(read_model(&dph, &mut port, 1).unwrap(), 1280);
+        let model = control_table::find(REGISTERS, "model").unwrap();
+        assert_eq!(
+            model.read(&dph, &mut port, 1).unwrap(),
+            RegisterValue::Int(1280)
+        );
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 1).unwrap(),
+            ServoKind::feetech_SCS0009
+        );
+
+        write_goal_position(&dph, &mut port, 1, 0.0).unwrap();
+        assert_eq!(dph.read(&mut port, 1, 42, 2).unwrap(), [0x01, 0xFF]);
+        assert_eq!(read_raw_goal_position(&dph, &mut port, 1).unwrap(), 511);
+        let goal = control_table::find(REGISTERS, "goal_position").unwrap();
+        assert_eq!(
+            goal.read(&dph, &mut port, 1).unwrap(),
+            RegisterValue::Int(511)
+        );
     }
 }
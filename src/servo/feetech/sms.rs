@@ -0,0 +1,21 @@
+//! Feetech SMS series register (protocol v1 over RS485)
+//!
+//! The SMS servos (e.g. SM8512BL) use the [STS3215](super::sts3215) control table and units (4096 steps per turn,
+//! 6.5 mA current unit), Feetech drives both series with the same code. They differ by their RS485 bus (a RS485
+//! adapter is needed, the protocol is the same) and their 12 to 24 V supply (higher default voltage limits).
+
+use crate::servo::{
+    conversion::Temperature,
+    feetech::{
+        sts3215::{sts_control_table, Mode},
+        Baudrate,
+    },
+    multi_turn::impl_multi_turn,
+};
+
+pub use super::sts3215::{Current, CurrentLimit, Load, Offset, TorqueLimit, Velocity, Voltage};
+pub use crate::servo::dynamixel::mx::AnglePosition;
+
+sts_control_table!(SMS);
+
+impl_multi_turn!(Sms, present: 56, goal: 42, i16);
@@ -1,58 +1,112 @@
+//! Feetech STS3215 register (protocol v1)
+//!
+//! 4096 steps per turn, 6.5 mA current unit. Its control table is shared with the other STS servos
+//! ([sts3250](super::sts3250)) and the RS485 SMS series ([sms](super::sms)).
+
 use std::f64::consts::PI;
 
-use crate::generate_servo;
-use crate::servo::conversion::Conversion;
-use crate::servo::dynamixel::mx::AnglePosition;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-generate_servo!(
-    STS3215, v1,
-    reg: (model, r, 3, u16, None),
-    reg: (id, rw, 5, u8, None),
-    reg: (baudrate, rw, 6, u8, None),
-    reg: (return_delay_time, rw, 7, u8, None),
-    reg: (response_status_level, rw, 8, u8, None),
-    reg: (min_angle_limit, rw, 9, i16, AnglePosition),
-    reg: (max_angle_limit, rw, 11, i16, AnglePosition),
-    reg: (max_temperature_limit, rw, 13, u8, None),
-    reg: (max_voltage_limit, rw, 14, u8, None),
-    reg: (min_voltage_limit, rw, 15, u8, None),
-    reg: (max_torque_limit, rw, 16, u16, None),
-    reg: (phase, rw, 18, u8, None),
-    reg: (unloading_condition, rw, 19, u8, None),
-    reg: (led_alarm_condition, rw, 20, u8, None),
-    reg: (p_coefficient, rw, 21, u8, None),
-    reg: (d_coefficient, rw, 22, u8, None),
-    reg: (i_coefficient, rw, 23, u8, None),
-    reg: (minimum_startup_force, rw, 24, u16, None),
-    reg: (cw_dead_zone, rw, 26, u8, None),
-    reg: (ccw_dead_zone, rw, 27, u8, None),
-    reg: (protection_current, rw, 28, u16, None),
-    reg: (angular_resolution, rw, 30, u8, None),
-    reg: (offset, rw, 31, u16, Offset),
-    reg: (mode, rw, 33, u8, None),
-    reg: (protective_torque, rw, 34, u8, None),
-    reg: (protection_time, rw, 35, u8, None),
-    reg: (overload_torque, rw, 36, u8, None),
-    reg: (speed_closed_loop_p_coefficient, rw, 37, u8, None),
-    reg: (over_current_protection_time, rw, 38, u8, None),
-    reg: (velocity_closed_loop_i_coefficient, rw, 39, u8, None),
-    reg: (torque_enable, rw, 40, u8, bool),
-    reg: (acceleration, rw, 41, u8, None),
-    reg: (goal_position, rw, 42, i16, AnglePosition),
-    reg: (goal_time, rw, 44, u16, None),
-    reg: (goal_speed, rw, 46, u16, Velocity),
-    reg: (torque_limit, rw, 48, u16, None),
-    reg: (lock, rw, 55, u8, bool),
-    reg: (present_position, r, 56, i16, AnglePosition),
-    reg: (present_speed, r, 58, u16, Velocity),
-    reg: (present_load, r, 60, u16, None),
-    reg: (present_voltage, r, 62, u8, None),
-    reg: (present_temperature, r, 63, u8, None),
-    reg: (status, r, 65, u8, None),
-    reg: (moving, r, 66, u8, bool),
-    reg: (present_current, r, 69, u16, None),
-    reg: (maximum_acceleration, rw, 85, u16, None),
-);
+use crate::servo::conversion::{self, enum_conversion, Conversion, Temperature};
+use crate::servo::dynamixel::mx::AnglePosition;
+use crate::servo::feetech::Baudrate;
+use crate::servo::multi_turn::impl_multi_turn;
+
+/// Control table of the STS and SMS series.
+///
+/// The model conversions (`AnglePosition`, `Velocity`, `Load`, `TorqueLimit`, `Current`, `CurrentLimit`, `Voltage` and
+/// `Offset`) and the configuration types (`Baudrate`, `Mode`) are taken from the calling module.
+macro_rules! sts_control_table {
+    ($servo_name:ident) => {
+        $crate::generate_servo!(
+            $servo_name, v1,
+            reg: (firmware_major_version, r, 0, u8, None),
+            reg: (firmware_minor_version, r, 1, u8, None),
+            reg: (model, r, 3, u16, None),
+            reg: (id, rw, 5, u8, None),
+            reg: (baudrate, rw, 6, u8, Baudrate),
+            reg: (return_delay_time, rw, 7, u8, None),
+            reg: (response_status_level, rw, 8, u8, None),
+            reg: (min_angle_limit, rw, 9, i16, AnglePosition, unit),
+            reg: (max_angle_limit, rw, 11, i16, AnglePosition, unit),
+            reg: (max_temperature_limit, rw, 13, u8, Temperature, unit),
+            reg: (max_voltage_limit, rw, 14, u8, Voltage, unit),
+            reg: (min_voltage_limit, rw, 15, u8, Voltage, unit),
+            reg: (max_torque_limit, rw, 16, u16, TorqueLimit),
+            reg: (phase, rw, 18, u8, None),
+            reg: (unloading_condition, rw, 19, u8, None),
+            reg: (led_alarm_condition, rw, 20, u8, None),
+            reg: (p_coefficient, rw, 21, u8, None),
+            reg: (d_coefficient, rw, 22, u8, None),
+            reg: (i_coefficient, rw, 23, u8, None),
+            reg: (minimum_startup_force, rw, 24, u16, None),
+            reg: (cw_dead_zone, rw, 26, u8, None),
+            reg: (ccw_dead_zone, rw, 27, u8, None),
+            reg: (protection_current, rw, 28, u16, CurrentLimit, unit),
+            reg: (angular_resolution, rw, 30, u8, None),
+            reg: (offset, rw, 31, u16, Offset, unit),
+            reg: (mode, rw, 33, u8, Mode),
+            reg: (protective_torque, rw, 34, u8, None),
+            reg: (protection_time, rw, 35, u8, None),
+            reg: (overload_torque, rw, 36, u8, None),
+            reg: (speed_closed_loop_p_coefficient, rw, 37, u8, None),
+            reg: (over_current_protection_time, rw, 38, u8, None),
+            reg: (velocity_closed_loop_i_coefficient, rw, 39, u8, None),
+            reg: (torque_enable, rw, 40, u8, bool),
+            reg: (acceleration, rw, 41, u8, None),
+            reg: (goal_position, rw, 42, i16, AnglePosition, unit),
+            reg: (goal_time, rw, 44, u16, None),
+            reg: (goal_speed, rw, 46, u16, Velocity, unit),
+            reg: (torque_limit, rw, 48, u16, TorqueLimit),
+            reg: (lock, rw, 55, u8, bool),
+            reg: (present_position, r, 56, i16, AnglePosition, unit),
+            reg: (present_speed, r, 58, u16, Velocity, unit),
+            reg: (present_load, r, 60, u16, Load),
+            reg: (present_voltage, r, 62, u8, Voltage, unit),
+            reg: (present_temperature, r, 63, u8, Temperature, unit),
+            reg: (status, r, 65, u8, None),
+            reg: (moving, r, 66, u8, bool),
+            reg: (present_current, r, 69, u16, Current, unit),
+            reg: (maximum_acceleration, rw, 85, u16, None),
+        );
+    };
+}
+pub(crate) use sts_control_table;
+
+sts_control_table!(STS3215);
+
+impl_multi_turn!(Sts3215, present: 56, goal: 42, i16);
+
+/// Load normalized to [-1.0, 1.0] (0.1 % unit, bit 10 is the direction)
+pub type Load = conversion::Load<u16, 1000, 10>;
+/// Torque limit normalized to [0.0, 1.0] (0.1 % unit)
+pub type TorqueLimit = conversion::Load<u16, 1000>;
+/// Current in A (6.5 mA unit, bit 15 is the direction)
+pub type Current = conversion::Current<u16, 6_500, 15>;
+/// Current limit in A (6.5 mA unit)
+pub type CurrentLimit = conversion::Current<u16, 6_500>;
+/// Voltage in V (0.1 V unit)
+pub type Voltage = conversion::Voltage<u8>;
+
+/// Control mode (*mode* register)
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass_enum,
+    pyo3::pyclass(eq, eq_int, name = "Sts3215Mode")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum Mode {
+    /// Position servo
+    Position = 0,
+    /// Constant speed (wheel) mode
+    Velocity = 1,
+    /// Open-loop (PWM) mode
+    Pwm = 2,
+    /// Stepper mode
+    Step = 3,
+}
+enum_conversion!(Mode, u8);
 
 pub struct Velocity;
 
@@ -77,6 +131,11 @@ impl Conversion for Velocity {
     }
 }
 
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for Velocity {
+    type Unit = crate::servo::units::RadiansPerSecond;
+}
+
 pub struct Offset;
 const MAX_MAGNITUDE: u16 = 2047;
 
@@ -111,6 +170,11 @@ impl Conversion for Offset {
     }
 }
 
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for Offset {
+    type Unit = crate::servo::units::Radians;
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -0,0 +1,42 @@
+//! Feetech STS3250 register (protocol v1)
+//!
+//! Same control table and units as the [STS3215](super::sts3215) (4096 steps per turn, 6.5 mA current unit).
+//! It differs by its 12 V supply (`min_voltage_limit`/`max_voltage_limit` default to 9.0/14.0 V instead of 4.0/8.0 V)
+//! and its 50 kg.cm stall torque, so the normalized `present_load` and `torque_limit` are relative to a larger torque.
+
+use crate::servo::{
+    conversion::Temperature,
+    feetech::{
+        sts3215::{sts_control_table, Mode},
+        Baudrate,
+    },
+    multi_turn::impl_multi_turn,
+};
+
+pub use super::sts3215::{Current, CurrentLimit, Load, Offset, TorqueLimit, Velocity, Voltage};
+pub use crate::servo::dynamixel::mx::AnglePosition;
+
+sts_control_table!(STS3250);
+
+impl_multi_turn!(Sts3250, present: 56, goal: 42, i16);
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV1, MockSerialPort},
+        servo::ServoKind,
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn identify() {
+        let dph = DynamixelProtocolHandler::v1();
+        let mut port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 0, 5));
+        dph.write(&mut port, 1, 3, &2315_u16.to_le_bytes()).unwrap();
+
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 1).unwrap(),
+            ServoKind::feetech_STS3250
+        );
+    }
+}
@@ -0,0 +1,74 @@
+//! Serialization formats for the configuration files (snapshots, bus configurations).
+
+use std::path::Path;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::Result;
+
+/// Supported file formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl FileFormat {
+    /// Guesses the format from the file extension
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self> {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("json") => Ok(FileFormat::Json),
+            Some("yaml") | Some("yml") => Ok(FileFormat::Yaml),
+            Some("toml") => Ok(FileFormat::Toml),
+            _ => Err(format!("Unknown file format for {:?}", path.as_ref()).into()),
+        }
+    }
+
+    pub fn serialize<T: Serialize>(&self, value: &T) -> Result<String> {
+        Ok(match self {
+            FileFormat::Json => serde_json::to_string_pretty(value)?,
+            FileFormat::Yaml => serde_yaml::to_string(value)?,
+            FileFormat::Toml => toml::to_string(value)?,
+        })
+    }
+
+    pub fn deserialize<T: DeserializeOwned>(&self, s: &str) -> Result<T> {
+        Ok(match self {
+            FileFormat::Json => serde_json::from_str(s)?,
+            FileFormat::Yaml => serde_yaml::from_str(s)?,
+            FileFormat::Toml => toml::from_str(s)?,
+        })
+    }
+}
+
+/// Saves a value to a file (format deduced from the file extension)
+pub fn save<T: Serialize>(value: &T, path: impl AsRef<Path>) -> Result<()> {
+    let format = FileFormat::from_path(&path)?;
+    std::fs::write(path, format.serialize(value)?)?;
+    Ok(())
+}
+
+/// Loads a value from a file (format deduced from the file extension)
+pub fn load<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let format = FileFormat::from_path(&path)?;
+    format.deserialize(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            FileFormat::from_path("servo.yml").unwrap(),
+            FileFormat::Yaml
+        );
+        assert_eq!(
+            FileFormat::from_path("a/b/servo.toml").unwrap(),
+            FileFormat::Toml
+        );
+        assert!(FileFormat::from_path("servo.txt").is_err());
+    }
+}
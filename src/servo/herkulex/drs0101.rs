@@ -0,0 +1,245 @@
+//! Herkulex DRS-0101 register (Herkulex protocol)
+//!
+//! The EEP registers are declared at [HERKULEX_EEP_BANK](crate::HERKULEX_EEP_BANK) + their address, the RAM registers at their own address.
+//! The RAM holds a copy of the configuration loaded from the EEP at boot (EEP address = RAM address + 6), the EEP
+//! copies are prefixed with `eep_`. It should work for
+//! * DRS-0101
+//! * DRS-0201 (same table, higher torque)
+//!
+//! Use [Drs0101Controller::i_jog] and [Drs0101Controller::s_jog] to move several servos at once.
+
+use crate::{
+    servo::conversion::{self, flags_conversion, Conversion},
+    HerkulexJog,
+};
+
+crate::generate_servo!(
+    DRS0101, herkulex,
+    reg: (model_number, r, 0x100, u16, None),
+    reg: (version, r, 0x102, u16, None),
+    reg: (baud_rate, rw, 0x104, u8, None),
+    reg: (eep_id, rw, 0x106, u8, None),
+    reg: (eep_ack_policy, rw, 0x107, u8, None),
+    reg: (eep_alarm_led_policy, rw, 0x108, u8, StatusError),
+    reg: (eep_torque_policy, rw, 0x109, u8, StatusError),
+    reg: (eep_max_temperature, rw, 0x10B, u8, None),
+    reg: (eep_min_voltage, rw, 0x10C, u8, Voltage, unit),
+    reg: (eep_max_voltage, rw, 0x10D, u8, Voltage, unit),
+    reg: (eep_acceleration_ratio, rw, 0x10E, u8, None),
+    reg: (eep_max_acceleration_time, rw, 0x10F, u8, None),
+    reg: (eep_dead_zone, rw, 0x110, u8, None),
+    reg: (eep_min_position, rw, 0x11A, u16, AnglePosition, unit),
+    reg: (eep_max_position, rw, 0x11C, u16, AnglePosition, unit),
+    reg: (eep_position_kp, rw, 0x11E, u16, None),
+    reg: (eep_position_kd, rw, 0x120, u16, None),
+    reg: (eep_position_ki, rw, 0x122, u16, None),
+    reg: (eep_calibration_difference, rw, 0x135, i8, None),
+
+    reg: (id, rw, 0, u8, None),
+    reg: (ack_policy, rw, 1, u8, None),
+    reg: (alarm_led_policy, rw, 2, u8, StatusError),
+    reg: (torque_policy, rw, 3, u8, StatusError),
+    reg: (max_temperature, rw, 5, u8, None),
+    reg: (min_voltage, rw, 6, u8, Voltage, unit),
+    reg: (max_voltage, rw, 7, u8, Voltage, unit),
+    reg: (acceleration_ratio, rw, 8, u8, None),
+    reg: (max_acceleration_time, rw, 9, u8, None),
+    reg: (dead_zone, rw, 10, u8, None),
+    reg: (saturator_offset, rw, 11, u8, None),
+    reg: (saturator_slope, rw, 12, u16, None),
+    reg: (pwm_offset, rw, 14, i8, None),
+    reg: (min_pwm, rw, 15, u8, None),
+    reg: (max_pwm, rw, 16, u16, None),
+    reg: (overload_pwm_threshold, rw, 18, u16, None),
+    reg: (min_position, rw, 20, u16, AnglePosition, unit),
+    reg: (max_position, rw, 22, u16, AnglePosition, unit),
+    reg: (position_kp, rw, 24, u16, None),
+    reg: (position_kd, rw, 26, u16, None),
+    reg: (position_ki, rw, 28, u16, None),
+    reg: (position_feedforward_1st_gain, rw, 30, u16, None),
+    reg: (position_feedforward_2nd_gain, rw, 32, u16, None),
+    reg: (led_blink_period, rw, 38, u8, None),
+    reg: (adc_fault_check_period, rw, 39, u8, None),
+    reg: (packet_garbage_check_period, rw, 40, u8, None),
+    reg: (stop_detection_period, rw, 41, u8, None),
+    reg: (overload_detection_period, rw, 42, u8, None),
+    reg: (stop_threshold, rw, 43, u8, None),
+    reg: (inposition_margin, rw, 44, u8, None),
+    reg: (calibration_difference, rw, 47, i8, None),
+    reg: (status_error, rw, 48, u8, StatusError),
+    reg: (status_detail, rw, 49, u8, None),
+    reg: (torque_control, rw, 52, u8, None),
+    reg: (led_control, rw, 53, u8, None),
+    reg: (present_voltage, r, 54, u8, Voltage, unit),
+    reg: (present_temperature, r, 55, u8, None),
+    reg: (current_control_mode, r, 56, u8, None),
+    reg: (tick, r, 57, u8, None),
+    reg: (present_position, r, 58, u16, AnglePosition, unit),
+    reg: (absolute_position, r, 60, u16, None),
+    reg: (differential_position, r, 62, i16, None),
+    reg: (pwm, r, 64, i16, None),
+    reg: (absolute_goal_position, r, 68, u16, AnglePosition, unit),
+    reg: (absolute_desired_trajectory_position, r, 70, u16, None),
+    reg: (desired_velocity, r, 72, i16, None),
+);
+
+/// *torque_control* value freeing the output
+pub const TORQUE_FREE: u8 = 0x00;
+/// *torque_control* value braking the output
+pub const TORQUE_BREAK: u8 = 0x40;
+/// *torque_control* value enabling the torque
+pub const TORQUE_ON: u8 = 0x60;
+
+/// Voltage in V (74 mV unit)
+pub type Voltage = conversion::Voltage<u8, 74>;
+
+/// Position in radians, 0 being the center position (0.325° unit, centered on 512)
+pub struct AnglePosition;
+
+impl Conversion for AnglePosition {
+    type RegisterType = u16;
+    type UsiType = f64;
+
+    fn from_raw(raw: u16) -> f64 {
+        ((raw as f64 - 512.0) * 0.325).to_radians()
+    }
+
+    fn to_raw(value: f64) -> u16 {
+        (value.to_degrees() / 0.325 + 512.0)
+            .round()
+            .clamp(0.0, 1023.0) as u16
+    }
+}
+
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
+
+bitflags::bitflags! {
+    /// Status errors (*status_error* register), also used to select the errors lighting the LED (*alarm_led_policy*)
+    /// or freeing the torque (*torque_policy*)
+    #[cfg_attr(feature = "python", pyo3_stub_gen::derive::gen_stub_pyclass, pyo3::pyclass(name = "HerkulexStatusError"))]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct StatusError: u8 {
+        const INPUT_VOLTAGE = 1 << 0;
+        const POT_LIMIT = 1 << 1;
+        const TEMPERATURE = 1 << 2;
+        const INVALID_PACKET = 1 << 3;
+        const OVERLOAD = 1 << 4;
+        const DRIVER_FAULT = 1 << 5;
+        const EEP_DISTORTED = 1 << 6;
+    }
+}
+flags_conversion!(StatusError, u8);
+
+impl<P: crate::bus::Transport> Drs0101Controller<P> {
+    /// Moves each servo to its position (rad) in its own play time (11.2 ms units), with the I_JOG command
+    pub fn i_jog(&mut self, ids: &[u8], positions: &[f64], playtimes: &[u8]) -> crate::Result<()> {
+        let jogs: Vec<HerkulexJog> = ids
+            .iter()
+            .zip(positions)
+            .zip(playtimes)
+            .map(|((&id, &position), &playtime)| HerkulexJog {
+                id,
+                value: AnglePosition::to_raw(position),
+                set: 0,
+                playtime,
+            })
+            .collect();
+
+        self.dph.i_jog(&mut *self.serial_port.lock(), &jogs)
+    }
+
+    /// Moves the servos to their positions (rad) in a common play time (11.2 ms units), with the S_JOG command
+    pub fn s_jog(&mut self, ids: &[u8], positions: &[f64], playtime: u8) -> crate::Result<()> {
+        let jogs: Vec<HerkulexJog> = ids
+            .iter()
+            .zip(positions)
+            .map(|(&id, &position)| HerkulexJog {
+                id,
+                value: AnglePosition::to_raw(position),
+                set: 0,
+                playtime,
+            })
+            .collect();
+
+        self.dph
+            .s_jog(&mut *self.serial_port.lock(), playtime, &jogs)
+    }
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl Drs0101PyController {
+    pub fn i_jog(&self, ids: Vec<u8>, positions: Vec<f64>, playtimes: Vec<u8>) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .i_jog(&ids, &positions, &playtimes)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+
+    pub fn s_jog(&self, ids: Vec<u8>, positions: Vec<f64>, playtime: u8) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .s_jog(&ids, &positions, playtime)
+            .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosHerkulex, MockSerialPort},
+        servo::ServoKind,
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn ram_and_eep() {
+        let dph = DynamixelProtocolHandler::herkulex();
+        // Id 12 and ACK policy 1 in RAM 0-1 look like an AX-12 model number
+        let mut port = MockSerialPort::new(FakeServosHerkulex::default().with_servo(12, 257));
+
+        assert!(dph.ping(&mut port, 12).unwrap());
+        assert_eq!(
+            ServoKind::identify(&dph, &mut port, 12).unwrap(),
+            ServoKind::herkulex_DRS0101
+        );
+
+        write_torque_control(&dph, &mut port, 12, TORQUE_ON).unwrap();
+        assert_eq!(read_torque_control(&dph, &mut port, 12).unwrap(), TORQUE_ON);
+        write_eep_max_voltage(&dph, &mut port, 12, 7.4).unwrap();
+        assert_eq!(read_raw_eep_max_voltage(&dph, &mut port, 12).unwrap(), 100);
+        assert_eq!(read_raw_max_voltage(&dph, &mut port, 12).unwrap(), 0);
+        assert!(read_status_error(&dph, &mut port, 12).unwrap().is_empty());
+    }
+
+    #[test]
+    fn jog() {
+        let port = MockSerialPort::new(
+            FakeServosHerkulex::default()
+                .with_servo(1, 257)
+                .with_servo(2, 258),
+        );
+        let mut c = Drs0101Controller::new()
+            .with_serial_port(Box::new(port))
+            .with_protocol_herkulex();
+
+        c.i_jog(&[1, 2], &[0.0, 32.5_f64.to_radians()], &[50, 100])
+            .unwrap();
+        assert_eq!(c.read_raw_absolute_goal_position(1).unwrap(), [512]);
+        assert_eq!(c.read_raw_absolute_goal_position(2).unwrap(), [612]);
+
+        c.s_jog(&[1, 2], &[-32.5_f64.to_radians(), 0.0], 50)
+            .unwrap();
+        assert_eq!(c.read_raw_absolute_goal_position(1).unwrap(), [412]);
+        assert_eq!(c.read_raw_absolute_goal_position(2).unwrap(), [512]);
+
+        assert!(c.sync_read_present_position(&[1, 2]).is_err());
+    }
+}
@@ -0,0 +1 @@
+pub mod drs0101;
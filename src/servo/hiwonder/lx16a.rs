@@ -0,0 +1,663 @@
+//! Hiwonder/LewanSoul LX-16A bus servo (LX-16A protocol)
+//!
+//! These servos are driven by commands rather than registers, see [DynamixelProtocolHandler::lx16a].
+//! It should work for
+//! * LX-16A, LX-224, LX-15D
+//! * HTS-35H
+//!
+//! The position ranges over 240° in 1000 steps, centered on 500.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::servo::hiwonder::lx16a::Lx16aController;
+//! use std::time::Duration;
+//!
+//! let mut c = Lx16aController::open("/dev/ttyUSB0", 115_200, Duration::from_millis(20)).unwrap();
+//!
+//! c.move_with_time(1, 90.0_f64.to_radians(), Duration::from_secs(1)).unwrap();
+//! println!("{}", c.read_present_position(1).unwrap());
+//! ```
+
+use std::time::Duration;
+
+use crate::{
+    bus::{SharedBus, Transport},
+    servo::conversion::{self, Conversion, Temperature},
+    CommunicationErrorKind, DynamixelProtocolHandler, Result,
+};
+
+/// Id answered by any servo, to find the id of a single servo on the bus
+pub const BROADCAST_ID: u8 = 254;
+
+/// Command codes, used as register addresses by [DynamixelProtocolHandler::lx16a]
+pub mod command {
+    pub const MOVE_TIME_WRITE: u16 = 1;
+    pub const MOVE_TIME_READ: u16 = 2;
+    pub const MOVE_STOP: u16 = 12;
+    pub const ID_WRITE: u16 = 13;
+    pub const ID_READ: u16 = 14;
+    pub const ANGLE_OFFSET_ADJUST: u16 = 17;
+    pub const ANGLE_OFFSET_WRITE: u16 = 18;
+    pub const ANGLE_OFFSET_READ: u16 = 19;
+    pub const TEMP_READ: u16 = 26;
+    pub const VIN_READ: u16 = 27;
+    pub const POS_READ: u16 = 28;
+    pub const SERVO_OR_MOTOR_MODE_WRITE: u16 = 29;
+    pub const SERVO_OR_MOTOR_MODE_READ: u16 = 30;
+    pub const LOAD_OR_UNLOAD_WRITE: u16 = 31;
+    pub const LOAD_OR_UNLOAD_READ: u16 = 32;
+}
+
+/// Angle in rad (240° over 1000 steps, centered on 500)
+pub struct AnglePosition;
+
+impl Conversion for AnglePosition {
+    type RegisterType = i16;
+    type UsiType = f64;
+
+    fn from_raw(raw: i16) -> f64 {
+        (raw as f64 - 500.0) * 240.0_f64.to_radians() / 1000.0
+    }
+
+    fn to_raw(value: f64) -> i16 {
+        (value * 1000.0 / 240.0_f64.to_radians() + 500.0).round() as i16
+    }
+}
+
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for AnglePosition {
+    type Unit = crate::servo::units::Radians;
+}
+
+/// Angle offset in rad (0.24° unit, from -125 to 125)
+pub struct Offset;
+
+impl Conversion for Offset {
+    type RegisterType = i8;
+    type UsiType = f64;
+
+    fn from_raw(raw: i8) -> f64 {
+        (raw as f64 * 0.24).to_radians()
+    }
+
+    fn to_raw(value: f64) -> i8 {
+        (value.to_degrees() / 0.24).round().clamp(-125.0, 125.0) as i8
+    }
+}
+
+#[cfg(feature = "units")]
+impl crate::servo::units::UnitConversion for Offset {
+    type Unit = crate::servo::units::Radians;
+}
+
+/// Voltage in V (1 mV unit)
+pub type Voltage = conversion::Voltage<u16, 1>;
+
+/// Longest move duration
+pub const MAX_MOVE_TIME: Duration = Duration::from_millis(30_000);
+
+/// Moves to `position` (rad) in `time` (at most [MAX_MOVE_TIME])
+pub fn move_with_time(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    position: f64,
+    time: Duration,
+) -> Result<()> {
+    let position = AnglePosition::to_raw(position).clamp(0, 1000) as u16;
+    let time = time.min(MAX_MOVE_TIME).as_millis() as u16;
+
+    let mut data = position.to_le_bytes().to_vec();
+    data.extend(time.to_le_bytes());
+    io.write(serial_port, id, command::MOVE_TIME_WRITE, &data)
+}
+
+/// Reads the last move target (rad) and duration
+pub fn read_move_time(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<(f64, Duration)> {
+    let val = io.read(serial_port, id, command::MOVE_TIME_READ, 4)?;
+    let position = i16::from_le_bytes([val[0], val[1]]);
+    let time = u16::from_le_bytes([val[2], val[3]]);
+
+    Ok((
+        AnglePosition::from_raw(position),
+        Duration::from_millis(time as u64),
+    ))
+}
+
+/// Stops the current move
+pub fn stop(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<()> {
+    io.write(serial_port, id, command::MOVE_STOP, &[])
+}
+
+/// Reads the raw position (1000 steps over 240°, may be negative)
+pub fn read_raw_present_position(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<i16> {
+    let val = io.read(serial_port, id, command::POS_READ, 2)?;
+    Ok(i16::from_le_bytes([val[0], val[1]]))
+}
+
+/// Reads the position in rad
+pub fn read_present_position(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<f64> {
+    read_raw_present_position(io, serial_port, id).map(AnglePosition::from_raw)
+}
+
+/// Reads the input voltage in V
+pub fn read_present_voltage(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<f64> {
+    let val = io.read(serial_port, id, command::VIN_READ, 2)?;
+    Ok(Voltage::from_raw(u16::from_le_bytes([val[0], val[1]])))
+}
+
+/// Reads the temperature in °C
+pub fn read_present_temperature(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<f64> {
+    let val = io.read(serial_port, id, command::TEMP_READ, 1)?;
+    Ok(Temperature::from_raw(val[0]))
+}
+
+/// Reads the servo id, use [BROADCAST_ID] to find the id of a single servo on the bus
+pub fn read_id(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<u8> {
+    match io.read(serial_port, id, command::ID_READ, 1) {
+        Ok(val) => Ok(val[0]),
+        // A broadcast read is answered with the actual id
+        Err(e) if id == BROADCAST_ID => match e.downcast_ref::<CommunicationErrorKind>() {
+            Some(CommunicationErrorKind::IncorrectId(_, actual)) => Ok(*actual),
+            _ => Err(e),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Gives the servo a new id (saved across power cycles)
+pub fn write_id(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    new_id: u8,
+) -> Result<()> {
+    io.write(serial_port, id, command::ID_WRITE, &[new_id])
+}
+
+/// Reads the angle offset in rad
+pub fn read_offset(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<f64> {
+    let val = io.read(serial_port, id, command::ANGLE_OFFSET_READ, 1)?;
+    Ok(Offset::from_raw(val[0] as i8))
+}
+
+/// Sets the angle offset in rad (at most ±30°), lost at power off unless saved with [save_offset]
+pub fn write_offset(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    offset: f64,
+) -> Result<()> {
+    io.write(
+        serial_port,
+        id,
+        command::ANGLE_OFFSET_ADJUST,
+        &[Offset::to_raw(offset) as u8],
+    )
+}
+
+/// Saves the angle offset across power cycles
+pub fn save_offset(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<()> {
+    io.write(serial_port, id, command::ANGLE_OFFSET_WRITE, &[])
+}
+
+/// Switches to motor (continuous rotation) mode, `speed` ranging from -1000 to 1000
+pub fn set_motor_mode(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    speed: i16,
+) -> Result<()> {
+    let mut data = vec![1, 0];
+    data.extend(speed.clamp(-1000, 1000).to_le_bytes());
+    io.write(serial_port, id, command::SERVO_OR_MOTOR_MODE_WRITE, &data)
+}
+
+/// Switches back to servo (position) mode
+pub fn set_servo_mode(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<()> {
+    io.write(
+        serial_port,
+        id,
+        command::SERVO_OR_MOTOR_MODE_WRITE,
+        &[0, 0, 0, 0],
+    )
+}
+
+/// Reads the motor mode speed, `None` in servo mode
+pub fn read_motor_mode(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<Option<i16>> {
+    let val = io.read(serial_port, id, command::SERVO_OR_MOTOR_MODE_READ, 4)?;
+    Ok(match val[0] {
+        0 => None,
+        _ => Some(i16::from_le_bytes([val[2], val[3]])),
+    })
+}
+
+/// Enables (load) or disables (unload) the torque
+pub fn write_torque_enable(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    enable: bool,
+) -> Result<()> {
+    io.write(
+        serial_port,
+        id,
+        command::LOAD_OR_UNLOAD_WRITE,
+        &[bool::to_raw(enable)],
+    )
+}
+
+/// Reads whether the torque is enabled
+pub fn read_torque_enable(
+    io: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<bool> {
+    let val = io.read(serial_port, id, command::LOAD_OR_UNLOAD_READ, 1)?;
+    Ok(bool::from_raw(val[0]))
+}
+
+/// High-level LX-16A controller, owning the serial port
+///
+/// `P` is `()` until [Lx16aController::with_serial_port] is called, the controller has no communication methods before.
+pub struct Lx16aController<P = Box<dyn serialport::SerialPort>> {
+    dph: DynamixelProtocolHandler,
+    serial_port: P,
+}
+
+impl Default for Lx16aController<()> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+macro_rules! forward {
+    ($(#[$doc:meta])* $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty) => {
+        $(#[$doc])*
+        pub fn $name(&mut self, id: u8 $(, $arg: $ty)*) -> Result<$ret> {
+            $name(&self.dph, &mut *self.serial_port.lock(), id $(, $arg)*)
+        }
+    };
+}
+
+impl Lx16aController<()> {
+    pub fn new() -> Self {
+        Self {
+            dph: DynamixelProtocolHandler::lx16a(),
+            serial_port: (),
+        }
+    }
+}
+
+impl<P> Lx16aController<P> {
+    pub fn with_serial_port(self, serial_port: Box<dyn serialport::SerialPort>) -> Lx16aController {
+        Lx16aController {
+            dph: self.dph,
+            serial_port,
+        }
+    }
+
+    /// Uses a serial port shared with other controllers
+    pub fn with_bus(self, bus: SharedBus) -> Lx16aController<SharedBus> {
+        Lx16aController {
+            dph: self.dph,
+            serial_port: bus,
+        }
+    }
+
+    /// Uses a configured LX-16A protocol handler (e.g. with echo cancellation)
+    pub fn with_protocol_handler(self, dph: DynamixelProtocolHandler) -> Lx16aController<P> {
+        Lx16aController {
+            dph,
+            serial_port: self.serial_port,
+        }
+    }
+}
+
+impl Lx16aController {
+    /// Opens the serial port at `path`
+    pub fn open(path: &str, baudrate: u32, timeout: Duration) -> Result<Self> {
+        let serial_port = serialport::new(path, baudrate).timeout(timeout).open()?;
+
+        Ok(Lx16aController::new().with_serial_port(serial_port))
+    }
+}
+
+impl<P: Transport> Lx16aController<P> {
+    /// Whether the servo answers (to an id read)
+    pub fn ping(&mut self, id: u8) -> Result<bool> {
+        self.dph.ping(&mut *self.serial_port.lock(), id)
+    }
+
+    forward!(
+        /// Moves to `position` (rad) in `time`
+        move_with_time(position: f64, time: Duration) -> ()
+    );
+    forward!(
+        /// Reads the last move target (rad) and duration
+        read_move_time() -> (f64, Duration)
+    );
+    forward!(
+        /// Stops the current move
+        stop() -> ()
+    );
+    forward!(
+        /// Reads the position in rad
+        read_present_position() -> f64
+    );
+    forward!(
+        /// Reads the raw position (1000 steps over 240°)
+        read_raw_present_position() -> i16
+    );
+    forward!(
+        /// Reads the input voltage in V
+        read_present_voltage() -> f64
+    );
+    forward!(
+        /// Reads the temperature in °C
+        read_present_temperature() -> f64
+    );
+    forward!(
+        /// Reads the servo id ([BROADCAST_ID] to find the id of a single servo)
+        read_id() -> u8
+    );
+    forward!(
+        /// Gives the servo a new id
+        write_id(new_id: u8) -> ()
+    );
+    forward!(
+        /// Reads the angle offset in rad
+        read_offset() -> f64
+    );
+    forward!(
+        /// Sets the angle offset in rad, see [save_offset]
+        write_offset(offset: f64) -> ()
+    );
+    forward!(
+        /// Saves the angle offset across power cycles
+        save_offset() -> ()
+    );
+    forward!(
+        /// Switches to motor mode, `speed` ranging from -1000 to 1000
+        set_motor_mode(speed: i16) -> ()
+    );
+    forward!(
+        /// Switches back to servo mode
+        set_servo_mode() -> ()
+    );
+    forward!(
+        /// Reads the motor mode speed, `None` in servo mode
+        read_motor_mode() -> Option<i16>
+    );
+    forward!(
+        /// Enables or disables the torque
+        write_torque_enable(enable: bool) -> ()
+    );
+    forward!(
+        /// Reads whether the torque is enabled
+        read_torque_enable() -> bool
+    );
+}
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+#[cfg(feature = "python")]
+use pyo3_stub_gen::derive::*;
+
+#[cfg(feature = "python")]
+#[gen_stub_pyclass]
+#[pyo3::pyclass(frozen)]
+pub struct Lx16aPyController(std::sync::Mutex<Lx16aController>);
+
+#[cfg(feature = "python")]
+fn to_py_err(e: Box<dyn std::error::Error>) -> PyErr {
+    pyo3::exceptions::PyRuntimeError::new_err(e.to_string())
+}
+
+#[cfg(feature = "python")]
+#[gen_stub_pymethods]
+#[pymethods]
+impl Lx16aPyController {
+    #[new]
+    #[pyo3(signature = (serial_port, baudrate, timeout, echo_cancellation = false))]
+    pub fn new(
+        serial_port: &str,
+        baudrate: u32,
+        timeout: f32,
+        echo_cancellation: bool,
+    ) -> PyResult<Self> {
+        let mut c = Lx16aController::open(serial_port, baudrate, Duration::from_secs_f32(timeout))
+            .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+        if echo_cancellation {
+            c = c.with_protocol_handler(DynamixelProtocolHandler::lx16a().with_echo_cancellation());
+        }
+
+        Ok(Self(std::sync::Mutex::new(c)))
+    }
+
+    pub fn ping(&self, id: u8) -> PyResult<bool> {
+        self.0.lock().unwrap().ping(id).map_err(to_py_err)
+    }
+
+    /// Moves to `position` (rad) in `time` (s)
+    pub fn move_with_time(&self, id: u8, position: f64, time: f64) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .move_with_time(id, position, Duration::from_secs_f64(time))
+            .map_err(to_py_err)
+    }
+
+    /// Reads the last move target (rad) and duration (s)
+    pub fn read_move_time(&self, id: u8) -> PyResult<(f64, f64)> {
+        let (position, time) = self
+            .0
+            .lock()
+            .unwrap()
+            .read_move_time(id)
+            .map_err(to_py_err)?;
+        Ok((position, time.as_secs_f64()))
+    }
+
+    pub fn stop(&self, id: u8) -> PyResult<()> {
+        self.0.lock().unwrap().stop(id).map_err(to_py_err)
+    }
+
+    pub fn read_present_position(&self, id: u8) -> PyResult<f64> {
+        self.0
+            .lock()
+            .unwrap()
+            .read_present_position(id)
+            .map_err(to_py_err)
+    }
+
+    pub fn read_raw_present_position(&self, id: u8) -> PyResult<i16> {
+        self.0
+            .lock()
+            .unwrap()
+            .read_raw_present_position(id)
+            .map_err(to_py_err)
+    }
+
+    pub fn read_present_voltage(&self, id: u8) -> PyResult<f64> {
+        self.0
+            .lock()
+            .unwrap()
+            .read_present_voltage(id)
+            .map_err(to_py_err)
+    }
+
+    pub fn read_present_temperature(&self, id: u8) -> PyResult<f64> {
+        self.0
+            .lock()
+            .unwrap()
+            .read_present_temperature(id)
+            .map_err(to_py_err)
+    }
+
+    pub fn read_id(&self, id: u8) -> PyResult<u8> {
+        self.0.lock().unwrap().read_id(id).map_err(to_py_err)
+    }
+
+    pub fn write_id(&self, id: u8, new_id: u8) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .write_id(id, new_id)
+            .map_err(to_py_err)
+    }
+
+    pub fn read_offset(&self, id: u8) -> PyResult<f64> {
+        self.0.lock().unwrap().read_offset(id).map_err(to_py_err)
+    }
+
+    pub fn write_offset(&self, id: u8, offset: f64) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .write_offset(id, offset)
+            .map_err(to_py_err)
+    }
+
+    pub fn save_offset(&self, id: u8) -> PyResult<()> {
+        self.0.lock().unwrap().save_offset(id).map_err(to_py_err)
+    }
+
+    pub fn set_motor_mode(&self, id: u8, speed: i16) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .set_motor_mode(id, speed)
+            .map_err(to_py_err)
+    }
+
+    pub fn set_servo_mode(&self, id: u8) -> PyResult<()> {
+        self.0.lock().unwrap().set_servo_mode(id).map_err(to_py_err)
+    }
+
+    pub fn read_motor_mode(&self, id: u8) -> PyResult<Option<i16>> {
+        self.0
+            .lock()
+            .unwrap()
+            .read_motor_mode(id)
+            .map_err(to_py_err)
+    }
+
+    pub fn write_torque_enable(&self, id: u8, enable: bool) -> PyResult<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .write_torque_enable(id, enable)
+            .map_err(to_py_err)
+    }
+
+    pub fn read_torque_enable(&self, id: u8) -> PyResult<bool> {
+        self.0
+            .lock()
+            .unwrap()
+            .read_torque_enable(id)
+            .map_err(to_py_err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamixel_protocol::mock::{FakeServosLx16a, MockSerialPort};
+
+    fn controller() -> Lx16aController {
+        let port = MockSerialPort::new(FakeServosLx16a::default().with_servo(3));
+        Lx16aController::new().with_serial_port(Box::new(port))
+    }
+
+    #[test]
+    fn move_and_read() {
+        let mut c = controller();
+        assert!(c.ping(3).unwrap());
+        assert!(!c.ping(4).unwrap());
+
+        c.move_with_time(3, 60.0_f64.to_radians(), Duration::from_millis(500))
+            .unwrap();
+        assert_eq!(c.read_raw_present_position(3).unwrap(), 750);
+        assert!((c.read_present_position(3).unwrap() - 60.0_f64.to_radians()).abs() < 1e-9);
+        let (position, time) = c.read_move_time(3).unwrap();
+        assert!((position - 60.0_f64.to_radians()).abs() < 1e-9);
+        assert_eq!(time, Duration::from_millis(500));
+
+        assert_eq!(c.read_present_voltage(3).unwrap(), 7.4);
+        assert_eq!(c.read_present_temperature(3).unwrap(), 42.0);
+    }
+
+    #[test]
+    fn configuration() {
+        let mut c = controller();
+        assert_eq!(c.read_id(BROADCAST_ID).unwrap(), 3);
+        c.write_id(3, 5).unwrap();
+        assert_eq!(c.read_id(BROADCAST_ID).unwrap(), 5);
+
+        c.write_offset(5, (-2.4_f64).to_radians()).unwrap();
+        assert!((c.read_offset(5).unwrap() + 2.4_f64.to_radians()).abs() < 1e-9);
+
+        assert_eq!(c.read_motor_mode(5).unwrap(), None);
+        c.set_motor_mode(5, -300).unwrap();
+        assert_eq!(c.read_motor_mode(5).unwrap(), Some(-300));
+        c.set_servo_mode(5).unwrap();
+        assert_eq!(c.read_motor_mode(5).unwrap(), None);
+
+        c.write_torque_enable(5, true).unwrap();
+        assert!(c.read_torque_enable(5).unwrap());
+    }
+
+    #[test]
+    fn unsupported() {
+        let dph = DynamixelProtocolHandler::lx16a();
+        let mut port = MockSerialPort::new(FakeServosLx16a::default().with_servo(1));
+        assert!(dph.reboot(&mut port, 1).is_err());
+        assert!(dph.sync_read(&mut port, &[1], 28, 2).is_err());
+        assert!(dph.write(&mut port, 1, 300, &[0]).is_err());
+    }
+}
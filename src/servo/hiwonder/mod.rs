@@ -0,0 +1 @@
+pub mod lx16a;
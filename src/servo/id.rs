@@ -0,0 +1,231 @@
+//! Servo id reassignment and duplicate id detection.
+//!
+//! New servos usually all ship with the same id: they have to be connected one at a time and given a unique id.
+//! Two servos sharing an id answer at the same time, which corrupts their replies (see [PingStatus]).
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{servo::id, DynamixelProtocolHandler};
+//! use std::time::Duration;
+//!
+//! let mut serial_port = serialport::new("/dev/ttyUSB0", 1_000_000)
+//!     .timeout(Duration::from_millis(10))
+//!     .open()
+//!     .unwrap();
+//! let dph = DynamixelProtocolHandler::v1();
+//!
+//! id::reassign_id(&dph, serial_port.as_mut(), 1, 12).unwrap();
+//! ```
+
+use std::fmt;
+
+use crate::{
+    servo::{
+        control_table::{self, ControlTableError},
+        ServoKind,
+    },
+    CommunicationErrorKind, DynamixelProtocolHandler, PingStatus, Result,
+};
+
+/// Id reassignment errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdError {
+    /// The id can not be given to a servo
+    InvalidId(u8),
+    /// No servo answered with this id
+    NotFound(u8),
+    /// Several servos answered with this id
+    Duplicate(u8),
+    /// Another servo already uses this id
+    AlreadyUsed(u8),
+    /// The servo did not answer with its new id after the change
+    VerificationFailed(u8),
+}
+
+impl fmt::Display for IdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdError::InvalidId(id) => write!(f, "Invalid servo id {id}"),
+            IdError::NotFound(id) => write!(f, "No servo found with id {id}"),
+            IdError::Duplicate(id) => write!(f, "Several servos share the id {id}"),
+            IdError::AlreadyUsed(id) => write!(f, "Id {id} is already used by another servo"),
+            IdError::VerificationFailed(id) => {
+                write!(f, "Servo did not answer with its new id {id}")
+            }
+        }
+    }
+}
+impl std::error::Error for IdError {}
+
+/// Lists the ids in `ids` shared by several servos
+pub fn find_duplicates(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    ids: impl IntoIterator<Item = u8>,
+) -> Result<Vec<u8>> {
+    let mut duplicates = Vec::new();
+    for id in ids {
+        if dph.ping_status(serial_port, id)? == PingStatus::Collision {
+            duplicates.push(id);
+        }
+    }
+    Ok(duplicates)
+}
+
+/// Gives the id `new` to the servo `old`.
+///
+/// Refuses to do so if `new` is above the [DynamixelProtocolHandler::max_id] of the protocol or already used, or if several servos answer to `old` (they would all be renamed).
+/// The torque is disabled (and Feetech EEPROM unlocked) while writing, then the servo is pinged with its new id.
+pub fn reassign_id(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    old: u8,
+    new: u8,
+) -> Result<()> {
+    if new > dph.max_id() || new == old {
+        return Err(Box::new(IdError::InvalidId(new)));
+    }
+
+    match dph.ping_status(serial_port, old)? {
+        PingStatus::Absent => return Err(Box::new(IdError::NotFound(old))),
+        PingStatus::Collision => return Err(Box::new(IdError::Duplicate(old))),
+        PingStatus::Present => {}
+    }
+    if dph.ping_status(serial_port, new)? != PingStatus::Absent {
+        return Err(Box::new(IdError::AlreadyUsed(new)));
+    }
+
+    let kind = ServoKind::identify(dph, serial_port, old)?;
+    let id_reg = control_table::find(kind.registers(), "id")
+        .ok_or_else(|| ControlTableError::UnknownRegister("id".to_string()))?;
+
+    let previous_torque = control_table::disable_torque(dph, serial_port, old, kind)?;
+    control_table::unlock_eeprom(dph, serial_port, old, kind)?;
+
+    log::debug!("Servo {old}: id -> {new}");
+    match dph.write(serial_port, old, id_reg.addr, &[new]) {
+        // Some servos already answer with their new id
+        Err(e)
+            if matches!(
+                e.downcast_ref::<CommunicationErrorKind>(),
+                Some(
+                    CommunicationErrorKind::ParsingError | CommunicationErrorKind::IncorrectId(..)
+                )
+            ) => {}
+        res => res?,
+    }
+
+    if dph.ping_status(serial_port, new)? != PingStatus::Present
+        || dph.ping_status(serial_port, old)? != PingStatus::Absent
+    {
+        return Err(Box::new(IdError::VerificationFailed(new)));
+    }
+
+    control_table::lock_eeprom(dph, serial_port, new, kind)?;
+    if let (Some(torque), Some(previous)) = (
+        control_table::find(kind.registers(), "torque_enable"),
+        previous_torque,
+    ) {
+        torque.write(dph, serial_port, new, &previous)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamixel_protocol::mock::{FakeServosV1, FakeServosV2, MockSerialPort, Responder};
+
+    // MX-28: model number 29, id at address 3
+    fn bus(ids: &[u8]) -> MockSerialPort {
+        let servos = ids
+            .iter()
+            .fold(FakeServosV1::default(), |s, &id| s.with_servo(id, 29, 3));
+        MockSerialPort::new(servos)
+    }
+
+    #[test]
+    fn detect_duplicates() {
+        let dph = DynamixelProtocolHandler::v1();
+        let mut port = bus(&[1, 2, 2, 3]);
+
+        assert_eq!(dph.ping_status(&mut port, 1).unwrap(), PingStatus::Present);
+        assert_eq!(dph.ping_status(&mut port, 4).unwrap(), PingStatus::Absent);
+        assert_eq!(find_duplicates(&dph, &mut port, 1..5).unwrap(), vec![2]);
+    }
+
+    /// Replies with a corrupted checksum to the first `corrupted` pings, and from id 9 after
+    struct Noisy {
+        corrupted: usize,
+    }
+
+    impl Responder for Noisy {
+        fn respond(&mut self, written: &[u8]) -> Vec<u8> {
+            let mut reply = FakeServosV2::status_packet(written[4], &[0; 3]);
+            if self.corrupted > 0 {
+                self.corrupted -= 1;
+                *reply.last_mut().unwrap() ^= 0xFF;
+            } else {
+                reply = FakeServosV2::status_packet(9, &[0; 3]);
+            }
+            reply
+        }
+    }
+
+    #[test]
+    fn ping_errors_are_not_collisions() {
+        let dph = DynamixelProtocolHandler::v2();
+
+        // Noise on a single reply is retried
+        let mut port = MockSerialPort::new(Noisy { corrupted: 1 });
+        let err = dph.ping_status(&mut port, 1).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CommunicationErrorKind>(),
+            Some(CommunicationErrorKind::IncorrectId(9, 1))
+        ));
+
+        let mut port = MockSerialPort::new(Noisy { corrupted: 2 });
+        assert_eq!(
+            dph.ping_status(&mut port, 1).unwrap(),
+            PingStatus::Collision
+        );
+    }
+
+    #[test]
+    fn reassign() {
+        let dph = DynamixelProtocolHandler::v1();
+        let mut port = bus(&[1, 2, 2]);
+
+        reassign_id(&dph, &mut port, 1, 5).unwrap();
+        assert!(dph.ping(&mut port, 5).unwrap());
+        assert!(!dph.ping(&mut port, 1).unwrap());
+
+        let err = |r: Result<()>| *r.unwrap_err().downcast::<IdError>().unwrap();
+        assert_eq!(
+            err(reassign_id(&dph, &mut port, 5, 2)),
+            IdError::AlreadyUsed(2)
+        );
+        assert_eq!(
+            err(reassign_id(&dph, &mut port, 2, 6)),
+            IdError::Duplicate(2)
+        );
+        assert_eq!(
+            err(reassign_id(&dph, &mut port, 7, 6)),
+            IdError::NotFound(7)
+        );
+        assert_eq!(
+            err(reassign_id(&dph, &mut port, 5, 254)),
+            IdError::InvalidId(254)
+        );
+
+        // Reserved in protocol v2 only
+        reassign_id(&dph, &mut port, 5, 253).unwrap();
+        assert!(dph.ping(&mut port, 253).unwrap());
+        let dph = DynamixelProtocolHandler::v2();
+        assert_eq!(
+            err(reassign_id(&dph, &mut port, 1, 253)),
+            IdError::InvalidId(253)
+        );
+    }
+}
@@ -1,10 +1,33 @@
+pub mod baudrate;
+pub mod bus_config;
+pub mod control_table;
 pub mod conversion;
+pub mod file_format;
+pub mod id;
+pub mod multi_turn;
+pub mod recover;
+pub mod snapshot;
+#[cfg(feature = "units")]
+pub mod units;
 
 pub mod dynamixel;
 pub mod feetech;
+pub mod herkulex;
+pub mod hiwonder;
 pub mod orbita;
 pub(crate) mod servo_macro;
 
+use crate::DynamixelProtocolHandler;
+
+/// Servo manufacturer family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServoFamily {
+    Dynamixel,
+    Feetech,
+    Herkulex,
+    Orbita,
+}
+
 crate::register_servo!(
     servo: (dynamixel, AX,
         (AX12, 12), // All AX12, except the W are equivalent.
@@ -25,14 +48,66 @@ crate::register_servo!(
     ),
     servo: (dynamixel, XL430,
         (XL430W250, 1060),
-        (XL430W2502, 1090)
+        (XL430W2502, 1090), // 2XL430-W250 (dual-axis)
+        (XC430W150, 1070),
+        (XC430W240, 1080),
+        (XC430W2502, 1160) // 2XC430-W250 (dual-axis)
+    ),
+    servo: (dynamixel, XC330,
+        (XC330T181, 1210),
+        (XC330T288, 1220),
+        (XC330M181, 1230),
+        (XC330M288, 1240)
+    ),
+    servo: (dynamixel, XM,
+        (XM430W210, 1030),
+        (XM430W350, 1020),
+        (XM540W150, 1130),
+        (XM540W270, 1120),
+        (XH540W150, 1110),
+        (XH540W270, 1100),
+        (XH540V150, 1150),
+        (XH540V270, 1140),
+        (XW540T260, 1170),
+        (XW540T140, 1180),
+        (XW430T333, 1270)
+    ),
+    servo: (dynamixel, XH430,
+        (XH430W210, 1010),
+        (XH430W350, 1000),
+        (XH430V210, 1050),
+        (XH430V350, 1040)
+    ),
+    servo: (dynamixel, PH54,
+        (PH54100S500R, 2010),
+        (PH54200S500R, 2020)
+    ),
+    servo: (dynamixel, PH42,
+        (PH42020S300R, 2000)
+    ),
+    servo: (dynamixel, PM54,
+        (PM54040S250R, 2110),
+        (PM54060S250R, 2120)
+    ),
+    servo: (dynamixel, PM42,
+        (PM42010S260R, 2100)
     ),
     servo: (feetech, STS3215,
         (STS3215, 2307)
     ),
+    servo: (feetech, STS3250,
+        (STS3250, 2315)
+    ),
+    servo: (feetech, SMS,
+        (SM8512BL, 2092)
+    ),
     servo: (feetech, SCS0009,
         (SCS0009, 1280)
     ),
+    servo: (herkulex, DRS0101,
+        (DRS0101, 257),
+        (DRS0201, 258)
+    ),
     servo: (orbita, orbita2d_poulpe,
         (orbita2d_poulpe, 10020)
     ),
@@ -46,3 +121,135 @@ crate::register_servo!(
         (orbita3d_foc, 10031)
     )
 );
+
+impl ServoKind {
+    /// Finds a servo kind from its name (e.g. `dynamixel_XL330M288`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        ServoKind::ALL
+            .iter()
+            .find(|kind| format!("{kind:?}") == name)
+            .copied()
+    }
+
+    /// Identifies the kind of the servo `id` by reading its model number.
+    ///
+    /// The model number is not located at the same address for all families (e.g. 0 for Dynamixel, 3 for Feetech),
+    /// so each known location is tried in turn.
+    pub fn identify(
+        dph: &DynamixelProtocolHandler,
+        serial_port: &mut dyn serialport::SerialPort,
+        id: u8,
+    ) -> crate::Result<Self> {
+        // Herkulex servos only speak their own protocol, and their RAM could be mistaken for another model number
+        let kinds = ServoKind::ALL
+            .iter()
+            .filter(|kind| (kind.family() == ServoFamily::Herkulex) == dph.is_herkulex());
+        let mut addresses: Vec<u16> = kinds
+            .clone()
+            .filter_map(|kind| control_table::model_register(kind.registers()).map(|r| r.addr))
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+
+        let mut model_numbers = Vec::new();
+        for addr in addresses {
+            let bytes = match dph.read(serial_port, id, addr, 2) {
+                // Address of another protocol (e.g. Herkulex EEP), or missing from a Modbus table
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<crate::CommunicationErrorKind>(),
+                        Some(crate::CommunicationErrorKind::AddressOutOfRange(..))
+                            | Some(crate::CommunicationErrorKind::ModbusException(0x02))
+                    ) =>
+                {
+                    continue
+                }
+                r => r?,
+            };
+
+            // Decoded in the byte order of each candidate (big-endian on the Feetech SCS series)
+            for kind in kinds.clone() {
+                let Some(reg) = control_table::model_register(kind.registers()) else {
+                    continue;
+                };
+                if reg.addr != addr {
+                    continue;
+                }
+                match reg.decode(&bytes[..reg.length as usize])? {
+                    control_table::RegisterValue::Int(v) if v == kind.model_number() as i64 => {
+                        return Ok(*kind)
+                    }
+                    v if !model_numbers.contains(&v) => model_numbers.push(v),
+                    _ => {}
+                }
+            }
+        }
+
+        Err(format!("Unknown model number for servo {id}: {model_numbers:?}").into())
+    }
+
+    /// Number of axes in the package, each answering to its own id (2 for the dual-axis 2XL430 and 2XC430)
+    pub fn axes(&self) -> u8 {
+        match self {
+            ServoKind::dynamixel_XL430W2502 | ServoKind::dynamixel_XC430W2502 => 2,
+            _ => 1,
+        }
+    }
+
+    /// Names of the errors set in a raw *hardware_error_status* value (*status_error* on Herkulex servos, empty for kinds without such register)
+    pub fn hardware_errors(&self, raw: u8) -> Vec<&'static str> {
+        use crate::servo::conversion::Conversion;
+
+        match (self, self.family()) {
+            (ServoKind::dynamixel_XL320, _) => dynamixel::xl320::HardwareError::from_raw(raw)
+                .iter_names()
+                .map(|(name, _)| name)
+                .collect(),
+            (_, ServoFamily::Dynamixel)
+                if control_table::find(self.registers(), "hardware_error_status").is_some() =>
+            {
+                dynamixel::x_series::HardwareError::from_raw(raw)
+                    .iter_names()
+                    .map(|(name, _)| name)
+                    .collect()
+            }
+            (_, ServoFamily::Herkulex) => herkulex::drs0101::StatusError::from_raw(raw)
+                .iter_names()
+                .map(|(name, _)| name)
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+/// Registers the register types (enums and flags) of the python module
+#[cfg(feature = "python")]
+pub(crate) fn register_types(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<crate::PingStatus>()?;
+    m.add_class::<dynamixel::StatusReturnLevel>()?;
+    m.add_class::<dynamixel::x_series::OperatingMode>()?;
+    m.add_class::<dynamixel::x_series::Baudrate>()?;
+    m.add_class::<dynamixel::x_series::DriveMode>()?;
+    m.add_class::<dynamixel::x_series::HardwareError>()?;
+    m.add_class::<dynamixel::mx::Baudrate>()?;
+    m.add_class::<dynamixel::ax::Baudrate>()?;
+    m.add_class::<dynamixel::mx::Alarm>()?;
+    m.add_class::<dynamixel::xl320::Baudrate>()?;
+    m.add_class::<dynamixel::xl320::HardwareError>()?;
+    m.add_class::<recover::Recovery>()?;
+    m.add_class::<feetech::Baudrate>()?;
+    m.add_class::<feetech::sts3215::Mode>()?;
+    m.add_class::<hiwonder::lx16a::Lx16aPyController>()?;
+    m.add_class::<herkulex::drs0101::StatusError>()?;
+    m.add_class::<dynamixel::mx::MxMultiTurnPyController>()?;
+    m.add_class::<dynamixel::xl330::Xl330MultiTurnPyController>()?;
+    m.add_class::<dynamixel::xl430::Xl430MultiTurnPyController>()?;
+    m.add_class::<dynamixel::xc330::Xc330MultiTurnPyController>()?;
+    m.add_class::<dynamixel::xh430::Xh430MultiTurnPyController>()?;
+    m.add_class::<dynamixel::xm::XmMultiTurnPyController>()?;
+    m.add_class::<feetech::sts3215::Sts3215MultiTurnPyController>()?;
+    m.add_class::<feetech::sts3250::Sts3250MultiTurnPyController>()?;
+    m.add_class::<feetech::sms::SmsMultiTurnPyController>()?;
+
+    Ok(())
+}
@@ -0,0 +1,382 @@
+//! Continuous (multi-turn) position tracking.
+//!
+//! The position registers of most servos wrap after one revolution (4096 counts on MX, X-series and STS),
+//! so wheels and continuous joints lose track of the revolutions they made.
+//! [MultiTurn] wraps the controllers of these servos (implementing [MultiTurnRegisters]), unwraps successive readings
+//! into a continuous angle, and accepts goals beyond one revolution.
+//!
+//! - In X-series extended position mode (and the other modes where the servo counts revolutions itself),
+//!   the registers are already continuous and are used as is.
+//! - The firmware applies `homing_offset` to both present and goal positions, so the continuous angle is in the homed frame.
+//! - Resetting a tracker sends the CLEAR instruction to servos counting revolutions where the protocol has one (v2),
+//!   bringing the servo count back within one revolution.
+//!
+//! Readings must be frequent enough for the servo to move less than half a revolution between two of them,
+//! and the tracker must be reset after changing the operating mode.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::servo::{dynamixel::xl330::Xl330Controller, multi_turn::MultiTurn};
+//!
+//! let serial_port = serialport::new("/dev/ttyUSB0", 1_000_000).open().unwrap();
+//! let mut c = MultiTurn::new(Xl330Controller::new().with_protocol_v2().with_serial_port(serial_port));
+//!
+//! c.reset_continuous_position(1).unwrap();
+//! c.write_continuous_goal_position(1, 4.0 * std::f64::consts::PI).unwrap();
+//! let angle = c.read_continuous_position(1).unwrap();
+//! ```
+
+use std::{collections::HashMap, f64::consts::PI};
+
+use crate::Result;
+
+#[derive(Debug, Clone, Copy)]
+struct Track {
+    last: i64,
+    turns: i64,
+    continuous: bool,
+}
+
+/// Revolutions tracked for each servo id
+#[derive(Debug, Default, Clone)]
+pub struct MultiTurnTracker {
+    tracks: HashMap<u8, Track>,
+}
+
+impl MultiTurnTracker {
+    /// Unwraps a raw position reading into continuous counts.
+    ///
+    /// `continuous` tells whether the raw value already counts revolutions, it is only used for the first reading of an id.
+    pub fn update(&mut self, id: u8, raw: i64, counts_per_turn: i64, continuous: bool) -> i64 {
+        let track = self.tracks.entry(id).or_insert(Track {
+            last: raw,
+            turns: 0,
+            continuous,
+        });
+
+        if !track.continuous {
+            let delta = raw - track.last;
+            if delta > counts_per_turn / 2 {
+                track.turns -= 1;
+            } else if delta < -counts_per_turn / 2 {
+                track.turns += 1;
+            }
+        }
+        track.last = raw;
+
+        raw + track.turns * counts_per_turn
+    }
+
+    /// Whether the registers of a tracked id already count revolutions
+    pub fn is_continuous(&self, id: u8) -> Option<bool> {
+        self.tracks.get(&id).map(|t| t.continuous)
+    }
+
+    /// Forgets the revolutions of `id`, the next reading starts a new track
+    pub fn reset(&mut self, id: u8) {
+        self.tracks.remove(&id);
+    }
+}
+
+/// Position registers of the servos with a wrapping position register, used by [MultiTurn]
+pub trait MultiTurnRegisters {
+    /// Encoder counts per revolution
+    const COUNTS_PER_TURN: i64;
+    /// Raw position of the 0 rad angle, as in the model `AnglePosition` conversion
+    const ZERO: i64;
+
+    fn read_raw_multi_turn_position(&mut self, id: u8) -> Result<i64>;
+    fn write_raw_multi_turn_goal(&mut self, id: u8, raw: i64) -> Result<()>;
+
+    /// Whether the servo position registers already count revolutions (e.g. X-series extended position mode)
+    fn reports_multi_turn(&mut self, _id: u8) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Resets the revolution count kept by the servo, if any
+    fn clear_multi_turn(&mut self, _id: u8) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl MultiTurnTracker {
+    fn is_continuous_on<C: MultiTurnRegisters>(&self, controller: &mut C, id: u8) -> Result<bool> {
+        match self.is_continuous(id) {
+            Some(continuous) => Ok(continuous),
+            None => controller.reports_multi_turn(id),
+        }
+    }
+
+    /// Reads the present position of `id` on `controller` as a continuous angle in rad
+    pub fn read_continuous_position<C: MultiTurnRegisters>(
+        &mut self,
+        controller: &mut C,
+        id: u8,
+    ) -> Result<f64> {
+        let continuous = self.is_continuous_on(controller, id)?;
+        let raw = controller.read_raw_multi_turn_position(id)?;
+        let counts = self.update(id, raw, C::COUNTS_PER_TURN, continuous);
+
+        Ok((counts - C::ZERO) as f64 * 2.0 * PI / C::COUNTS_PER_TURN as f64)
+    }
+
+    /// Writes a goal position of `id` on `controller` given as a continuous angle in rad
+    pub fn write_continuous_goal_position<C: MultiTurnRegisters>(
+        &mut self,
+        controller: &mut C,
+        id: u8,
+        angle: f64,
+    ) -> Result<()> {
+        let continuous = self.is_continuous_on(controller, id)?;
+        let target = (angle * C::COUNTS_PER_TURN as f64 / (2.0 * PI)).round() as i64 + C::ZERO;
+
+        match continuous {
+            true => controller.write_raw_multi_turn_goal(id, target),
+            false => {
+                controller.write_raw_multi_turn_goal(id, target.rem_euclid(C::COUNTS_PER_TURN))
+            }
+        }
+    }
+
+    /// Resets the revolutions of `id`, and the servo count (CLEAR instruction) if its registers count revolutions
+    pub fn reset_continuous_position<C: MultiTurnRegisters>(
+        &mut self,
+        controller: &mut C,
+        id: u8,
+    ) -> Result<()> {
+        self.reset(id);
+        match controller.reports_multi_turn(id)? {
+            true => controller.clear_multi_turn(id),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Controller tracking the revolutions of its servos.
+///
+/// Wraps the controllers implementing [MultiTurnRegisters], which are still reachable through `Deref`.
+pub struct MultiTurn<C> {
+    controller: C,
+    tracker: MultiTurnTracker,
+}
+
+impl<C: MultiTurnRegisters> MultiTurn<C> {
+    pub fn new(controller: C) -> Self {
+        Self {
+            controller,
+            tracker: MultiTurnTracker::default(),
+        }
+    }
+
+    /// Reads the present position as a continuous angle in rad
+    pub fn read_continuous_position(&mut self, id: u8) -> Result<f64> {
+        self.tracker
+            .read_continuous_position(&mut self.controller, id)
+    }
+
+    /// Writes a goal position given as a continuous angle in rad.
+    ///
+    /// Only servos counting revolutions (extended position mode) turn several times to reach it,
+    /// others go to the same angle within one revolution.
+    pub fn write_continuous_goal_position(&mut self, id: u8, angle: f64) -> Result<()> {
+        self.tracker
+            .write_continuous_goal_position(&mut self.controller, id, angle)
+    }
+
+    /// Resets the tracked revolutions, and the servo count (CLEAR instruction) if its registers count revolutions
+    pub fn reset_continuous_position(&mut self, id: u8) -> Result<()> {
+        self.tracker
+            .reset_continuous_position(&mut self.controller, id)
+    }
+}
+
+impl<C> MultiTurn<C> {
+    /// Gives the controller back, forgetting the tracked revolutions
+    pub fn into_inner(self) -> C {
+        self.controller
+    }
+}
+
+impl<C> std::ops::Deref for MultiTurn<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        &self.controller
+    }
+}
+
+impl<C> std::ops::DerefMut for MultiTurn<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        &mut self.controller
+    }
+}
+
+/// Implements [MultiTurnRegisters] on a generated controller, from the addresses and raw type of its position registers,
+/// and adds the `<Servo>MultiTurnPyController` python class wrapping its python controller.
+///
+/// `extended` (optional) is a function telling from the controller and the id whether the position registers count revolutions.
+/// Controllers with one also reset the servo count with the CLEAR instruction in protocol v2 (in v1, the servo keeps its count).
+macro_rules! impl_multi_turn {
+    ($servo_name:ident, present: $present_addr:expr, goal: $goal_addr:expr, $position_type:ty $(, extended: $extended:expr)?) => {
+        paste::paste! {
+        impl<P: $crate::bus::Transport> $crate::servo::multi_turn::MultiTurnRegisters for [<$servo_name:camel Controller>]<P> {
+            const COUNTS_PER_TURN: i64 = 4096;
+            const ZERO: i64 = 2048;
+
+            fn read_raw_multi_turn_position(&mut self, id: u8) -> $crate::Result<i64> {
+                let val = self.read_raw_data(id, $present_addr, size_of::<$position_type>() as u16)?;
+                Ok(<$position_type>::from_le_bytes(BYTE_ORDER.swapped(val.as_slice().try_into()?)) as i64)
+            }
+
+            fn write_raw_multi_turn_goal(&mut self, id: u8, raw: i64) -> $crate::Result<()> {
+                let raw = <$position_type>::try_from(raw).map_err(|_| {
+                    $crate::servo::conversion::ConversionError::InvalidRawValue(
+                        stringify!($position_type),
+                        raw as u32,
+                    )
+                })?;
+                self.write_raw_data(id, $goal_addr, BYTE_ORDER.swapped(raw.to_le_bytes().to_vec()))
+            }
+
+            $(
+                fn reports_multi_turn(&mut self, id: u8) -> $crate::Result<bool> {
+                    $extended(self, id)
+                }
+
+                fn clear_multi_turn(&mut self, id: u8) -> $crate::Result<()> {
+                    match self.dph.is_v2() {
+                        true => self.dph.clear_multi_turn(&mut *self.serial_port.lock(), id),
+                        false => Ok(()),
+                    }
+                }
+            )?
+        }
+
+        /// Tracks the revolutions of the servos of a python controller
+        #[cfg(feature = "python")]
+        #[gen_stub_pyclass]
+        #[pyo3::pyclass(frozen)]
+        pub struct [<$servo_name:camel MultiTurnPyController>] {
+            controller: Py<[<$servo_name:camel PyController>]>,
+            tracker: std::sync::Mutex<$crate::servo::multi_turn::MultiTurnTracker>,
+        }
+
+        #[cfg(feature = "python")]
+        #[gen_stub_pymethods]
+        #[pymethods]
+        impl [<$servo_name:camel MultiTurnPyController>] {
+            #[new]
+            pub fn new(controller: Py<[<$servo_name:camel PyController>]>) -> Self {
+                Self {
+                    controller,
+                    tracker: Default::default(),
+                }
+            }
+
+            /// Reads the present position as a continuous angle in rad
+            pub fn read_continuous_position(&self, id: u8) -> PyResult<f64> {
+                self.tracker
+                    .lock()
+                    .unwrap()
+                    .read_continuous_position(&mut *self.controller.get().0.lock().unwrap(), id)
+                    .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            }
+
+            /// Writes a goal position given as a continuous angle in rad
+            pub fn write_continuous_goal_position(&self, id: u8, angle: f64) -> PyResult<()> {
+                self.tracker
+                    .lock()
+                    .unwrap()
+                    .write_continuous_goal_position(&mut *self.controller.get().0.lock().unwrap(), id, angle)
+                    .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            }
+
+            /// Resets the tracked revolutions
+            pub fn reset_continuous_position(&self, id: u8) -> PyResult<()> {
+                self.tracker
+                    .lock()
+                    .unwrap()
+                    .reset_continuous_position(&mut *self.controller.get().0.lock().unwrap(), id)
+                    .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+            }
+        }
+        }
+    };
+}
+pub(crate) use impl_multi_turn;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV1, MockSerialPort},
+        servo::dynamixel::{mx::MxController, xm::XmController},
+    };
+
+    #[test]
+    fn unwrap_readings() {
+        let mut tracker = MultiTurnTracker::default();
+
+        let counts: Vec<i64> = [4000, 4090, 10, 2000, 4000, 100, 3000, 4095]
+            .iter()
+            .map(|&raw| tracker.update(1, raw, 4096, false))
+            .collect();
+        assert_eq!(counts, [4000, 4090, 4106, 6096, 8096, 8292, 7096, 8191]);
+
+        // Registers counting revolutions are used as is
+        assert_eq!(tracker.update(2, 10_000, 4096, true), 10_000);
+        assert_eq!(tracker.update(2, 1_000, 4096, true), 1_000);
+
+        tracker.reset(1);
+        assert_eq!(tracker.update(1, 10, 4096, false), 10);
+    }
+
+    #[test]
+    fn continuous_position() {
+        let port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 29, 3));
+        let mut c = MultiTurn::new(
+            MxController::new()
+                .with_protocol_v1()
+                .with_serial_port(Box::new(port)),
+        );
+
+        // Moves the fake servo and reads its continuous position
+        let mut read_at = |raw: i16| {
+            c.write_raw_data(1, 36, raw.to_le_bytes().to_vec()).unwrap();
+            c.read_continuous_position(1).unwrap()
+        };
+
+        assert_eq!(read_at(2048), 0.0);
+        read_at(4000);
+        read_at(1000);
+        assert!((read_at(2048) - 2.0 * PI).abs() < 1e-9);
+        // Going back across the wrap
+        let before = read_at(10);
+        assert!((before - read_at(4000) - 106.0 * 2.0 * PI / 4096.0).abs() < 1e-9);
+
+        c.write_continuous_goal_position(1, 3.0 * PI).unwrap();
+        assert_eq!(c.read_raw_goal_position(1).unwrap()[0], 0);
+
+        c.reset_continuous_position(1).unwrap();
+        c.write_raw_data(1, 36, 2048i16.to_le_bytes().to_vec())
+            .unwrap();
+        assert_eq!(c.read_continuous_position(1).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn reset_extended_position_v1() {
+        // XM in extended position mode over protocol v1, which has no CLEAR instruction
+        let mut servos = FakeServosV1::default().with_servo(1, 1020, 7);
+        servos.servos[0].write(11, &[4]);
+        servos.servos[0].write(132, &10_000i32.to_le_bytes());
+        let mut c = MultiTurn::new(
+            XmController::new()
+                .with_protocol_v1()
+                .with_serial_port(Box::new(MockSerialPort::new(servos))),
+        );
+
+        let angle = c.read_continuous_position(1).unwrap();
+        c.reset_continuous_position(1).unwrap();
+        assert_eq!(c.read_continuous_position(1).unwrap(), angle);
+    }
+}
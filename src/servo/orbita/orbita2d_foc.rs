@@ -36,6 +36,8 @@ pub struct Pid {
     pub d: f32,
 }
 
+crate::servo::control_table::impl_raw_bytes!(MotorValue<T>, Vec3d<T>, MotorPositionSpeedLoad, Pid);
+
 generate_servo!(
     Orbita2dFoc, v1,
     reg: (model_number, r, 0, u16, None),
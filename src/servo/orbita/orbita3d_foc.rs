@@ -44,6 +44,8 @@ pub struct Pid {
     pub d: f32,
 }
 
+crate::servo::control_table::impl_raw_bytes!(DiskValue<T>, Vec3d<T>, DiskPositionSpeedLoad, Pid);
+
 generate_servo!(
     Orbita3dFoc, v1,
     reg: (model_number, r, 0, u16, None),
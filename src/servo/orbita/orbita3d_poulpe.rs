@@ -44,6 +44,8 @@ pub struct Pid {
     pub i: i16,
 }
 
+crate::servo::control_table::impl_raw_bytes!(MotorValue<T>, Vec3d<T>, MotorPositionSpeedLoad, Pid);
+
 generate_servo!(
     Orbita3dPoulpe, v1,
     reg: (model_number, r, 0, u16, None),
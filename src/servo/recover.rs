@@ -0,0 +1,272 @@
+//! Recovery of servos latching hardware errors.
+//!
+//! Dynamixel protocol v2 servos latch their hardware errors (overload, overheating, electrical shock, encoder, input voltage)
+//! in the *hardware_error_status* register, and the errors selected by the *shutdown* register disable the torque.
+//! The torque can then only be enabled again after a reboot, which also resets the RAM area of the control table.
+//!
+//! [recover] reboots a faulted servo and writes back the RAM configuration lost in the reboot (goals, gains and profiles),
+//! then enables the torque again if it was enabled before the fault.
+//! The servo thus moves back towards its previous goal.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{servo::recover, DynamixelProtocolHandler};
+//! use std::time::Duration;
+//!
+//! let mut serial_port = serialport::new("/dev/ttyUSB0", 1_000_000)
+//!     .timeout(Duration::from_millis(10))
+//!     .open()
+//!     .unwrap();
+//! let dph = DynamixelProtocolHandler::v2();
+//!
+//! let recovery = recover::recover(&dph, serial_port.as_mut(), 1).unwrap();
+//! println!("{recovery}");
+//! ```
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    servo::{
+        control_table::{self, RegisterInfo, RegisterValue},
+        ServoKind,
+    },
+    DynamixelProtocolHandler, Result,
+};
+
+/// Longest time a servo may take to answer again after a reboot
+pub const BOOT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Period of the pings sent while waiting for a rebooting servo
+const BOOT_POLL_PERIOD: Duration = Duration::from_millis(10);
+
+/// Recovery errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryError {
+    /// This kind of servo has no *hardware_error_status* register
+    Unsupported(ServoKind),
+    /// The servo did not answer after its reboot
+    NoReply(u8),
+}
+
+impl fmt::Display for RecoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecoveryError::Unsupported(kind) => {
+                write!(f, "No hardware error status on {kind:?} servos")
+            }
+            RecoveryError::NoReply(id) => write!(f, "Servo {id} did not answer after its reboot"),
+        }
+    }
+}
+impl std::error::Error for RecoveryError {}
+
+/// What [recover] found and did
+#[cfg_attr(
+    feature = "python",
+    pyo3_stub_gen::derive::gen_stub_pyclass,
+    pyo3::pyclass(get_all)
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recovery {
+    pub id: u8,
+    /// Raw *hardware_error_status* found before the reboot
+    pub hardware_error_status: u8,
+    /// Names of the errors found before the reboot
+    pub errors: Vec<&'static str>,
+    pub rebooted: bool,
+    /// Registers written back after the reboot
+    pub restored: Vec<&'static str>,
+    pub torque_enabled: bool,
+    /// Names of the errors still set after the reboot (e.g. the servo is still overheating)
+    pub remaining_errors: Vec<&'static str>,
+}
+
+impl Recovery {
+    /// Whether the servo is free of hardware errors
+    pub fn is_recovered(&self) -> bool {
+        self.remaining_errors.is_empty()
+    }
+}
+
+impl fmt::Display for Recovery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.rebooted {
+            return write!(f, "Servo {}: no hardware error", self.id);
+        }
+        write!(
+            f,
+            "Servo {}: errors {:?}, rebooted, restored {:?}, torque {}",
+            self.id,
+            self.errors,
+            self.restored,
+            if self.torque_enabled { "on" } else { "off" }
+        )?;
+        if !self.is_recovered() {
+            write!(f, ", still failing with {:?}", self.remaining_errors)?;
+        }
+        Ok(())
+    }
+}
+
+/// RAM registers written back after a reboot: goals, gains and profiles (the torque is handled separately)
+pub fn ram_config_registers(registers: &[RegisterInfo]) -> Vec<&RegisterInfo> {
+    control_table::unique_registers(registers)
+        .into_iter()
+        .filter(|r| r.access.is_writable() && !r.is_eeprom(registers))
+        .filter(|r| {
+            r.name.starts_with("goal_")
+                || r.name.starts_with("profile_")
+                || r.name.ends_with("_gain")
+                || matches!(r.name, "moving_speed" | "torque_limit")
+        })
+        .collect()
+}
+
+/// Reboots the servo `id` if it reports hardware errors, and restores its RAM configuration.
+///
+/// The errors are logged and returned in the [Recovery].
+/// The torque is only enabled again if it was enabled, or disabled by a *shutdown* error, and no error remains after the reboot.
+pub fn recover(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<Recovery> {
+    let kind = ServoKind::identify(dph, serial_port, id)?;
+    let registers = kind.registers();
+    let status = control_table::find(registers, "hardware_error_status")
+        .ok_or(RecoveryError::Unsupported(kind))?;
+    let torque = control_table::find(registers, "torque_enable");
+
+    let raw = dph.read(serial_port, id, status.addr, 1)?[0];
+    let mut recovery = Recovery {
+        id,
+        hardware_error_status: raw,
+        errors: kind.hardware_errors(raw),
+        rebooted: false,
+        restored: vec![],
+        torque_enabled: false,
+        remaining_errors: vec![],
+    };
+    if raw == 0 {
+        if let Some(torque) = torque {
+            recovery.torque_enabled = torque.read(dph, serial_port, id)? != RegisterValue::Int(0);
+        }
+        return Ok(recovery);
+    }
+    log::warn!("Servo {id}: hardware errors {:?}", recovery.errors);
+
+    // Saved before the reboot resets the RAM area
+    let mut config = Vec::new();
+    for register in ram_config_registers(registers) {
+        config.push((register, register.read(dph, serial_port, id)?));
+    }
+    let torque_was_enabled = match torque {
+        Some(torque) => torque.read(dph, serial_port, id)? != RegisterValue::Int(0),
+        None => false,
+    };
+    let shut_down = match control_table::find(registers, "shutdown") {
+        Some(shutdown) => dph.read(serial_port, id, shutdown.addr, 1)?[0] & raw != 0,
+        None => false,
+    };
+
+    dph.reboot(serial_port, id)?;
+    recovery.rebooted = true;
+    wait_for_boot(dph, serial_port, id)?;
+
+    let raw = dph.read(serial_port, id, status.addr, 1)?[0];
+    recovery.remaining_errors = kind.hardware_errors(raw);
+
+    for (register, value) in config {
+        if register.read(dph, serial_port, id)? != value {
+            register.write(dph, serial_port, id, &value)?;
+            recovery.restored.push(register.name);
+        }
+    }
+
+    match (torque, raw) {
+        (Some(torque), 0) if torque_was_enabled || shut_down => {
+            torque.write(dph, serial_port, id, &RegisterValue::Int(1))?;
+            recovery.torque_enabled = true;
+        }
+        (_, 0) => {}
+        _ => log::warn!(
+            "Servo {id}: hardware errors {:?} remain after the reboot",
+            recovery.remaining_errors
+        ),
+    }
+    log::info!("{recovery}");
+
+    Ok(recovery)
+}
+
+/// Pings the servo until it answers, for at most [BOOT_TIMEOUT]
+fn wait_for_boot(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<()> {
+    let start = Instant::now();
+    loop {
+        std::thread::sleep(BOOT_POLL_PERIOD);
+        if let Ok(true) = dph.ping(serial_port, id) {
+            return Ok(());
+        }
+        if start.elapsed() > BOOT_TIMEOUT {
+            return Err(Box::new(RecoveryError::NoReply(id)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamixel_protocol::mock::{FakeServosV1, FakeServosV2, MockSerialPort};
+
+    #[test]
+    fn reboot_and_restore() {
+        let dph = DynamixelProtocolHandler::v2();
+        // XL330-M288
+        let mut port = MockSerialPort::new(FakeServosV2::default().with_servo(1, 1200));
+
+        let recovery = recover(&dph, &mut port, 1).unwrap();
+        assert!(!recovery.rebooted);
+        assert!(recovery.is_recovered());
+
+        dph.write(&mut port, 1, 116, &3000_i32.to_le_bytes())
+            .unwrap();
+        dph.write(&mut port, 1, 84, &900_u16.to_le_bytes()).unwrap();
+        // Overload, selected by shutdown: the torque was disabled
+        dph.write(&mut port, 1, 63, &[0b110100]).unwrap();
+        dph.write(&mut port, 1, 70, &[0b100000]).unwrap();
+
+        let recovery = recover(&dph, &mut port, 1).unwrap();
+        assert_eq!(recovery.errors, ["OVERLOAD"]);
+        assert!(recovery.rebooted);
+        assert!(recovery.is_recovered());
+        assert_eq!(recovery.restored, ["position_p_gain", "goal_position"]);
+        assert!(recovery.torque_enabled);
+
+        assert_eq!(dph.read(&mut port, 1, 70, 1).unwrap(), [0]);
+        assert_eq!(dph.read(&mut port, 1, 64, 1).unwrap(), [1]);
+        assert_eq!(
+            dph.read(&mut port, 1, 116, 4).unwrap(),
+            3000_i32.to_le_bytes()
+        );
+    }
+
+    #[test]
+    fn unsupported() {
+        let dph = DynamixelProtocolHandler::v1();
+        // MX-28: errors are only reported in status packets
+        let mut port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 29, 3));
+
+        let err = recover(&dph, &mut port, 1).unwrap_err();
+        assert_eq!(
+            *err.downcast::<RecoveryError>().unwrap(),
+            RecoveryError::Unsupported(ServoKind::dynamixel_MX28)
+        );
+    }
+}
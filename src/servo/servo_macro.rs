@@ -1,30 +1,68 @@
 #[macro_export]
 macro_rules! generate_servo {
     ($servo_name:ident, $protocol:ident,
-     $(reg: ($reg_name:ident, $reg_access:ident, $reg_addr:expr, $reg_type:ty, $conv:ident),)+
+     $(byte_order: $byte_order:ident,)?
+     $(reg: ($reg_name:ident, $reg_access:ident, $reg_addr:expr, $reg_type:ty, $conv:ident $(, $unit:ident)?),)+
+     $(group: ($group_name:ident, $group_access:ident, [$($group_reg:ident),+ $(,)?]),)*
     ) => {
         paste::paste! {
-            pub struct [<$servo_name:camel Controller>] {
-                dph: Option<$crate::DynamixelProtocolHandler>,
-                serial_port: Option<Box<dyn serialport::SerialPort>>,
+            /// Controller of the servos sharing a serial port.
+            ///
+            /// Built with `new()`, `with_serial_port` and a `with_protocol_*` method (in any order), or opened at once
+            /// with `open`. Until both the port and the protocol are set, `P` or `D` is `()` and the controller has no
+            /// communication methods, so a missing step is a compile error instead of a panic.
+            pub struct [<$servo_name:camel Controller>]<
+                P = Box<dyn serialport::SerialPort>,
+                D = $crate::DynamixelProtocolHandler,
+            > {
+                dph: D,
+                serial_port: P,
             }
 
-            impl Default for [<$servo_name:camel Controller>] {
+            impl Default for [<$servo_name:camel Controller>]<(), ()> {
                 fn default() -> Self {
                     Self::new()
                 }
             }
 
-            impl [<$servo_name:camel Controller>] {
+            impl [<$servo_name:camel Controller>]<(), ()> {
                 pub fn new() -> Self {
-                    Self {dph: None, serial_port: None}
+                    Self {dph: (), serial_port: ()}
                 }
+            }
+
+            impl<P, D> [<$servo_name:camel Controller>]<P, D> {
                 pub fn with_serial_port(self,
                                         serial_port: Box<dyn serialport::SerialPort>,
-                ) -> Self {
-                    Self {
-                        serial_port: Some(serial_port),
-                        ..self
+                ) -> [<$servo_name:camel Controller>]<Box<dyn serialport::SerialPort>, D> {
+                    [<$servo_name:camel Controller>] {
+                        dph: self.dph,
+                        serial_port,
+                    }
+                }
+                /// Uses a serial port shared with other controllers (see `bus::SharedBus`)
+                pub fn with_bus(self,
+                                bus: $crate::bus::SharedBus,
+                ) -> [<$servo_name:camel Controller>]<$crate::bus::SharedBus, D> {
+                    [<$servo_name:camel Controller>] {
+                        dph: self.dph,
+                        serial_port: bus,
+                    }
+                }
+                /// Accesses the control table through Modbus-RTU holding registers (two bytes per register)
+                pub fn with_protocol_modbus(
+                    self,
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    self.with_protocol_handler($crate::DynamixelProtocolHandler::modbus())
+                }
+                /// Uses a configured protocol handler (e.g. with echo cancellation or per-call deadlines)
+                pub fn with_protocol_handler(
+                    self,
+                    dph: $crate::DynamixelProtocolHandler,
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    [<$servo_name:camel Controller>] {
+                        dph,
+                        serial_port: self.serial_port,
                     }
                 }
             }
@@ -43,10 +81,38 @@ macro_rules! generate_servo {
         $crate::generate_protocol_constructor!($servo_name, $protocol);
         $crate::generate_special_instructions!($servo_name);
         $crate::generate_addr_read_write!($servo_name);
+        $crate::generate_control_table!($servo_name, [$($byte_order)?], $(($reg_name, $reg_access, $reg_addr, $reg_type),)+);
+
+        $(
+            $crate::generate_reg_access!($servo_name, $reg_name, $reg_access, $reg_addr, $reg_type, $conv, [$($unit)?]);
+            $crate::generate_baudrate_change!($servo_name, $reg_name, $conv);
+        )*
+
+        /// Compile-time description of each register
+        pub mod registers {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                $crate::generate_register_type!($reg_name, $reg_access, $reg_addr, $reg_type, $conv);
+            )*
+        }
 
         $(
-            $crate::generate_reg_access!($servo_name, $reg_name, $reg_access, $reg_addr, $reg_type, $conv);
+            $crate::generate_group!($servo_name, $group_name, $group_access, $($group_reg),+);
         )*
+
+        paste::paste! {
+            /// Registers the python classes of the controller and of its register groups
+            #[cfg(feature = "python")]
+            pub(crate) fn register_classes(m: &Bound<'_, PyModule>) -> PyResult<()> {
+                m.add_class::<[<$servo_name:camel PyController>]>()?;
+                $(
+                    m.add_class::<[<$servo_name:camel $group_name:camel>]>()?;
+                )*
+                Ok(())
+            }
+        }
     };
 }
 
@@ -54,14 +120,21 @@ macro_rules! generate_servo {
 macro_rules! generate_protocol_constructor {
     ($servo_name:ident, v1) => {
         paste::paste! {
-            impl [<$servo_name:camel Controller>] {
+            impl<P, D> [<$servo_name:camel Controller>]<P, D> {
                 pub fn with_protocol_v1(
                     self,
-                ) -> Self {
-                    Self {
-                        dph: Some($crate::DynamixelProtocolHandler::v1()),
-                        ..self
-                    }
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    self.with_protocol_handler($crate::DynamixelProtocolHandler::v1())
+                }
+            }
+            impl [<$servo_name:camel Controller>] {
+                /// Opens the serial port at `path` and uses the protocol v1
+                pub fn open(path: &str, baudrate: u32, timeout: std::time::Duration) -> $crate::Result<Self> {
+                    let serial_port = serialport::new(path, baudrate).timeout(timeout).open()?;
+
+                    Ok([<$servo_name:camel Controller>]::new()
+                        .with_serial_port(serial_port)
+                        .with_protocol_v1())
                 }
             }
             #[cfg(feature = "python")]
@@ -69,15 +142,20 @@ macro_rules! generate_protocol_constructor {
             #[pymethods]
             impl [<$servo_name:camel PyController>] {
                 #[new]
-                pub fn new(serial_port: &str, baudrate: u32, timeout: f32) -> PyResult<Self> {
+                #[pyo3(signature = (serial_port, baudrate, timeout, echo_cancellation = false))]
+                pub fn new(serial_port: &str, baudrate: u32, timeout: f32, echo_cancellation: bool) -> PyResult<Self> {
                     let serial_port = serialport::new(serial_port, baudrate)
                         .timeout(std::time::Duration::from_secs_f32(timeout))
                         .open()
                         .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
 
+                    let mut dph = $crate::DynamixelProtocolHandler::v1();
+                    if echo_cancellation {
+                        dph = dph.with_echo_cancellation();
+                    }
                     let c = [<$servo_name:camel Controller>]::new()
                         .with_serial_port(serial_port)
-                        .with_protocol_v1();
+                        .with_protocol_handler(dph);
 
                     Ok(Self(std::sync::Mutex::new(c)))
                 }
@@ -86,14 +164,65 @@ macro_rules! generate_protocol_constructor {
     };
     ($servo_name:ident, v2) => {
         paste::paste! {
-            impl [<$servo_name:camel Controller>] {
+            impl<P, D> [<$servo_name:camel Controller>]<P, D> {
                 pub fn with_protocol_v2(
                     self,
-                ) -> Self {
-                    Self {
-                        dph: Some($crate::DynamixelProtocolHandler::v2()),
-                        ..self
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    self.with_protocol_handler($crate::DynamixelProtocolHandler::v2())
+                }
+            }
+            impl [<$servo_name:camel Controller>] {
+                /// Opens the serial port at `path` and uses the protocol v2
+                pub fn open(path: &str, baudrate: u32, timeout: std::time::Duration) -> $crate::Result<Self> {
+                    let serial_port = serialport::new(path, baudrate).timeout(timeout).open()?;
+
+                    Ok([<$servo_name:camel Controller>]::new()
+                        .with_serial_port(serial_port)
+                        .with_protocol_v2())
+                }
+            }
+            #[cfg(feature = "python")]
+            #[gen_stub_pymethods]
+            #[pymethods]
+            impl [<$servo_name:camel PyController>] {
+                #[new]
+                #[pyo3(signature = (serial_port, baudrate, timeout, echo_cancellation = false))]
+                pub fn new(serial_port: &str, baudrate: u32, timeout: f32, echo_cancellation: bool) -> PyResult<Self> {
+                    let serial_port = serialport::new(serial_port, baudrate)
+                        .timeout(std::time::Duration::from_secs_f32(timeout))
+                        .open()
+                        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+                    let mut dph = $crate::DynamixelProtocolHandler::v2();
+                    if echo_cancellation {
+                        dph = dph.with_echo_cancellation();
                     }
+                    let c = [<$servo_name:camel Controller>]::new()
+                        .with_serial_port(serial_port)
+                        .with_protocol_handler(dph);
+
+                    Ok(Self(std::sync::Mutex::new(c)))
+                }
+            }
+        }
+    };
+    ($servo_name:ident, herkulex) => {
+        paste::paste! {
+            impl<P, D> [<$servo_name:camel Controller>]<P, D> {
+                pub fn with_protocol_herkulex(
+                    self,
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    self.with_protocol_handler($crate::DynamixelProtocolHandler::herkulex())
+                }
+            }
+            impl [<$servo_name:camel Controller>] {
+                /// Opens the serial port at `path` and uses the Herkulex protocol
+                pub fn open(path: &str, baudrate: u32, timeout: std::time::Duration) -> $crate::Result<Self> {
+                    let serial_port = serialport::new(path, baudrate).timeout(timeout).open()?;
+
+                    Ok([<$servo_name:camel Controller>]::new()
+                        .with_serial_port(serial_port)
+                        .with_protocol_herkulex())
                 }
             }
             #[cfg(feature = "python")]
@@ -101,15 +230,74 @@ macro_rules! generate_protocol_constructor {
             #[pymethods]
             impl [<$servo_name:camel PyController>] {
                 #[new]
-                pub fn new(serial_port: &str, baudrate: u32, timeout: f32) -> PyResult<Self> {
+                #[pyo3(signature = (serial_port, baudrate, timeout, echo_cancellation = false))]
+                pub fn new(serial_port: &str, baudrate: u32, timeout: f32, echo_cancellation: bool) -> PyResult<Self> {
                     let serial_port = serialport::new(serial_port, baudrate)
                         .timeout(std::time::Duration::from_secs_f32(timeout))
                         .open()
                         .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
 
+                    let mut dph = $crate::DynamixelProtocolHandler::herkulex();
+                    if echo_cancellation {
+                        dph = dph.with_echo_cancellation();
+                    }
                     let c = [<$servo_name:camel Controller>]::new()
                         .with_serial_port(serial_port)
-                        .with_protocol_v2();
+                        .with_protocol_handler(dph);
+
+                    Ok(Self(std::sync::Mutex::new(c)))
+                }
+            }
+        }
+    };
+    ($servo_name:ident, v1_v2) => {
+        paste::paste! {
+            impl<P, D> [<$servo_name:camel Controller>]<P, D> {
+                pub fn with_protocol_v1(
+                    self,
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    self.with_protocol_handler($crate::DynamixelProtocolHandler::v1())
+                }
+
+                pub fn with_protocol_v2(
+                    self,
+                ) -> [<$servo_name:camel Controller>]<P, $crate::DynamixelProtocolHandler> {
+                    self.with_protocol_handler($crate::DynamixelProtocolHandler::v2())
+                }
+            }
+            impl [<$servo_name:camel Controller>] {
+                /// Opens the serial port at `path` and uses the protocol v2 (see `with_protocol_v1` for v1)
+                pub fn open(path: &str, baudrate: u32, timeout: std::time::Duration) -> $crate::Result<Self> {
+                    let serial_port = serialport::new(path, baudrate).timeout(timeout).open()?;
+
+                    Ok([<$servo_name:camel Controller>]::new()
+                        .with_serial_port(serial_port)
+                        .with_protocol_v2())
+                }
+            }
+            #[cfg(feature = "python")]
+            #[gen_stub_pymethods]
+            #[pymethods]
+            impl [<$servo_name:camel PyController>] {
+                #[new]
+                #[pyo3(signature = (serial_port, baudrate, timeout, protocol = 2, echo_cancellation = false))]
+                pub fn new(serial_port: &str, baudrate: u32, timeout: f32, protocol: u8, echo_cancellation: bool) -> PyResult<Self> {
+                    let mut dph = match protocol {
+                        1 => $crate::DynamixelProtocolHandler::v1(),
+                        2 => $crate::DynamixelProtocolHandler::v2(),
+                        _ => return Err(pyo3::exceptions::PyValueError::new_err(format!("Unknown protocol version {protocol}"))),
+                    };
+                    if echo_cancellation {
+                        dph = dph.with_echo_cancellation();
+                    }
+                    let serial_port = serialport::new(serial_port, baudrate)
+                        .timeout(std::time::Duration::from_secs_f32(timeout))
+                        .open()
+                        .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))?;
+
+                    let c = [<$servo_name:camel Controller>]::new()
+                        .with_serial_port(serial_port)
+                        .with_protocol_handler(dph);
 
                     Ok(Self(std::sync::Mutex::new(c)))
                 }
@@ -122,17 +310,38 @@ macro_rules! generate_protocol_constructor {
 macro_rules! generate_special_instructions {
     ($servo_macro:ident) => {
         paste::paste! {
-            impl [<$servo_macro:camel Controller>] {
+            impl<P: $crate::bus::Transport> [<$servo_macro:camel Controller>]<P> {
                 pub fn ping(&mut self, id: u8) -> $crate::Result<bool> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.ping(serial_port, id)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.ping(&mut *serial_port, id)
+                }
+
+                /// Pings the servo, detecting several servos sharing the id
+                pub fn ping_status(&mut self, id: u8) -> $crate::Result<$crate::PingStatus> {
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.ping_status(&mut *serial_port, id)
+                }
+
+                /// Gives a new id to a servo, refusing collisions (see `servo::id::reassign_id`)
+                pub fn reassign_id(&mut self, old: u8, new: u8) -> $crate::Result<()> {
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    $crate::servo::id::reassign_id(dph, &mut *serial_port, old, new)
                 }
 
                 pub fn reboot(&mut self, id: u8) -> $crate::Result<bool> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.reboot(serial_port, id)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.reboot(&mut *serial_port, id)
+                }
+
+                /// Reboots a servo latching hardware errors and restores its RAM configuration (see `servo::recover::recover`)
+                pub fn recover(&mut self, id: u8) -> $crate::Result<$crate::servo::recover::Recovery> {
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    $crate::servo::recover::recover(dph, &mut *serial_port, id)
                 }
 
                 pub fn factory_reset(
@@ -141,63 +350,536 @@ macro_rules! generate_special_instructions {
                     conserve_id_only: bool,
                     conserve_id_and_baudrate: bool,
                 ) -> $crate::Result<()> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.factory_reset(serial_port, id, conserve_id_only, conserve_id_and_baudrate)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.factory_reset(&mut *serial_port, id, conserve_id_only, conserve_id_and_baudrate)
                 }
             }
         }
     };
 }
 
+/// Generates `change_baudrate` for servos whose baudrate register has a typed conversion (see `servo::baudrate`)
+#[macro_export]
+macro_rules! generate_baudrate_change {
+    ($servo_name:ident, baudrate, $conv:ident) => {
+        $crate::generate_baudrate_change!(@impl $servo_name, $conv);
+    };
+    ($servo_name:ident, baud_rate, $conv:ident) => {
+        $crate::generate_baudrate_change!(@impl $servo_name, $conv);
+    };
+    (@impl $servo_name:ident, None) => {};
+    (@impl $servo_name:ident, $conv:ident) => {
+        paste::paste! {
+            /// Changes the baudrate of the servo and of the serial port, rolling back if the servo does not answer (see `servo::baudrate::change_baudrate`)
+            pub fn change_baudrate(
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                id: u8,
+                target_bps: u32,
+            ) -> $crate::Result<()> {
+                $crate::servo::baudrate::change_baudrate::<$conv>(io, serial_port, id, target_bps)
+            }
+
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                /// Changes the baudrate of the servo and of the owned serial port, rolling back if the servo does not answer
+                pub fn change_baudrate(&mut self, id: u8, target_bps: u32) -> $crate::Result<()> {
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    change_baudrate(dph, &mut *serial_port, id, target_bps)
+                }
+            }
+
+            #[cfg(feature = "python")]
+            #[gen_stub_pymethods]
+            #[pymethods]
+            impl [<$servo_name:camel PyController>] {
+                pub fn change_baudrate(&self, id: u8, target_bps: u32) -> PyResult<()> {
+                    self.0.lock().unwrap().change_baudrate(id, target_bps)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+            }
+        }
+    };
+    ($servo_name:ident, $reg_name:ident, $conv:ident) => {};
+}
+
+#[macro_export]
+macro_rules! generate_control_table {
+    ($servo_name:ident, [$($byte_order:ident)?], $(($reg_name:ident, $reg_access:ident, $reg_addr:expr, $reg_type:ty),)+) => {
+        paste::paste! {
+            /// Byte order of the multi-byte registers of the servo
+            pub const BYTE_ORDER: $crate::servo::control_table::ByteOrder =
+                $crate::register_byte_order!($($byte_order)?);
+
+            /// Control table of the servo, as declared in its definition
+            pub const REGISTERS: &[$crate::servo::control_table::RegisterInfo] = &[
+                $(
+                    $crate::servo::control_table::RegisterInfo {
+                        name: stringify!($reg_name),
+                        addr: $reg_addr,
+                        length: size_of::<$reg_type>() as u8,
+                        access: $crate::register_access!($reg_access),
+                        kind: <$reg_type as $crate::servo::control_table::RawType>::KIND,
+                        byte_order: BYTE_ORDER,
+                    },
+                )+
+            ];
+
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                /// Reads every readable register of the servo (see `servo::snapshot::dump`)
+                pub fn dump(&mut self, id: u8) -> $crate::Result<$crate::servo::snapshot::Snapshot> {
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    $crate::servo::snapshot::dump(dph, &mut *serial_port, id)
+                }
+
+                /// Writes back a snapshot to the servo (see `servo::snapshot::restore`)
+                pub fn restore(
+                    &mut self,
+                    id: u8,
+                    snapshot: &$crate::servo::snapshot::Snapshot,
+                    scope: $crate::servo::snapshot::RestoreScope,
+                ) -> $crate::Result<Vec<$crate::servo::control_table::RegisterChange>> {
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    $crate::servo::snapshot::restore(dph, &mut *serial_port, id, snapshot, scope)
+                }
+            }
+        }
+    };
+}
+
+/// Generates a type describing a register at compile time (see [Register](crate::servo::control_table::Register))
+#[macro_export]
+macro_rules! generate_register_type {
+    ($reg_name:ident, $reg_access:ident, $reg_addr:expr, $reg_type:ty, $conv:ident) => {
+        #[allow(non_camel_case_types)]
+        pub struct $reg_name;
+
+        impl $crate::servo::control_table::Register for $reg_name {
+            const NAME: &'static str = stringify!($reg_name);
+            const ADDR: u16 = $reg_addr;
+            const LENGTH: usize = size_of::<$reg_type>();
+            type Value = $crate::register_value_type!($reg_type, $conv);
+        }
+
+        $crate::generate_register_access_type!($reg_name, $reg_access, $reg_type, $conv);
+    };
+}
+
+#[macro_export]
+macro_rules! register_value_type {
+    ($reg_type:ty, None) => {
+        $reg_type
+    };
+    ($reg_type:ty, $conv:ident) => {
+        <$conv as $crate::servo::conversion::RegisterConversion>::UsiType
+    };
+}
+
+#[macro_export]
+macro_rules! generate_register_access_type {
+    ($reg_name:ident, r, $reg_type:ty, $conv:ident) => {
+        $crate::generate_register_read_type!($reg_name, $reg_type, $conv);
+    };
+    ($reg_name:ident, w, $reg_type:ty, $conv:ident) => {
+        $crate::generate_register_write_type!($reg_name, $reg_type, $conv);
+    };
+    ($reg_name:ident, rw, $reg_type:ty, $conv:ident) => {
+        $crate::generate_register_read_type!($reg_name, $reg_type, $conv);
+        $crate::generate_register_write_type!($reg_name, $reg_type, $conv);
+    };
+}
+
+#[macro_export]
+macro_rules! generate_register_read_type {
+    ($reg_name:ident, $reg_type:ty, None) => {
+        impl $crate::servo::control_table::ReadRegister for $reg_name {
+            fn decode(bytes: &[u8]) -> $crate::Result<$reg_type> {
+                Ok(<$reg_type>::from_le_bytes(
+                    BYTE_ORDER.swapped(bytes.try_into()?),
+                ))
+            }
+        }
+    };
+    ($reg_name:ident, $reg_type:ty, $conv:ident) => {
+        impl $crate::servo::control_table::ReadRegister for $reg_name {
+            fn decode(bytes: &[u8]) -> $crate::Result<Self::Value> {
+                let raw = <$reg_type>::from_le_bytes(BYTE_ORDER.swapped(bytes.try_into()?));
+                Ok(<$conv as $crate::servo::conversion::RegisterConversion>::decode(raw)?)
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! generate_register_write_type {
+    ($reg_name:ident, $reg_type:ty, None) => {
+        impl $crate::servo::control_table::WriteRegister for $reg_name {
+            fn encode(value: $reg_type) -> Vec<u8> {
+                BYTE_ORDER.swapped(value.to_le_bytes().to_vec())
+            }
+        }
+    };
+    ($reg_name:ident, $reg_type:ty, $conv:ident) => {
+        impl $crate::servo::control_table::WriteRegister for $reg_name {
+            fn encode(value: Self::Value) -> Vec<u8> {
+                BYTE_ORDER.swapped(
+                    <$conv as $crate::servo::conversion::RegisterConversion>::encode(value)
+                        .to_le_bytes()
+                        .to_vec(),
+                )
+            }
+        }
+    };
+}
+
+/// Generates the struct and the accessors of a group of contiguous registers.
+///
+/// The registers must follow each other in the control table and allow the group access, this is checked at compile time.
+#[macro_export]
+macro_rules! generate_group {
+    ($servo_name:ident, $group_name:ident, $group_access:ident, $first:ident $(, $field:ident)*) => {
+        paste::paste! {
+            #[doc = concat!("Registers *", stringify!($first), $(", ", stringify!($field),)* "*, accessed at once")]
+            #[derive(Debug, Clone, PartialEq)]
+            #[cfg_attr(
+                feature = "python",
+                pyo3_stub_gen::derive::gen_stub_pyclass,
+                pyo3::pyclass(get_all, set_all)
+            )]
+            pub struct [<$servo_name:camel $group_name:camel>] {
+                pub $first: <registers::$first as $crate::servo::control_table::Register>::Value,
+                $(pub $field: <registers::$field as $crate::servo::control_table::Register>::Value,)*
+            }
+
+            const _: () = {
+                use $crate::servo::control_table::Register;
+
+                let addrs = [registers::$first::ADDR as usize $(, registers::$field::ADDR as usize)*];
+                let lengths = [registers::$first::LENGTH $(, registers::$field::LENGTH)*];
+
+                let mut i = 1;
+                while i < addrs.len() {
+                    assert!(
+                        addrs[i] == addrs[i - 1] + lengths[i - 1],
+                        concat!("Registers of group ", stringify!($group_name), " are not contiguous")
+                    );
+                    i += 1;
+                }
+            };
+
+            impl [<$servo_name:camel $group_name:camel>] {
+                const ADDR: u16 = <registers::$first as $crate::servo::control_table::Register>::ADDR;
+                const LENGTH: usize = <registers::$first as $crate::servo::control_table::Register>::LENGTH
+                    $(+ <registers::$field as $crate::servo::control_table::Register>::LENGTH)*;
+            }
+
+            #[cfg(feature = "python")]
+            #[gen_stub_pymethods]
+            #[pymethods]
+            impl [<$servo_name:camel $group_name:camel>] {
+                #[new]
+                fn py_new(
+                    $first: <registers::$first as $crate::servo::control_table::Register>::Value,
+                    $($field: <registers::$field as $crate::servo::control_table::Register>::Value,)*
+                ) -> Self {
+                    Self { $first $(, $field)* }
+                }
+
+                fn __repr__(&self) -> String {
+                    format!("{self:?}")
+                }
+            }
+
+            $crate::generate_group_access!($servo_name, $group_name, $group_access, $first $(, $field)*);
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! generate_group_access {
+    ($servo_name:ident, $group_name:ident, r, $($field:ident),+) => {
+        $crate::generate_group_read!($servo_name, $group_name, $($field),+);
+    };
+    ($servo_name:ident, $group_name:ident, w, $($field:ident),+) => {
+        $crate::generate_group_write!($servo_name, $group_name, $($field),+);
+    };
+    ($servo_name:ident, $group_name:ident, rw, $($field:ident),+) => {
+        $crate::generate_group_read!($servo_name, $group_name, $($field),+);
+        $crate::generate_group_write!($servo_name, $group_name, $($field),+);
+    };
+}
+
+#[macro_export]
+macro_rules! generate_group_read {
+    ($servo_name:ident, $group_name:ident, $($field:ident),+) => {
+        paste::paste! {
+            impl [<$servo_name:camel $group_name:camel>] {
+                #[allow(unused_assignments)]
+                fn decode(bytes: &[u8]) -> $crate::Result<Self> {
+                    use $crate::servo::control_table::{ReadRegister, Register};
+
+                    if bytes.len() != Self::LENGTH {
+                        return Err(Box::new($crate::CommunicationErrorKind::ParsingError));
+                    }
+                    let mut offset = 0;
+                    $(
+                        let $field = registers::$field::decode(&bytes[offset..offset + registers::$field::LENGTH])?;
+                        offset += registers::$field::LENGTH;
+                    )+
+
+                    Ok(Self { $($field),+ })
+                }
+            }
+
+            #[doc = concat!("Read register group *", stringify!($group_name), "*")]
+            pub fn [<read_ $group_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                id: u8,
+            ) -> $crate::Result<[<$servo_name:camel $group_name:camel>]> {
+                type Group = [<$servo_name:camel $group_name:camel>];
+                let val = io.read(serial_port, id, Group::ADDR, Group::LENGTH as u16)?;
+                Group::decode(&val)
+            }
+
+            #[doc = concat!("Sync read register group *", stringify!($group_name), "*")]
+            pub fn [<sync_read_ $group_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                ids: &[u8],
+            ) -> $crate::Result<Vec<[<$servo_name:camel $group_name:camel>]>> {
+                type Group = [<$servo_name:camel $group_name:camel>];
+                let val = io.sync_read(serial_port, ids, Group::ADDR, Group::LENGTH as u16)?;
+                val.iter().map(|v| Group::decode(v)).collect()
+            }
+
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                #[doc = concat!("Read register group *", stringify!($group_name), "*")]
+                pub fn [<read_ $group_name>](
+                    &mut self,
+                    id: u8,
+                ) -> $crate::Result<[<$servo_name:camel $group_name:camel>]> {
+                    [<read_ $group_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        id,
+                    )
+                }
+
+                #[doc = concat!("Sync read register group *", stringify!($group_name), "*")]
+                pub fn [<sync_read_ $group_name>](
+                    &mut self,
+                    ids: &[u8],
+                ) -> $crate::Result<Vec<[<$servo_name:camel $group_name:camel>]>> {
+                    [<sync_read_ $group_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        ids,
+                    )
+                }
+            }
+
+            #[cfg(feature = "python")]
+            #[gen_stub_pymethods]
+            #[pymethods]
+            impl [<$servo_name:camel PyController>] {
+                #[doc = concat!("Read register group *", stringify!($group_name), "*")]
+                pub fn [<read_ $group_name>](
+                    &self,
+                    id: u8,
+                ) -> PyResult<[<$servo_name:camel $group_name:camel>]> {
+                    self.0.lock().unwrap().[<read_ $group_name>](id)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+
+                #[doc = concat!("Sync read register group *", stringify!($group_name), "*")]
+                pub fn [<sync_read_ $group_name>](
+                    &self,
+                    ids: Vec<u8>,
+                ) -> PyResult<Vec<[<$servo_name:camel $group_name:camel>]>> {
+                    self.0.lock().unwrap().[<sync_read_ $group_name>](&ids)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! generate_group_write {
+    ($servo_name:ident, $group_name:ident, $($field:ident),+) => {
+        paste::paste! {
+            impl [<$servo_name:camel $group_name:camel>] {
+                fn encode(&self) -> Vec<u8> {
+                    use $crate::servo::control_table::WriteRegister;
+
+                    let mut bytes = Vec::new();
+                    $(bytes.extend(registers::$field::encode(self.$field.clone()));)+
+                    bytes
+                }
+            }
+
+            #[doc = concat!("Write register group *", stringify!($group_name), "*")]
+            pub fn [<write_ $group_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                id: u8,
+                val: &[<$servo_name:camel $group_name:camel>],
+            ) -> $crate::Result<()> {
+                io.write(serial_port, id, [<$servo_name:camel $group_name:camel>]::ADDR, &val.encode())
+            }
+
+            #[doc = concat!("Sync write register group *", stringify!($group_name), "*")]
+            pub fn [<sync_write_ $group_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                ids: &[u8],
+                values: &[[<$servo_name:camel $group_name:camel>]],
+            ) -> $crate::Result<()> {
+                io.sync_write(
+                    serial_port,
+                    ids,
+                    [<$servo_name:camel $group_name:camel>]::ADDR,
+                    &values.iter().map(|v| v.encode()).collect::<Vec<Vec<u8>>>(),
+                )
+            }
+
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                #[doc = concat!("Write register group *", stringify!($group_name), "*")]
+                pub fn [<write_ $group_name>](
+                    &mut self,
+                    id: u8,
+                    value: &[<$servo_name:camel $group_name:camel>],
+                ) -> $crate::Result<()> {
+                    [<write_ $group_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        id,
+                        value,
+                    )
+                }
+
+                #[doc = concat!("Sync write register group *", stringify!($group_name), "*")]
+                pub fn [<sync_write_ $group_name>](
+                    &mut self,
+                    ids: &[u8],
+                    values: &[[<$servo_name:camel $group_name:camel>]],
+                ) -> $crate::Result<()> {
+                    [<sync_write_ $group_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        ids,
+                        values,
+                    )
+                }
+            }
+
+            #[cfg(feature = "python")]
+            #[gen_stub_pymethods]
+            #[pymethods]
+            impl [<$servo_name:camel PyController>] {
+                #[doc = concat!("Write register group *", stringify!($group_name), "*")]
+                pub fn [<write_ $group_name>](
+                    &self,
+                    id: u8,
+                    value: [<$servo_name:camel $group_name:camel>],
+                ) -> PyResult<()> {
+                    self.0.lock().unwrap().[<write_ $group_name>](id, &value)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+
+                #[doc = concat!("Sync write register group *", stringify!($group_name), "*")]
+                pub fn [<sync_write_ $group_name>](
+                    &self,
+                    ids: Vec<u8>,
+                    values: Vec<[<$servo_name:camel $group_name:camel>]>,
+                ) -> PyResult<()> {
+                    self.0.lock().unwrap().[<sync_write_ $group_name>](&ids, &values)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+            }
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! register_access {
+    (r) => {
+        $crate::servo::control_table::Access::Read
+    };
+    (w) => {
+        $crate::servo::control_table::Access::Write
+    };
+    (rw) => {
+        $crate::servo::control_table::Access::ReadWrite
+    };
+}
+
+/// Byte order of the registers of a servo definition (`byte_order: big_endian`), little-endian by default
+#[macro_export]
+macro_rules! register_byte_order {
+    () => {
+        $crate::servo::control_table::ByteOrder::LittleEndian
+    };
+    (little_endian) => {
+        $crate::servo::control_table::ByteOrder::LittleEndian
+    };
+    (big_endian) => {
+        $crate::servo::control_table::ByteOrder::BigEndian
+    };
+}
+
 #[macro_export]
 macro_rules! generate_addr_read_write {
     ($servo_name:ident) => {
         paste::paste! {
-            impl [<$servo_name:camel Controller>] {
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
 
                 pub fn read_raw_data(
                     &mut self,
                     id: u8,
-                    addr: u8,
-                    length: u8,
+                    addr: u16,
+                    length: u16,
                 ) -> $crate::Result<Vec<u8>> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.read(serial_port, id, addr, length)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.read(&mut *serial_port, id, addr, length)
                 }
 
                 pub fn write_raw_data(
                     &mut self,
                     id: u8,
-                    addr: u8,
+                    addr: u16,
                     data: Vec<u8>,
                 ) -> $crate::Result<()> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.write(serial_port, id, addr, &data)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.write(&mut *serial_port, id, addr, &data)
                 }
 
                 pub fn sync_read_raw_data(
                     &mut self,
                     ids: &[u8],
-                    addr: u8,
-                    length: u8,
+                    addr: u16,
+                    length: u16,
                 ) -> $crate::Result<Vec<Vec<u8>>> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.sync_read(serial_port, ids, addr, length)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.sync_read(&mut *serial_port, ids, addr, length)
                 }
 
                 pub fn sync_write_raw_data(
                     &mut self,
                     ids: &[u8],
-                    addr: u8,
+                    addr: u16,
                     data: &[Vec<u8>],
                 ) -> $crate::Result<()> {
-                    let dph = self.dph.as_ref().unwrap();
-                    let serial_port = self.serial_port.as_mut().unwrap().as_mut();
-                    dph.sync_write(serial_port, ids, addr, data)
+                    let dph = &self.dph;
+                    let mut serial_port = self.serial_port.lock();
+                    dph.sync_write(&mut *serial_port, ids, addr, data)
                 }
             }
 
@@ -209,8 +891,8 @@ macro_rules! generate_addr_read_write {
                     &self,
                     py: Python,
                     id: u8,
-                    addr: u8,
-                    length: u8,
+                    addr: u16,
+                    length: u16,
                 ) -> PyResult<PyObject> {
 
 
@@ -224,7 +906,7 @@ macro_rules! generate_addr_read_write {
                 pub fn write_raw_data(
                     &self,
                     id: u8,
-                    addr: u8,
+                    addr: u16,
                     data: &Bound<'_, pyo3::types::PyList>,
                 ) -> PyResult<()> {
                     let data = data.extract::<Vec<u8>>()?;
@@ -238,8 +920,8 @@ macro_rules! generate_addr_read_write {
                     &self,
                     py: Python,
                     ids: &Bound<'_, pyo3::types::PyList>,
-                    addr: u8,
-                    length: u8,
+                    addr: u16,
+                    length: u16,
                 ) -> PyResult<PyObject> {
                     let ids = ids.extract::<Vec<u8>>()?;
 
@@ -253,7 +935,7 @@ macro_rules! generate_addr_read_write {
                 pub fn sync_write_raw_data(
                     &self,
                     ids: &Bound<'_, pyo3::types::PyList>,
-                    addr: u8,
+                    addr: u16,
                     data: &Bound<'_, pyo3::types::PyList>,
                 ) -> PyResult<()> {
                     let ids = ids.extract::<Vec<u8>>()?;
@@ -269,11 +951,26 @@ macro_rules! generate_addr_read_write {
                         .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
                 }
 
+                pub fn ping_status(&self, id: u8) -> PyResult<$crate::PingStatus> {
+                    self.0.lock().unwrap().ping_status(id)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+
+                pub fn reassign_id(&self, old: u8, new: u8) -> PyResult<()> {
+                    self.0.lock().unwrap().reassign_id(old, new)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+
                 pub fn reboot(&self, id: u8) -> PyResult<bool> {
                     self.0.lock().unwrap().reboot(id)
                         .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
                 }
 
+                pub fn recover(&self, id: u8) -> PyResult<$crate::servo::recover::Recovery> {
+                    self.0.lock().unwrap().recover(id)
+                        .map_err(|e| pyo3::exceptions::PyRuntimeError::new_err(e.to_string()))
+                }
+
                 #[pyo3(signature = (
                     id,
                     conserve_id_only = true,
@@ -295,15 +992,19 @@ macro_rules! generate_addr_read_write {
 
 #[macro_export]
 macro_rules! generate_reg_access {
-    ($servo_name:ident, $reg_name:ident, r, $reg_addr:expr, $reg_type:ty, $conv:ident) => {
+    ($servo_name:ident, $reg_name:ident, r, $reg_addr:expr, $reg_type:ty, $conv:ident, $unit:tt) => {
         $crate::generate_reg_read!($servo_name, $reg_name, $reg_addr, $reg_type, $conv);
+        $crate::generate_reg_read_typed!($servo_name, $reg_name, $reg_addr, $conv, $unit);
     };
-    ($servo_name:ident, $reg_name:ident, w, $reg_addr:expr, $reg_type:ty, $conv:ident) => {
+    ($servo_name:ident, $reg_name:ident, w, $reg_addr:expr, $reg_type:ty, $conv:ident, $unit:tt) => {
         $crate::generate_reg_write!($servo_name, $reg_name, $reg_addr, $reg_type, $conv);
+        $crate::generate_reg_write_typed!($servo_name, $reg_name, $reg_addr, $conv, $unit);
     };
-    ($servo_name:ident, $reg_name:ident, rw, $reg_addr:expr, $reg_type:ty, $conv:ident) => {
+    ($servo_name:ident, $reg_name:ident, rw, $reg_addr:expr, $reg_type:ty, $conv:ident, $unit:tt) => {
         $crate::generate_reg_read!($servo_name, $reg_name, $reg_addr, $reg_type, $conv);
+        $crate::generate_reg_read_typed!($servo_name, $reg_name, $reg_addr, $conv, $unit);
         $crate::generate_reg_write!($servo_name, $reg_name, $reg_addr, $reg_type, $conv);
+        $crate::generate_reg_write_typed!($servo_name, $reg_name, $reg_addr, $conv, $unit);
     };
 }
 #[macro_export]
@@ -317,7 +1018,7 @@ macro_rules! generate_reg_read {
                 id: u8,
             ) -> $crate::Result<$reg_type> {
                 let val = io.read(serial_port, id, $reg_addr, size_of::<$reg_type>().try_into().unwrap())?;
-                let val = $reg_type::from_le_bytes(val.try_into().unwrap());
+                let val = $reg_type::from_le_bytes(BYTE_ORDER.swapped(val).try_into().unwrap());
 
                 Ok(val)
             }
@@ -330,37 +1031,37 @@ macro_rules! generate_reg_read {
             ) -> $crate::Result<Vec<$reg_type>> {
                 let val: Vec<Vec<u8>> = io.sync_read(serial_port, ids, $reg_addr, size_of::<$reg_type>().try_into().unwrap())?;
                 let val = val
-                    .iter()
-                    .map(|v| $reg_type::from_le_bytes(v.as_slice().try_into().unwrap()))
+                    .into_iter()
+                    .map(|v| $reg_type::from_le_bytes(BYTE_ORDER.swapped(v).try_into().unwrap()))
                     .collect();
 
                 Ok(val)
             }
 
-            impl [<$servo_name:camel Controller>] {
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
                 #[doc = concat!("Sync read register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!($reg_type), ")")]
                 pub fn [<sync_read_ $reg_name>](
                     &mut self,
                     ids: &[u8],
                 ) -> $crate::Result<Vec<$reg_type>> {
                     [<sync_read_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         ids,
                     )
                 }
             }
 
 
-            impl [<$servo_name:camel Controller>] {
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
                 #[doc = concat!("Read register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!($reg_type), ")")]
                 pub fn [<read_ $reg_name>](
                     &mut self,
                     id: u8,
                 ) -> $crate::Result<Vec<$reg_type>> {
                     let r= match [<read_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         id,
                     ){
                         Ok(r) =>Ok(vec![r]),
@@ -421,7 +1122,7 @@ macro_rules! generate_reg_read {
                 id: u8,
             ) -> $crate::Result<$reg_type> {
                 let val = io.read(serial_port, id, $reg_addr, size_of::<$reg_type>().try_into().unwrap())?;
-                let val = $reg_type::from_le_bytes(val.try_into().unwrap());
+                let val = $reg_type::from_le_bytes(BYTE_ORDER.swapped(val).try_into().unwrap());
 
                 Ok(val)
             }
@@ -430,9 +1131,9 @@ macro_rules! generate_reg_read {
                 io: &$crate::DynamixelProtocolHandler,
                 serial_port: &mut dyn serialport::SerialPort,
                 id: u8,
-            ) -> $crate::Result<<$conv as Conversion>::UsiType> {
+            ) -> $crate::Result<<$conv as $crate::servo::conversion::RegisterConversion>::UsiType> {
                 let val = [<read_raw_ $reg_name>](io, serial_port, id)?;
-                let val = $conv::from_raw(val);
+                let val = <$conv as $crate::servo::conversion::RegisterConversion>::decode(val)?;
                 Ok(val)
             }
 
@@ -444,8 +1145,8 @@ macro_rules! generate_reg_read {
             ) -> $crate::Result<Vec<$reg_type>> {
                 let val: Vec<Vec<u8>> = io.sync_read(serial_port, ids, $reg_addr, size_of::<$reg_type>().try_into().unwrap())?;
                 let val = val
-                    .iter()
-                    .map(|v| $reg_type::from_le_bytes(v.as_slice().try_into().unwrap()))
+                    .into_iter()
+                    .map(|v| $reg_type::from_le_bytes(BYTE_ORDER.swapped(v).try_into().unwrap()))
                     .collect();
 
                 Ok(val)
@@ -455,25 +1156,25 @@ macro_rules! generate_reg_read {
                 io: &$crate::DynamixelProtocolHandler,
                 serial_port: &mut dyn serialport::SerialPort,
                 ids: &[u8],
-            ) -> $crate::Result<Vec<<$conv as Conversion>::UsiType>> {
+            ) -> $crate::Result<Vec<<$conv as $crate::servo::conversion::RegisterConversion>::UsiType>> {
                 let val = [<sync_read_raw_ $reg_name>](io, serial_port, ids)?;
                 let val = val
                     .iter()
-                    .map(|&v| $conv::from_raw(v))
-                    .collect();
+                    .map(|&v| <$conv as $crate::servo::conversion::RegisterConversion>::decode(v))
+                    .collect::<std::result::Result<Vec<_>, _>>()?;
 
                 Ok(val)
             }
 
-            impl [<$servo_name:camel Controller>] {
-                #[doc = concat!("Sync read raw register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as Conversion>::UsiType), ")")]
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                #[doc = concat!("Sync read raw register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as RegisterConversion>::UsiType), ")")]
                 pub fn [<sync_read_raw_ $reg_name>](
                     &mut self,
                     ids: &[u8],
                 ) -> $crate::Result<Vec<$reg_type>> {
                     [<sync_read_raw_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         ids,
                     )
                 }
@@ -482,22 +1183,22 @@ macro_rules! generate_reg_read {
                 pub fn [<sync_read_ $reg_name>](
                     &mut self,
                     ids: &[u8],
-                ) -> $crate::Result<Vec<<$conv as Conversion>::UsiType>> {
+                ) -> $crate::Result<Vec<<$conv as $crate::servo::conversion::RegisterConversion>::UsiType>> {
                     [<sync_read_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         ids,
                     )
                 }
 
-                #[doc = concat!("Read raw register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as Conversion>::UsiType), ")")]
+                #[doc = concat!("Read raw register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as RegisterConversion>::UsiType), ")")]
                 pub fn [<read_raw_ $reg_name>](
                     &mut self,
                     id: u8,
                 ) -> $crate::Result<Vec<$reg_type>> {
                     let r=match([<read_raw_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         id,
                     ))
                     {
@@ -511,10 +1212,10 @@ macro_rules! generate_reg_read {
                 pub fn [<read_ $reg_name>](
                     &mut self,
                     id: u8,
-                ) -> $crate::Result< Vec<<$conv as Conversion>::UsiType  >> {
+                ) -> $crate::Result< Vec<<$conv as $crate::servo::conversion::RegisterConversion>::UsiType  >> {
                     let r=match([<read_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         id,
                     )){
                         Ok(r) => Ok(vec![r]),
@@ -602,7 +1303,7 @@ macro_rules! generate_reg_write {
                 id: u8,
                 val: $reg_type,
             ) -> $crate::Result<()> {
-                io.write(serial_port, id, $reg_addr, &val.to_le_bytes())
+                io.write(serial_port, id, $reg_addr, &BYTE_ORDER.swapped(val.to_le_bytes()))
             }
 
             #[doc = concat!("Sync write register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!($reg_type), ")")]
@@ -618,12 +1319,12 @@ macro_rules! generate_reg_write {
                     $reg_addr,
                     &values
                         .iter()
-                        .map(|v| v.to_le_bytes().to_vec())
+                        .map(|v| BYTE_ORDER.swapped(v.to_le_bytes().to_vec()))
                         .collect::<Vec<Vec<u8>>>(),
                 )
             }
 
-            impl [<$servo_name:camel Controller>] {
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
                 #[doc = concat!("Sync write register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!($reg_type), ")")]
                 pub fn [<sync_write_ $reg_name>](
                     &mut self,
@@ -631,8 +1332,8 @@ macro_rules! generate_reg_write {
                     values: &[$reg_type],
                 ) -> $crate::Result<()> {
                     [<sync_write_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         ids,
                         values,
                     )
@@ -646,8 +1347,8 @@ macro_rules! generate_reg_write {
                     value: $reg_type,
                 ) -> $crate::Result<()> {
                     [<write_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         id,
                         value,
                     )
@@ -699,16 +1400,16 @@ macro_rules! generate_reg_write {
                 id: u8,
                 val: $reg_type,
             ) -> $crate::Result<()> {
-                io.write(serial_port, id, $reg_addr, &val.to_le_bytes())
+                io.write(serial_port, id, $reg_addr, &BYTE_ORDER.swapped(val.to_le_bytes()))
             }
 
             pub fn [<write_ $reg_name>](
                 io: &$crate::DynamixelProtocolHandler,
                 serial_port: &mut dyn serialport::SerialPort,
                 id: u8,
-                val: <$conv as Conversion>::UsiType,
+                val: <$conv as $crate::servo::conversion::RegisterConversion>::UsiType,
             ) -> $crate::Result<()> {
-                let val = $conv::to_raw(val);
+                let val = <$conv as $crate::servo::conversion::RegisterConversion>::encode(val);
                 [<write_raw_ $reg_name>](io, serial_port, id, val)
             }
 
@@ -725,7 +1426,7 @@ macro_rules! generate_reg_write {
                     $reg_addr,
                     &values
                         .iter()
-                        .map(|v| v.to_le_bytes().to_vec())
+                        .map(|v| BYTE_ORDER.swapped(v.to_le_bytes().to_vec()))
                         .collect::<Vec<Vec<u8>>>(),
                 )
             }
@@ -734,16 +1435,16 @@ macro_rules! generate_reg_write {
                 io: &$crate::DynamixelProtocolHandler,
                 serial_port: &mut dyn serialport::SerialPort,
                 ids: &[u8],
-                values: &[<$conv as Conversion>::UsiType],
+                values: &[<$conv as $crate::servo::conversion::RegisterConversion>::UsiType],
             ) -> $crate::Result<()> {
                 let values = values
                     .iter()
-                    .map(|&v| $conv::to_raw(v))
+                    .map(|&v| <$conv as $crate::servo::conversion::RegisterConversion>::encode(v))
                     .collect::<Vec<_>>();
                 [<sync_write_raw_ $reg_name>](io, serial_port, ids, &values)
             }
 
-            impl [<$servo_name:camel Controller>] {
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
                 #[doc = concat!("Sync write raw register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!($reg_type), ")")]
                 pub fn [<sync_write_raw_ $reg_name>](
                     &mut self,
@@ -751,22 +1452,22 @@ macro_rules! generate_reg_write {
                     values: &[$reg_type],
                 ) -> $crate::Result<()> {
                     [<sync_write_raw_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         ids,
                         values,
                     )
                 }
 
-                #[doc = concat!("Sync write register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as Conversion>::UsiType), ")")]
+                #[doc = concat!("Sync write register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as RegisterConversion>::UsiType), ")")]
                 pub fn [<sync_write_ $reg_name>](
                     &mut self,
                     ids: &[u8],
-                    values: &[<$conv as Conversion>::UsiType],
+                    values: &[<$conv as $crate::servo::conversion::RegisterConversion>::UsiType],
                 ) -> $crate::Result<()> {
                     [<sync_write_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         ids,
                         values,
                     )
@@ -779,22 +1480,22 @@ macro_rules! generate_reg_write {
                     value: $reg_type,
                 ) -> $crate::Result<()> {
                     [<write_raw_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         id,
                         value,
                     )
                 }
 
-                #[doc = concat!("Write register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as Conversion>::UsiType), ")")]
+                #[doc = concat!("Write register *", stringify!($name), "* (addr: ", stringify!($addr), ", type: ", stringify!(<$conv as RegisterConversion>::UsiType), ")")]
                 pub fn [<write_ $reg_name>](
                     &mut self,
                     id: u8,
-                    value: <$conv as Conversion>::UsiType,
+                    value: <$conv as $crate::servo::conversion::RegisterConversion>::UsiType,
                 ) -> $crate::Result<()> {
                     [<write_ $reg_name>](
-                        self.dph.as_ref().unwrap(),
-                        self.serial_port.as_mut().unwrap().as_mut(),
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
                         id,
                         value,
                     )
@@ -827,7 +1528,7 @@ macro_rules! generate_reg_write {
                     values: &Bound<'_, pyo3::types::PyList>,
                 ) -> PyResult<()> {
                     let ids = ids.extract::<Vec<u8>>()?;
-                    let values = values.extract::<Vec<<$conv as Conversion>::UsiType>>()?;
+                    let values = values.extract::<Vec<<$conv as $crate::servo::conversion::RegisterConversion>::UsiType>>()?;
 
                     self.0.lock().unwrap().[<sync_write_ $reg_name>](&ids, &values).map_err(|e| {
                         pyo3::exceptions::PyRuntimeError::new_err(e.to_string())
@@ -851,7 +1552,7 @@ macro_rules! generate_reg_write {
                 pub fn [<write_ $reg_name>](
                     &self,
                     id: u8,
-                    value: <$conv as Conversion>::UsiType,
+                    value: <$conv as $crate::servo::conversion::RegisterConversion>::UsiType,
                 ) -> PyResult<()> {
 
                     self.0.lock().unwrap().[<write_ $reg_name>](id, value).map_err(|e| {
@@ -866,6 +1567,128 @@ macro_rules! generate_reg_write {
     };
 }
 
+/// Generates the unit typed accessors of a register declared with the `unit` marker (`units` feature).
+///
+/// Its conversion must implement [UnitConversion](crate::servo::units::UnitConversion), which gives the unit newtype.
+#[macro_export]
+macro_rules! generate_reg_read_typed {
+    ($servo_name:ident, $reg_name:ident, $reg_addr:expr, $conv:ident, []) => {};
+    ($servo_name:ident, $reg_name:ident, $reg_addr:expr, $conv:ident, [unit]) => {
+        paste::paste! {
+            #[cfg(feature = "units")]
+            #[doc = concat!("Read register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+            pub fn [<read_typed_ $reg_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                id: u8,
+            ) -> $crate::Result<<$conv as $crate::servo::units::UnitConversion>::Unit> {
+                [<read_ $reg_name>](io, serial_port, id).map(Into::into)
+            }
+
+            #[cfg(feature = "units")]
+            #[doc = concat!("Sync read register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+            pub fn [<sync_read_typed_ $reg_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                ids: &[u8],
+            ) -> $crate::Result<Vec<<$conv as $crate::servo::units::UnitConversion>::Unit>> {
+                let val = [<sync_read_ $reg_name>](io, serial_port, ids)?;
+                Ok(val.into_iter().map(Into::into).collect())
+            }
+
+            #[cfg(feature = "units")]
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                #[doc = concat!("Read register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+                pub fn [<read_typed_ $reg_name>](
+                    &mut self,
+                    id: u8,
+                ) -> $crate::Result<<$conv as $crate::servo::units::UnitConversion>::Unit> {
+                    [<read_typed_ $reg_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        id,
+                    )
+                }
+
+                #[doc = concat!("Sync read register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+                pub fn [<sync_read_typed_ $reg_name>](
+                    &mut self,
+                    ids: &[u8],
+                ) -> $crate::Result<Vec<<$conv as $crate::servo::units::UnitConversion>::Unit>> {
+                    [<sync_read_typed_ $reg_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        ids,
+                    )
+                }
+            }
+        }
+    };
+}
+
+/// See [generate_reg_read_typed]
+#[macro_export]
+macro_rules! generate_reg_write_typed {
+    ($servo_name:ident, $reg_name:ident, $reg_addr:expr, $conv:ident, []) => {};
+    ($servo_name:ident, $reg_name:ident, $reg_addr:expr, $conv:ident, [unit]) => {
+        paste::paste! {
+            #[cfg(feature = "units")]
+            #[doc = concat!("Write register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+            pub fn [<write_typed_ $reg_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                id: u8,
+                val: <$conv as $crate::servo::units::UnitConversion>::Unit,
+            ) -> $crate::Result<()> {
+                [<write_ $reg_name>](io, serial_port, id, val.into())
+            }
+
+            #[cfg(feature = "units")]
+            #[doc = concat!("Sync write register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+            pub fn [<sync_write_typed_ $reg_name>](
+                io: &$crate::DynamixelProtocolHandler,
+                serial_port: &mut dyn serialport::SerialPort,
+                ids: &[u8],
+                values: &[<$conv as $crate::servo::units::UnitConversion>::Unit],
+            ) -> $crate::Result<()> {
+                let values = values.iter().map(|&v| v.into()).collect::<Vec<_>>();
+                [<sync_write_ $reg_name>](io, serial_port, ids, &values)
+            }
+
+            #[cfg(feature = "units")]
+            impl<P: $crate::bus::Transport> [<$servo_name:camel Controller>]<P> {
+                #[doc = concat!("Write register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+                pub fn [<write_typed_ $reg_name>](
+                    &mut self,
+                    id: u8,
+                    value: <$conv as $crate::servo::units::UnitConversion>::Unit,
+                ) -> $crate::Result<()> {
+                    [<write_typed_ $reg_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        id,
+                        value,
+                    )
+                }
+
+                #[doc = concat!("Sync write register *", stringify!($reg_name), "* (addr: ", stringify!($reg_addr), ") in its unit")]
+                pub fn [<sync_write_typed_ $reg_name>](
+                    &mut self,
+                    ids: &[u8],
+                    values: &[<$conv as $crate::servo::units::UnitConversion>::Unit],
+                ) -> $crate::Result<()> {
+                    [<sync_write_typed_ $reg_name>](
+                        &self.dph,
+                        &mut *self.serial_port.lock(),
+                        ids,
+                        values,
+                    )
+                }
+            }
+        }
+    };
+}
+
 /// Generates write and sync_write functions with feedback for given register
 #[macro_export]
 macro_rules! generate_reg_write_fb {
@@ -910,7 +1733,7 @@ macro_rules! register_servo {
                $(($name:ident, $model_number:expr)),+)
     ),+) => {
         paste::paste! {
-            #[derive(Debug, Clone, Copy)]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
             pub enum ServoKind {
                 $(
                     $(
@@ -920,6 +1743,15 @@ macro_rules! register_servo {
                 )+
             }
             impl ServoKind {
+                /// All registered servo kinds
+                pub const ALL: &'static [ServoKind] = &[
+                    $(
+                        $(
+                            Self::[<$group _ $name>],
+                        )+
+                    )+
+                ];
+
                 pub fn try_from(model_number: u16) -> Result<Self, String> {
                     match model_number {
                         $(
@@ -930,6 +1762,37 @@ macro_rules! register_servo {
                             _ => Err(format!("Unknown model number: {}", model_number)),
                     }
                 }
+
+                pub fn model_number(&self) -> u16 {
+                    match self {
+                        $(
+                            $(
+                                Self::[<$group _ $name>] => $model_number,
+                            )+
+                        )+
+                    }
+                }
+
+                pub fn family(&self) -> $crate::servo::ServoFamily {
+                    match self {
+                        $(
+                            $(
+                                Self::[<$group _ $name>] => $crate::servo::ServoFamily::[<$group:camel>],
+                            )+
+                        )+
+                    }
+                }
+
+                /// Control table of this kind of servo
+                pub fn registers(&self) -> &'static [$crate::servo::control_table::RegisterInfo] {
+                    match self {
+                        $(
+                            $(
+                                Self::[<$group _ $name>] => $group::[<$servo:lower>]::REGISTERS,
+                            )+
+                        )+
+                    }
+                }
             }
 
             #[cfg(feature = "python")]
@@ -938,7 +1801,7 @@ macro_rules! register_servo {
             #[cfg(feature = "python")]
             pub(crate) fn register_class(m: &Bound<'_, PyModule>) -> PyResult<()> {
                 $(
-                    m.add_class::<$group::[<$servo:lower>]::[<$servo:camel PyController>]>()?;
+                    $group::[<$servo:lower>]::register_classes(m)?;
                 )+
 
                 Ok(())
@@ -0,0 +1,212 @@
+//! Full control table dump and restore.
+//!
+//! A [Snapshot] holds the value of every readable register of a servo, along with its model and firmware.
+//! It can be saved to (and loaded from) a JSON, YAML or TOML file and restored on the same or another servo of the same model.
+//! Only the EEPROM (configuration) registers are restored by default, see [RestoreScope].
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::{servo::snapshot::{self, Snapshot}, DynamixelProtocolHandler};
+//! use std::time::Duration;
+//!
+//! let mut serial_port = serialport::new("/dev/ttyUSB0", 1_000_000)
+//!     .timeout(Duration::from_millis(10))
+//!     .open()
+//!     .expect("Failed to open port");
+//! let dph = DynamixelProtocolHandler::v2();
+//!
+//! // Save the configuration of the old servo...
+//! let snap = snapshot::dump(&dph, serial_port.as_mut(), 1).unwrap();
+//! snap.save("servo_1.yaml").unwrap();
+//!
+//! // ...and apply it to its replacement.
+//! let snap = Snapshot::load("servo_1.yaml").unwrap();
+//! snapshot::restore(&dph, serial_port.as_mut(), 1, &snap, Default::default()).unwrap();
+//! ```
+
+use std::{collections::BTreeMap, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    servo::{
+        control_table::{self, ControlTableError, RegisterChange, RegisterValue},
+        file_format, ServoKind,
+    },
+    DynamixelProtocolHandler, Result,
+};
+
+use control_table::LINK_REGISTERS;
+
+/// Values of all readable registers of a servo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Servo kind (see [ServoKind])
+    pub model: String,
+    pub model_number: u16,
+    pub firmware_version: Option<String>,
+    /// Id of the dumped servo
+    pub id: u8,
+    /// Raw register values, by register name
+    pub registers: BTreeMap<String, RegisterValue>,
+}
+
+impl Snapshot {
+    /// Saves the snapshot (format deduced from the file extension)
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        file_format::save(self, path)
+    }
+
+    /// Loads a snapshot (format deduced from the file extension)
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        file_format::load(path)
+    }
+}
+
+/// Registers written back by [restore]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestoreScope {
+    /// Only the EEPROM area (the configuration of the servo)
+    #[default]
+    Eeprom,
+    /// The EEPROM and RAM areas: goals, gains and `torque_enable` are written back too,
+    /// so the servo may move to the goal it had when dumped
+    EepromAndRam,
+}
+
+/// Reads every readable register of the servo `id`.
+///
+/// The servo model is identified first, then its control table is read register by register.
+pub fn dump(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+) -> Result<Snapshot> {
+    let kind = ServoKind::identify(dph, serial_port, id)?;
+
+    let mut registers = BTreeMap::new();
+    for reg in control_table::unique_registers(kind.registers()) {
+        if reg.access.is_readable() {
+            registers.insert(reg.name.to_string(), reg.read(dph, serial_port, id)?);
+        }
+    }
+
+    let firmware_version = match (
+        registers.get("firmware_version"),
+        registers.get("firmware_major_version"),
+        registers.get("firmware_minor_version"),
+    ) {
+        (Some(v), _, _) => Some(v.to_string()),
+        (None, Some(major), Some(minor)) => Some(format!("{major}.{minor}")),
+        _ => None,
+    };
+
+    Ok(Snapshot {
+        model: format!("{kind:?}"),
+        model_number: kind.model_number(),
+        firmware_version,
+        id,
+        registers,
+    })
+}
+
+/// Writes the registers of a snapshot to the servo `id`.
+///
+/// The servo must be of the same model as the dumped one.
+/// Only writable registers of the `scope` whose value differs are written (`id`, baudrate and `lock` are never restored:
+/// locking a Dynamixel EEPROM lasts until a power cycle).
+/// The torque is disabled (and Feetech EEPROM unlocked) while writing, then set back to its previous value,
+/// or to the `torque_enable` value of the snapshot when restoring the RAM area.
+///
+/// Returns the list of applied changes.
+pub fn restore(
+    dph: &DynamixelProtocolHandler,
+    serial_port: &mut dyn serialport::SerialPort,
+    id: u8,
+    snapshot: &Snapshot,
+    scope: RestoreScope,
+) -> Result<Vec<RegisterChange>> {
+    let kind = ServoKind::identify(dph, serial_port, id)?;
+    if kind.model_number() != snapshot.model_number {
+        return Err(Box::new(ControlTableError::ModelMismatch(
+            snapshot.model_number,
+            kind.model_number(),
+        )));
+    }
+
+    let registers = kind.registers();
+    let values = control_table::unique_registers(registers)
+        .into_iter()
+        .filter(|reg| reg.access.is_writable() && !LINK_REGISTERS.contains(&reg.name))
+        .filter(|reg| reg.name != "lock")
+        .filter(|reg| scope == RestoreScope::EepromAndRam || reg.is_eeprom(registers))
+        .filter_map(|reg| snapshot.registers.get(reg.name).map(|v| (*reg, v.clone())))
+        .collect::<Vec<_>>();
+
+    let changes = control_table::diff(dph, serial_port, id, &values)?;
+    control_table::write_changes(dph, serial_port, id, kind, &changes)?;
+
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::servo::file_format::FileFormat;
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            model: "dynamixel_XL330M288".to_string(),
+            model_number: 1200,
+            firmware_version: Some("46".to_string()),
+            id: 3,
+            registers: BTreeMap::from([
+                ("homing_offset".to_string(), RegisterValue::Int(-1024)),
+                ("return_delay_time".to_string(), RegisterValue::Int(0)),
+                (
+                    "velocity_limit".to_string(),
+                    RegisterValue::Bytes(vec![1, 2, 3, 4]),
+                ),
+            ]),
+        }
+    }
+
+    #[test]
+    fn restore_eeprom_only() {
+        use crate::dynamixel_protocol::mock::{FakeServosV2, MockSerialPort};
+
+        let mut port = MockSerialPort::new(FakeServosV2::default().with_servo(1, 1200));
+        let dph = DynamixelProtocolHandler::v2();
+
+        let mut snap = dump(&dph, &mut port, 1).unwrap();
+        for (name, value) in [
+            ("return_delay_time", 0),
+            ("torque_enable", 1),
+            ("goal_position", 2048),
+        ] {
+            snap.registers
+                .insert(name.to_string(), RegisterValue::Int(value));
+        }
+        dph.write(&mut port, 1, 9, &[250]).unwrap();
+
+        // Only the EEPROM is written back by default
+        let changes = restore(&dph, &mut port, 1, &snap, RestoreScope::Eeprom).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].register.name, "return_delay_time");
+        assert_eq!(dph.read(&mut port, 1, 64, 1).unwrap(), [0]);
+        assert_eq!(dph.read(&mut port, 1, 116, 4).unwrap(), [0; 4]);
+
+        let changes = restore(&dph, &mut port, 1, &snap, RestoreScope::EepromAndRam).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(dph.read(&mut port, 1, 64, 1).unwrap(), [1]);
+    }
+
+    #[test]
+    fn serialization_round_trip() {
+        let snap = snapshot();
+        for format in [FileFormat::Json, FileFormat::Yaml, FileFormat::Toml] {
+            let s = format.serialize(&snap).unwrap();
+            assert_eq!(format.deserialize::<Snapshot>(&s).unwrap(), snap);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Unit newtypes for the converted registers (`units` feature).
+//!
+//! The `f64` API does not say whether a value is in rad, rad/s, A, V or °C.
+//! With this feature, each register declared with the `unit` marker (its conversion having a physical unit) also gets
+//! `read_typed_*`, `sync_read_typed_*`, `write_typed_*` and `sync_write_typed_*` accessors, taking and returning the
+//! matching newtype. Registers without unit (e.g. modes and flags) have no typed accessors.
+//!
+//! The raw and `f64` accessors are unchanged.
+//!
+//! ## Example
+//! ```no_run
+//! use rustypot::servo::{dynamixel::mx::MxController, units::Radians};
+//!
+//! let serial_port = serialport::new("/dev/ttyUSB0", 1_000_000).open().unwrap();
+//! let mut c = MxController::new().with_protocol_v1().with_serial_port(serial_port);
+//!
+//! let position: Radians = c.read_typed_present_position(1).unwrap();
+//! c.write_typed_goal_position(1, position + Radians::from_degrees(10.0)).unwrap();
+//! ```
+//!
+//! Reading a position as a current is a compile error:
+//! ```compile_fail
+//! # use rustypot::servo::{dynamixel::mx::MxController, units::Amperes};
+//! # fn read(c: &mut MxController) {
+//! let current: Amperes = c.read_typed_present_position(1).unwrap();
+//! # }
+//! ```
+//!
+//! So is a typed access to a register without unit:
+//! ```compile_fail
+//! # use rustypot::servo::dynamixel::mx::MxController;
+//! # fn read(c: &mut MxController) {
+//! c.read_typed_alarm_led(1).unwrap();
+//! # }
+//! ```
+
+use std::{
+    fmt,
+    ops::{Add, Div, Mul, Neg, Sub},
+};
+
+use crate::servo::conversion::{Current, Temperature, Velocity, Voltage};
+
+macro_rules! unit {
+    ($(#[$attr:meta])* $name:ident, $symbol:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+        pub struct $name(pub f64);
+
+        impl $name {
+            /// Value in the SI unit
+            pub fn value(self) -> f64 {
+                self.0
+            }
+        }
+
+        impl From<f64> for $name {
+            fn from(value: f64) -> Self {
+                $name(value)
+            }
+        }
+
+        impl From<$name> for f64 {
+            fn from(value: $name) -> f64 {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} {}", self.0, $symbol)
+            }
+        }
+
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> $name {
+                $name(self.0 + rhs.0)
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> $name {
+                $name(self.0 - rhs.0)
+            }
+        }
+
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $name(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = $name;
+            fn mul(self, rhs: f64) -> $name {
+                $name(self.0 * rhs)
+            }
+        }
+
+        impl Div<f64> for $name {
+            type Output = $name;
+            fn div(self, rhs: f64) -> $name {
+                $name(self.0 / rhs)
+            }
+        }
+    };
+}
+
+unit!(
+    /// Angle in rad
+    Radians,
+    "rad"
+);
+unit!(
+    /// Angular velocity in rad/s
+    RadiansPerSecond,
+    "rad/s"
+);
+unit!(
+    /// Current in A
+    Amperes,
+    "A"
+);
+unit!(
+    /// Voltage in V
+    Volts,
+    "V"
+);
+unit!(
+    /// Temperature in °C
+    Celsius,
+    "°C"
+);
+
+impl Radians {
+    pub fn from_degrees(degrees: f64) -> Self {
+        Radians(degrees.to_radians())
+    }
+
+    pub fn to_degrees(self) -> f64 {
+        self.0.to_degrees()
+    }
+}
+
+impl RadiansPerSecond {
+    pub fn from_rpm(rpm: f64) -> Self {
+        RadiansPerSecond(rpm * 2.0 * std::f64::consts::PI / 60.0)
+    }
+
+    pub fn to_rpm(self) -> f64 {
+        self.0 * 60.0 / (2.0 * std::f64::consts::PI)
+    }
+}
+
+impl Amperes {
+    pub fn from_milliamps(milliamps: f64) -> Self {
+        Amperes(milliamps * 1e-3)
+    }
+
+    pub fn to_milliamps(self) -> f64 {
+        self.0 * 1e3
+    }
+}
+
+impl Celsius {
+    pub fn from_fahrenheit(fahrenheit: f64) -> Self {
+        Celsius((fahrenheit - 32.0) * 5.0 / 9.0)
+    }
+
+    pub fn to_fahrenheit(self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+}
+
+/// Associates a [Conversion](crate::servo::conversion::Conversion) with the unit of its converted value
+pub trait UnitConversion {
+    type Unit;
+}
+
+impl<R, const MICRO_RPM: u32, const SIGN_BIT: u32> UnitConversion
+    for Velocity<R, MICRO_RPM, SIGN_BIT>
+{
+    type Unit = RadiansPerSecond;
+}
+
+impl<R, const MICRO_AMPS: u32, const SIGN_BIT: u32> UnitConversion
+    for Current<R, MICRO_AMPS, SIGN_BIT>
+{
+    type Unit = Amperes;
+}
+
+impl<R, const MILLI_VOLTS: u32> UnitConversion for Voltage<R, MILLI_VOLTS> {
+    type Unit = Volts;
+}
+
+impl UnitConversion for Temperature {
+    type Unit = Celsius;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dynamixel_protocol::mock::{FakeServosV1, MockSerialPort},
+        servo::dynamixel::mx,
+        DynamixelProtocolHandler,
+    };
+
+    #[test]
+    fn explicit_conversions() {
+        assert!((Radians::from_degrees(180.0).value() - std::f64::consts::PI).abs() < 1e-12);
+        assert!((RadiansPerSecond::from_rpm(60.0).to_rpm() - 60.0).abs() < 1e-12);
+        assert_eq!(Amperes::from_milliamps(1500.0), Amperes(1.5));
+        assert_eq!(Celsius(100.0).to_fahrenheit(), 212.0);
+        assert_eq!(Volts(6.0) + Volts(1.5) * 2.0, Volts(9.0));
+        assert_eq!(f64::from(-Radians(1.0)), -1.0);
+    }
+
+    #[test]
+    fn typed_accessors() {
+        let dph = DynamixelProtocolHandler::v1();
+        let mut port = MockSerialPort::new(FakeServosV1::default().with_servo(1, 29, 3));
+
+        dph.write(&mut port, 1, 43, &[42]).unwrap();
+        let temperature: Celsius = mx::read_typed_present_temperature(&dph, &mut port, 1).unwrap();
+        assert_eq!(temperature, Celsius(42.0));
+
+        mx::write_typed_goal_position(&dph, &mut port, 1, Radians(0.0)).unwrap();
+        assert_eq!(
+            mx::read_raw_goal_position(&dph, &mut port, 1).unwrap(),
+            2048
+        );
+        assert_eq!(
+            mx::read_goal_position(&dph, &mut port, 1).unwrap(),
+            mx::read_typed_goal_position(&dph, &mut port, 1)
+                .unwrap()
+                .value()
+        );
+    }
+}